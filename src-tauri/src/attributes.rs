@@ -0,0 +1,593 @@
+//! Generic attribute bags
+//!
+//! Scanners only declare the XML attributes the toolbox understands. An [`AttributeBag`]
+//! keeps every attribute of a record's element and its descendants as flattened
+//! `path → value` pairs, so columns the toolbox doesn't know about can still be shown
+//! and filtered on:
+//!
+//! ```text
+//! <weapon key="ak47.weapon">                 key = "ak47.weapon"
+//!   <specification stab_range="1.5" />      specification.stab_range = "1.5"
+//!   <stance state_key="running" />          stance.state_key = "running"
+//!   <stance state_key="prone" />            stance[1].state_key = "prone"
+//! ```
+//!
+//! The first of several same-named sibling elements keeps the plain path; later ones get
+//! their position as a `[n]` suffix.
+//!
+//! While templates are merged every path keeps the values of each file along the chain
+//! ([`AttributeTrace`]), which `explain_weapon` / `explain_item` report field by field.
+//! Each kind merges like its typed record does ([`MergeRules`]): repeated elements by key
+//! or as whole lists rather than by position, and without the paths the typed record never
+//! inherits (a weapon's `key`).
+//! [`to_xml`] turns a resolved bag back into a standalone element for `export_weapon` /
+//! `export_item`.
+
+use crate::diagnostics::Diagnostic;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Flattened attributes of one record, sorted by path
+pub type AttributeBag = BTreeMap<String, String>;
+
+/// Values of every attribute path along a template chain, nearest file first
+pub type AttributeTrace = BTreeMap<String, Vec<TracedValue>>;
+
+/// An attribute value and the file that declares it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracedValue {
+    pub file: String,
+    pub value: String,
+}
+
+/// Where one resolved field comes from
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldTrace {
+    /// Flattened attribute path (e.g. `specification.magazine_size`)
+    pub field: String,
+    pub value: String,
+    /// File supplying the resolved value
+    pub source_file: String,
+    /// Whether `source_file` is a template rather than the explained file itself
+    pub inherited: bool,
+    /// Values further up the template chain hidden by `value`, nearest first
+    pub overridden: Vec<TracedValue>,
+}
+
+/// Field-by-field inheritance trace of one weapon / carry_item / visual_item
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Explanation {
+    pub source_file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Templates resolved from the file, outermost first
+    pub template_chain: Vec<String>,
+    pub fields: Vec<FieldTrace>,
+    /// Template problems; fields of unresolved templates are missing from `fields`
+    pub errors: Vec<Diagnostic>,
+}
+
+/// Path segments of the open elements below a bag's root, with sibling counters
+struct Frame {
+    path: String,
+    children: HashMap<String, usize>,
+}
+
+/// Attribute bags of every `element` in `content`, in document order.
+///
+/// Elements nested inside a matching element belong to the outer bag. Malformed XML ends
+/// the walk early; the bags collected up to that point are returned.
+pub(crate) fn attribute_bags(content: &str, element: &str) -> Vec<AttributeBag> {
+    let mut reader = Reader::from_str(content);
+    let mut bags: Vec<AttributeBag> = Vec::new();
+    // Open elements of the current bag; empty while outside a matching element
+    let mut frames: Vec<Frame> = Vec::new();
+
+    loop {
+        let (start, is_empty) = match reader.read_event() {
+            Ok(Event::Start(start)) => (start, false),
+            Ok(Event::Empty(start)) => (start, true),
+            Ok(Event::End(_)) => {
+                frames.pop();
+                continue;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+
+        let path = match frames.last_mut() {
+            Some(parent) => {
+                let count = parent.children.entry(name.clone()).or_insert(0);
+                let segment = match *count {
+                    0 => name,
+                    n => format!("{}[{}]", name, n),
+                };
+                *count += 1;
+                if parent.path.is_empty() {
+                    segment
+                } else {
+                    format!("{}.{}", parent.path, segment)
+                }
+            }
+            None if name == element => {
+                bags.push(AttributeBag::new());
+                String::new()
+            }
+            None => continue,
+        };
+
+        if let Some(bag) = bags.last_mut() {
+            insert_attributes(bag, &path, &start);
+        }
+        if !is_empty {
+            frames.push(Frame {
+                path,
+                children: HashMap::new(),
+            });
+        }
+    }
+
+    bags
+}
+
+fn insert_attributes(bag: &mut AttributeBag, path: &str, start: &BytesStart) {
+    for attribute in start.attributes().flatten() {
+        let name = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        let value = attribute
+            .unescape_value()
+            .map(|v| v.into_owned())
+            .unwrap_or_else(|_| String::from_utf8_lossy(&attribute.value).into_owned());
+        let key = if path.is_empty() {
+            name
+        } else {
+            format!("{}.{}", path, name)
+        };
+        bag.insert(key, value);
+    }
+}
+
+/// Traces of every `element` in `content` (see [`attribute_bags`]), declared by `file`
+pub(crate) fn attribute_traces(content: &str, element: &str, file: &Path) -> Vec<AttributeTrace> {
+    let file = file.to_string_lossy();
+    attribute_bags(content, element)
+        .into_iter()
+        .map(|bag| {
+            bag.into_iter()
+                .map(|(path, value)| {
+                    let value = TracedValue {
+                        file: file.to_string(),
+                        value,
+                    };
+                    (path, vec![value])
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// How repeated child elements of a record's root are merged along a template chain.
+///
+/// Elements not named in a merge's list are merged attribute by attribute, which suits the
+/// single ones (`specification`, `inventory`, ...).
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ListMerge {
+    /// Elements with the same value of this attribute are merged; the rest are kept
+    ByKey(&'static str),
+    /// The child's elements replace the parent's when it declares any
+    Replace,
+}
+
+/// How the attribute paths of one record kind merge along a template chain, mirroring the
+/// kind's typed merge
+pub(crate) struct MergeRules {
+    /// Repeated root children, each list under every element name it is spelled as
+    /// (e.g. `nextInChain` / `next_in_chain`)
+    pub lists: &'static [(&'static [&'static str], ListMerge)],
+    /// Paths a record never takes from its template (e.g. the root `key`)
+    pub not_inherited: &'static [&'static str],
+}
+
+impl MergeRules {
+    /// Plain attribute-by-attribute merge
+    pub(crate) const NONE: MergeRules = MergeRules {
+        lists: &[],
+        not_inherited: &[],
+    };
+}
+
+/// A repeated root child taken out of a trace, with paths relative to the element
+struct ListElement {
+    name: String,
+    trace: AttributeTrace,
+}
+
+/// Merge a template's trace into the trace of the file extending it (child values win).
+///
+/// Listed elements are merged as `rules` says and renumbered afterwards.
+pub(crate) fn merge_traces(
+    mut parent: AttributeTrace,
+    child: AttributeTrace,
+    rules: &MergeRules,
+) -> AttributeTrace {
+    for path in rules.not_inherited {
+        parent.remove(*path);
+    }
+    let (parent_lists, parent) = split_lists(parent, rules);
+    let (child_lists, mut merged) = split_lists(child, rules);
+
+    for (path, values) in parent {
+        merged.entry(path).or_default().extend(values);
+    }
+
+    for (((_, merge), parent_elements), child_elements) in
+        rules.lists.iter().zip(parent_lists).zip(child_lists)
+    {
+        let elements = match merge {
+            ListMerge::Replace if child_elements.is_empty() => parent_elements,
+            ListMerge::Replace => child_elements,
+            ListMerge::ByKey(attribute) => merge_by_key(parent_elements, child_elements, attribute),
+        };
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for element in elements {
+            let position = positions.entry(element.name.clone()).or_insert(0);
+            let segment = match *position {
+                0 => element.name,
+                n => format!("{}[{}]", element.name, n),
+            };
+            *position += 1;
+            for (path, values) in element.trace {
+                merged.insert(format!("{}.{}", segment, path), values);
+            }
+        }
+    }
+
+    merged
+}
+
+/// Take the elements listed in `rules` out of `trace`, one list per rule in document order
+/// of each element name
+fn split_lists(
+    trace: AttributeTrace,
+    rules: &MergeRules,
+) -> (Vec<Vec<ListElement>>, AttributeTrace) {
+    let mut lists: Vec<BTreeMap<(usize, usize), ListElement>> =
+        rules.lists.iter().map(|_| BTreeMap::new()).collect();
+    let mut rest = AttributeTrace::new();
+
+    for (path, values) in trace {
+        let listed = path.split_once('.').and_then(|(segment, attribute)| {
+            let (name, position) = parse_segment(segment);
+            let (list, spelling) =
+                rules
+                    .lists
+                    .iter()
+                    .enumerate()
+                    .find_map(|(list, (names, _))| {
+                        Some((list, names.iter().position(|n| *n == name)?))
+                    })?;
+            Some((list, (spelling, position), name, attribute.to_string()))
+        });
+        match listed {
+            Some((list, at, name, attribute)) => {
+                lists[list]
+                    .entry(at)
+                    .or_insert_with(|| ListElement {
+                        name,
+                        trace: AttributeTrace::new(),
+                    })
+                    .trace
+                    .insert(attribute, values);
+            }
+            None => {
+                rest.insert(path, values);
+            }
+        }
+    }
+
+    let lists = lists
+        .into_iter()
+        .map(|elements| elements.into_values().collect())
+        .collect();
+    (lists, rest)
+}
+
+/// Child elements in order, each merged with the parent element of the same key, then the
+/// parent elements the child doesn't override
+fn merge_by_key(
+    parent: Vec<ListElement>,
+    child: Vec<ListElement>,
+    attribute: &str,
+) -> Vec<ListElement> {
+    let key_of = |element: &ListElement| {
+        element
+            .trace
+            .get(attribute)
+            .and_then(|values| values.first())
+            .map(|value| value.value.clone())
+    };
+    let mut parent: Vec<Option<ListElement>> = parent.into_iter().map(Some).collect();
+
+    let mut merged: Vec<ListElement> = child
+        .into_iter()
+        .map(|mut element| {
+            let key = key_of(&element);
+            let inherited = parent
+                .iter_mut()
+                .find(|p| key.is_some() && p.as_ref().is_some_and(|p| key_of(p) == key));
+            if let Some(inherited) = inherited.and_then(Option::take) {
+                element.trace = merge_traces(inherited.trace, element.trace, &MergeRules::NONE);
+            }
+            element
+        })
+        .collect();
+    merged.extend(parent.into_iter().flatten());
+    merged
+}
+
+/// The resolved value of every path
+pub(crate) fn resolved(trace: &AttributeTrace) -> AttributeBag {
+    trace
+        .iter()
+        .filter_map(|(path, values)| Some((path.clone(), values.first()?.value.clone())))
+        .collect()
+}
+
+/// The resolved bag of `file` as a standalone record: the template reference is dropped
+/// because the chain is inlined, and so is a `key` only inherited from a template, which
+/// would clash with the template's own definition
+pub(crate) fn standalone(trace: &AttributeTrace, file: &Path) -> AttributeBag {
+    let file = file.to_string_lossy();
+    let mut bag = resolved(trace);
+    bag.remove("file");
+    if trace
+        .get("key")
+        .and_then(|values| values.first())
+        .is_some_and(|value| value.file != file)
+    {
+        bag.remove("key");
+    }
+    bag
+}
+
+/// Element rebuilt from bag paths; children are keyed by `(name, position)`
+#[derive(Default)]
+struct XmlNode {
+    attributes: Vec<(String, String)>,
+    children: BTreeMap<(String, usize), XmlNode>,
+}
+
+/// Rebuild a `<element>` from a resolved bag, indented by `depth` tabs.
+///
+/// Attributes keep their bag order; child elements are written by name, then position.
+/// Bags only hold attributes, so text content and elements without any attribute in their
+/// subtree are not rebuilt.
+pub(crate) fn to_xml(element: &str, bag: &AttributeBag, depth: usize) -> String {
+    let mut root = XmlNode::default();
+    for (path, value) in bag {
+        let mut segments: Vec<&str> = path.split('.').collect();
+        let Some(attribute) = segments.pop() else {
+            continue;
+        };
+        let mut node = &mut root;
+        for segment in segments {
+            node = node.children.entry(parse_segment(segment)).or_default();
+        }
+        node.attributes.push((attribute.to_string(), value.clone()));
+    }
+
+    let mut xml = String::new();
+    write_node(&mut xml, element, &root, depth);
+    xml
+}
+
+/// `stance[1]` → `("stance", 1)`, `stance` → `("stance", 0)`
+fn parse_segment(segment: &str) -> (String, usize) {
+    segment
+        .strip_suffix(']')
+        .and_then(|s| s.split_once('['))
+        .and_then(|(name, n)| Some((name.to_string(), n.parse().ok()?)))
+        .unwrap_or_else(|| (segment.to_string(), 0))
+}
+
+fn write_node(xml: &mut String, name: &str, node: &XmlNode, depth: usize) {
+    let indent = "\t".repeat(depth);
+    xml.push_str(&indent);
+    xml.push('<');
+    xml.push_str(name);
+    for (attribute, value) in &node.attributes {
+        xml.push_str(&format!(
+            " {}=\"{}\"",
+            attribute,
+            quick_xml::escape::escape(value.as_str())
+        ));
+    }
+    if node.children.is_empty() {
+        xml.push_str(" />\n");
+        return;
+    }
+    xml.push_str(">\n");
+    for ((child_name, _), child) in &node.children {
+        write_node(xml, child_name, child, depth + 1);
+    }
+    xml.push_str(&format!("{}</{}>\n", indent, name));
+}
+
+impl Explanation {
+    pub(crate) fn new(
+        file: &Path,
+        key: Option<String>,
+        template_chain: &[impl AsRef<Path>],
+        trace: AttributeTrace,
+        errors: Vec<Diagnostic>,
+    ) -> Self {
+        let source_file = file.to_string_lossy().to_string();
+        let fields = trace
+            .into_iter()
+            .filter_map(|(field, values)| {
+                let mut values = values.into_iter();
+                let winner = values.next()?;
+                Some(FieldTrace {
+                    field,
+                    inherited: winner.file != source_file,
+                    value: winner.value,
+                    source_file: winner.file,
+                    overridden: values.collect(),
+                })
+            })
+            .collect();
+
+        Self {
+            key,
+            template_chain: template_chain
+                .iter()
+                .map(|p| p.as_ref().to_string_lossy().to_string())
+                .collect(),
+            fields,
+            errors,
+            source_file,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flattens_nested_and_repeated_elements() {
+        let xml = r#"<weapon key="a.weapon">
+            <specification stab_range="1.5" name="A" />
+            <stance state_key="running" accuracy="0.3" />
+            <stance state_key="prone" accuracy="0.9" />
+            <projectile file="b.projectile"><result kill_probability="2" /></projectile>
+        </weapon>"#;
+
+        let bags = attribute_bags(xml, "weapon");
+        assert_eq!(bags.len(), 1);
+        let bag = &bags[0];
+        assert_eq!(bag["key"], "a.weapon");
+        assert_eq!(bag["specification.stab_range"], "1.5");
+        assert_eq!(bag["stance.state_key"], "running");
+        assert_eq!(bag["stance[1].accuracy"], "0.9");
+        assert_eq!(bag["projectile.result.kill_probability"], "2");
+    }
+
+    #[test]
+    fn one_bag_per_element() {
+        let xml = r#"<carry_items>
+            <carry_item key="a" name="A"><inventory price="1" /></carry_item>
+            <carry_item key="b" />
+        </carry_items>"#;
+
+        let bags = attribute_bags(xml, "carry_item");
+        assert_eq!(bags.len(), 2);
+        assert_eq!(bags[0]["inventory.price"], "1");
+        assert_eq!(bags[1]["key"], "b");
+    }
+
+    #[test]
+    fn rebuilds_xml_from_bag() {
+        let xml = r#"<weapon key="a.weapon">
+            <specification name="A &amp; B" />
+            <stance state_key="running" />
+            <stance state_key="prone" />
+            <projectile file="b.projectile"><result kill_probability="2" /></projectile>
+        </weapon>"#;
+        let bag = attribute_bags(xml, "weapon").remove(0);
+
+        let rebuilt = to_xml("weapon", &bag, 0);
+        assert_eq!(attribute_bags(&rebuilt, "weapon").remove(0), bag);
+        assert!(rebuilt.contains(r#"name="A &amp; B""#));
+    }
+
+    #[test]
+    fn rebuilt_xml_keeps_attributes_but_not_text() {
+        let xml = r#"<carry_item key="a">
+            <description lang="en">Light vest</description>
+            <sound />
+            <inventory price="1" />
+        </carry_item>"#;
+        let bag = attribute_bags(xml, "carry_item").remove(0);
+
+        let rebuilt = to_xml("carry_item", &bag, 0);
+        assert_eq!(attribute_bags(&rebuilt, "carry_item").remove(0), bag);
+        assert!(rebuilt.contains(r#"<description lang="en" />"#));
+        assert!(!rebuilt.contains("Light vest"));
+        assert!(!rebuilt.contains("<sound"));
+    }
+
+    #[test]
+    fn child_values_win_and_parents_are_traced() {
+        let parent = r#"<weapon><specification name="Parent" magazine_size="30" /></weapon>"#;
+        let child = r#"<weapon file="parent.weapon"><specification name="Child" /></weapon>"#;
+        let parent = attribute_traces(parent, "weapon", Path::new("parent.weapon")).remove(0);
+        let child = attribute_traces(child, "weapon", Path::new("child.weapon")).remove(0);
+
+        let merged = merge_traces(parent, child, &MergeRules::NONE);
+        let bag = resolved(&merged);
+        assert_eq!(bag["specification.name"], "Child");
+        assert_eq!(bag["specification.magazine_size"], "30");
+
+        let explanation = Explanation::new(
+            Path::new("child.weapon"),
+            None,
+            &["parent.weapon"],
+            merged,
+            Vec::new(),
+        );
+        let name = explanation
+            .fields
+            .iter()
+            .find(|f| f.field == "specification.name")
+            .unwrap();
+        assert!(!name.inherited);
+        assert_eq!(name.overridden[0].file, "parent.weapon");
+        let magazine = explanation
+            .fields
+            .iter()
+            .find(|f| f.field == "specification.magazine_size")
+            .unwrap();
+        assert!(magazine.inherited);
+        assert_eq!(magazine.source_file, "parent.weapon");
+    }
+
+    #[test]
+    fn repeated_elements_merge_by_key_or_are_replaced() {
+        let parent = r#"<weapon>
+            <stance state_key="running" accuracy="0.3" />
+            <stance state_key="prone" accuracy="0.9" />
+            <modifier class="speed" value="0.1" />
+            <modifier class="detectability" value="0.2" />
+        </weapon>"#;
+        let child = r#"<weapon file="parent.weapon">
+            <stance state_key="prone" accuracy="1.0" />
+            <modifier class="speed" value="0.5" />
+        </weapon>"#;
+        let parent = attribute_traces(parent, "weapon", Path::new("parent.weapon")).remove(0);
+        let child = attribute_traces(child, "weapon", Path::new("child.weapon")).remove(0);
+        let rules = MergeRules {
+            lists: &[
+                (&["stance"], ListMerge::ByKey("state_key")),
+                (&["modifier"], ListMerge::Replace),
+            ],
+            not_inherited: &[],
+        };
+        let merged = merge_traces(parent, child, &rules);
+        assert_eq!(merged["stance.accuracy"][1].file, "parent.weapon");
+        let bag = resolved(&merged);
+        assert_eq!(bag["stance.state_key"], "prone");
+        assert_eq!(bag["stance.accuracy"], "1.0");
+        assert_eq!(bag["stance[1].state_key"], "running");
+        assert_eq!(bag["stance[1].accuracy"], "0.3");
+        assert!(!bag.contains_key("stance[2].state_key"));
+        assert_eq!(bag["modifier.value"], "0.5");
+        assert!(!bag.contains_key("modifier[1].class"));
+    }
+}
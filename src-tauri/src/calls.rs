@@ -0,0 +1,447 @@
+//! Radio calls scanner module
+//!
+//! Scans RWR packages for `.call` files (artillery, airstrikes, supply drops), resolves their
+//! `file="..."` template inheritance and returns price, cooldown, rank requirement and what
+//! the call spawns.
+
+use crate::diagnostics::{parse_recovering, parse_xml, read_file, Diagnostic};
+use crate::packages::{KeyDefinition, Provenance};
+use crate::scan_cache::{Parsed, ScanCache};
+use crate::scan_jobs::ScanJobs;
+use crate::scanner::{self, PackageScanner, ScanOptions, ScanSummary};
+use crate::templates::{inherit, Template};
+use crate::utils::{package_name_of, relative_file_path, resolve_texture_path};
+use crate::watch::{self, WatchRegistry};
+use crate::ScanEvent;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// Radio call with its template chain resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Call {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    /// Seconds before the call can be used again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown: Option<f64>,
+    /// Minimum rank XP, from `<capacity source="rank" source_value="...">`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank_requirement: Option<f64>,
+    /// Projectile files spawned by the call: direct spawns first, then each `<round>`'s in order
+    pub projectiles: Vec<String>,
+    /// Vehicle keys/files spawned by the call: direct spawns first, then each `<round>`'s in order
+    pub vehicles: Vec<String>,
+    /// Icon filename from <hud_icon filename="..."/> element
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hud_icon: Option<String>,
+    /// Absolute path of `hud_icon`, resolved like `get_texture_path`; None if the file is missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hud_icon_path: Option<String>,
+    pub file_path: String,
+    pub source_file: String,
+    pub source_directory: String,
+    pub package_name: String,
+    /// Effective scans only: the package this definition was taken from and what it overrides
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provenance: Option<Provenance>,
+    /// The file is malformed XML; these fields were recovered from a repaired copy and
+    /// anything after the broken markup may be missing
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub partially_parsed: bool,
+}
+
+/// Result from call scanning
+#[derive(Debug, Serialize)]
+pub struct CallScanResult {
+    pub calls: Vec<Call>,
+    #[serde(flatten)]
+    pub summary: ScanSummary,
+}
+
+/// Raw call XML structure
+#[derive(Debug, Deserialize, Default)]
+struct RawCall {
+    #[serde(rename = "@file", default)]
+    template_file: Option<String>,
+    #[serde(rename = "@key", default)]
+    key: Option<String>,
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "@cooldown", default)]
+    cooldown: Option<f64>,
+    #[serde(rename = "inventory", default)]
+    inventory: Option<RawInventory>,
+    #[serde(rename = "capacity", default)]
+    capacity: Option<RawCapacity>,
+    #[serde(rename = "hud_icon", default)]
+    hud_icon: Option<RawHudIcon>,
+    /// Spawns may be listed directly or grouped in `<round>` elements
+    #[serde(rename = "round", default)]
+    rounds: Vec<RawSpawns>,
+    #[serde(rename = "projectile", default)]
+    projectiles: Vec<RawSpawnRef>,
+    #[serde(rename = "vehicle", default)]
+    vehicles: Vec<RawSpawnRef>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawSpawns {
+    #[serde(rename = "projectile", default)]
+    projectiles: Vec<RawSpawnRef>,
+    #[serde(rename = "vehicle", default)]
+    vehicles: Vec<RawSpawnRef>,
+}
+
+/// `<projectile file="..."/>` / `<vehicle key="..."/>`
+#[derive(Debug, Deserialize, Default)]
+struct RawSpawnRef {
+    #[serde(rename = "@file", default)]
+    file: Option<String>,
+    #[serde(rename = "@key", default)]
+    key: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawInventory {
+    #[serde(rename = "@price", default)]
+    price: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawCapacity {
+    #[serde(rename = "@source", default)]
+    source: Option<String>,
+    #[serde(rename = "@source_value", default)]
+    source_value: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawHudIcon {
+    #[serde(rename = "@filename", default)]
+    filename: Option<String>,
+}
+
+/// Scan all call files from a game/workshop directory.
+///
+/// The scan runs in the background; the returned job id can be passed to `cancel_scan`.
+#[tauri::command]
+pub async fn scan_calls(
+    game_path: String,
+    directory: Option<String>,
+    jobs: State<'_, ScanJobs>,
+    on_event: Channel<ScanEvent<Call>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    scanner::spawn_scan::<CallScanner>(&jobs, source_directory, on_event)
+}
+
+/// [`scan_calls`] in one payload, for WebViews that drop `Channel` messages
+/// (see `scan_weapons_collect`).
+#[tauri::command]
+pub async fn scan_calls_collect(
+    game_path: String,
+    directory: Option<String>,
+    effective: Option<bool>,
+) -> Result<CallScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        effective: effective.unwrap_or(false),
+        ..Default::default()
+    };
+    let scan = scanner::collect_scan::<CallScanner>(&source_directory, options)?;
+    Ok(CallScanResult {
+        calls: scan.records,
+        summary: scan.summary,
+    })
+}
+
+/// Watch the packages roots of a calls scan and stream add/update/remove deltas.
+#[tauri::command]
+pub async fn watch_calls(
+    game_path: String,
+    directory: Option<String>,
+    registry: State<'_, WatchRegistry>,
+    on_event: Channel<ScanEvent<Call>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    watch::start::<CallScanner>(&registry, source_directory, on_event)
+}
+
+/// Call scanner for the shared scanning framework
+pub(crate) struct CallScanner;
+
+impl PackageScanner for CallScanner {
+    type Record = Call;
+
+    const KIND: &'static str = "calls";
+
+    fn matches(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "call")
+    }
+
+    fn parse_cached(
+        cache: &ScanCache<Call>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Call>, Diagnostic> {
+        parse_call_cached(cache, file, packages_root, index, source_directory)
+    }
+
+    fn record_id(record: &Call) -> &str {
+        &record.id
+    }
+
+    fn key_definition(record: &Call) -> Option<KeyDefinition> {
+        Some(KeyDefinition {
+            key: record.key.clone()?,
+            id: record.id.clone(),
+            package_name: record.package_name.clone(),
+            file_path: record.file_path.clone(),
+            source_file: record.source_file.clone(),
+        })
+    }
+
+    fn set_provenance(record: &mut Call, provenance: Provenance) {
+        record.provenance = Some(provenance);
+    }
+}
+
+/// Parse a call file through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits. The hud icon is
+/// resolved here too, outside the cache, so an icon added after the call file still shows.
+fn parse_call_cached(
+    cache: &ScanCache<Call>,
+    path: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<Call>, Diagnostic> {
+    let id = format!("{}_{}", path.to_string_lossy(), index);
+
+    let mut parsed = cache.get_or_parse(path, |dependencies, diagnostics| {
+        parse_call_file(
+            path,
+            packages_root,
+            id.clone(),
+            source_directory,
+            dependencies,
+            diagnostics,
+        )
+        .map(|call| vec![call])
+    })?;
+
+    for call in &mut parsed.records {
+        call.id = id.clone();
+        call.source_directory = source_directory.to_string();
+        call.hud_icon_path = call
+            .hud_icon
+            .as_deref()
+            .and_then(|icon| resolve_texture_path(path, icon).ok())
+            .map(|p| p.to_string_lossy().to_string());
+    }
+    Ok(parsed)
+}
+
+/// Parse a single call file with template resolution.
+/// Every template file consulted is appended to `dependencies`; template failures are
+/// reported as warnings in `diagnostics`.
+fn parse_call_file(
+    call_path: &Path,
+    packages_root: &Path,
+    id: String,
+    source_directory: &str,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Call, Diagnostic> {
+    let content = read_file(call_path)?;
+    let (raw, mut partially_parsed) = parse_recovering(&content, diagnostics, |content| {
+        parse_xml::<RawCall>(content, call_path)
+    })?;
+    let (raw, template_repaired) = inherit(raw, call_path, dependencies, diagnostics);
+    partially_parsed |= template_repaired;
+
+    let mut projectiles: Vec<String> = Vec::new();
+    let mut vehicles: Vec<String> = Vec::new();
+    let direct = RawSpawns {
+        projectiles: raw.projectiles,
+        vehicles: raw.vehicles,
+    };
+    for spawns in std::iter::once(direct).chain(raw.rounds) {
+        projectiles.extend(spawns.projectiles.into_iter().filter_map(spawn_name));
+        vehicles.extend(spawns.vehicles.into_iter().filter_map(spawn_name));
+    }
+
+    let rank_requirement = raw
+        .capacity
+        .filter(|c| c.source.as_deref() == Some("rank"))
+        .and_then(|c| c.source_value);
+
+    let hud_icon = raw
+        .hud_icon
+        .and_then(|h| h.filename)
+        .filter(|s| !s.is_empty());
+
+    let file_path = relative_file_path(call_path, packages_root);
+
+    let key = raw.key.filter(|k| !k.is_empty()).or_else(|| {
+        call_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_string())
+    });
+
+    Ok(Call {
+        id,
+        name: raw.name.unwrap_or_default(),
+        key,
+        price: raw.inventory.and_then(|i| i.price),
+        cooldown: raw.cooldown,
+        rank_requirement,
+        projectiles,
+        vehicles,
+        hud_icon,
+        hud_icon_path: None,
+        file_path,
+        source_file: call_path.to_string_lossy().to_string(),
+        source_directory: source_directory.to_string(),
+        package_name: package_name_of(call_path, packages_root),
+        provenance: None,
+        partially_parsed,
+    })
+}
+
+/// Name a spawned projectile/vehicle by its key, else its file
+fn spawn_name(spawn: RawSpawnRef) -> Option<String> {
+    spawn.key.or(spawn.file).filter(|s| !s.is_empty())
+}
+
+impl Template for RawCall {
+    const VANILLA_DIR: &'static str = "calls";
+    const LABEL: &'static str = "call";
+
+    fn parse(content: &str, path: &Path) -> Result<Self, Diagnostic> {
+        parse_xml(content, path)
+    }
+
+    fn template_file(&self) -> Option<String> {
+        self.template_file.clone()
+    }
+
+    fn merge(parent: Self, child: Self) -> Self {
+        merge_call_attributes(parent, child)
+    }
+}
+
+/// Child values win; missing ones are taken from `parent`.
+/// Spawn lists are replaced as a whole when the child declares any.
+fn merge_call_attributes(parent: RawCall, mut child: RawCall) -> RawCall {
+    child.name = child.name.or(parent.name);
+    child.cooldown = child.cooldown.or(parent.cooldown);
+    child.inventory = child.inventory.or(parent.inventory);
+    child.capacity = child.capacity.or(parent.capacity);
+    child.hud_icon = child.hud_icon.or(parent.hud_icon);
+
+    let has_spawns = !child.projectiles.is_empty()
+        || !child.vehicles.is_empty()
+        || child
+            .rounds
+            .iter()
+            .any(|r| !r.projectiles.is_empty() || !r.vehicles.is_empty());
+    if !has_spawns {
+        child.rounds = parent.rounds;
+        child.projectiles = parent.projectiles;
+        child.vehicles = parent.vehicles;
+    }
+
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inherits_template_and_collects_spawns() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let calls = packages.join("vanilla/calls");
+        std::fs::create_dir_all(&calls).unwrap();
+        std::fs::create_dir_all(packages.join("vanilla/textures")).unwrap();
+        std::fs::write(packages.join("vanilla/textures/hud_arty.png"), "").unwrap();
+        std::fs::write(
+            calls.join("base.call"),
+            r#"<call name="Artillery" cooldown="60.0">
+                <inventory price="100.0"/>
+                <capacity source="rank" source_value="1.2"/>
+                <hud_icon filename="hud_arty.png"/>
+                <round><projectile file="shell.projectile"/></round>
+                <round><projectile file="shell.projectile"/><vehicle key="drone.vehicle"/></round>
+            </call>"#,
+        )
+        .unwrap();
+        let heavy = calls.join("heavy.call");
+        std::fs::write(
+            &heavy,
+            r#"<call file="base.call" name="Heavy artillery"><inventory price="250.0"/></call>"#,
+        )
+        .unwrap();
+
+        let source_directory = packages.to_string_lossy().to_string();
+        let cache = ScanCache::load(CallScanner::KIND, &source_directory);
+        let call = parse_call_cached(&cache, &heavy, packages, 0, &source_directory)
+            .unwrap()
+            .records
+            .remove(0);
+
+        assert_eq!(call.name, "Heavy artillery");
+        assert_eq!(call.key.as_deref(), Some("heavy.call"));
+        assert_eq!(call.price, Some(250.0));
+        assert_eq!(call.cooldown, Some(60.0));
+        assert_eq!(call.rank_requirement, Some(1.2));
+        assert_eq!(call.projectiles, ["shell.projectile", "shell.projectile"]);
+        assert_eq!(call.vehicles, ["drone.vehicle"]);
+        assert!(call.hud_icon_path.is_some());
+    }
+
+    #[test]
+    fn collects_spawns_interleaved_with_rounds() {
+        let packages = tempfile::tempdir().unwrap();
+        let calls = packages.path().join("vanilla/calls");
+        std::fs::create_dir_all(&calls).unwrap();
+        let path = calls.join("mixed.call");
+        std::fs::write(
+            &path,
+            r#"<call name="Mixed">
+                <round><projectile file="a.projectile"/></round>
+                <projectile file="b.projectile"/>
+                <round><vehicle key="drone.vehicle"/><projectile file="c.projectile"/></round>
+                <vehicle key="jeep.vehicle"/>
+            </call>"#,
+        )
+        .unwrap();
+
+        let source_directory = packages.path().to_string_lossy().to_string();
+        let cache = ScanCache::load(CallScanner::KIND, &source_directory);
+        let call = parse_call_cached(&cache, &path, packages.path(), 0, &source_directory)
+            .unwrap()
+            .records
+            .remove(0);
+
+        assert!(!call.partially_parsed);
+        // Direct spawns come first, then each round's
+        assert_eq!(
+            call.projectiles,
+            ["b.projectile", "a.projectile", "c.projectile"]
+        );
+        assert_eq!(call.vehicles, ["jeep.vehicle", "drone.vehicle"]);
+    }
+}
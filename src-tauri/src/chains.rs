@@ -0,0 +1,285 @@
+//! Weapon chain graph
+//!
+//! `<next_in_chain key="..."/>` links a weapon to the one switching modes turns it into
+//! (fire modes, attachments, ...). Players cycle through a chain, so a healthy group is a
+//! closed loop: A → B → A. [`build_chain_graph`] resolves every link of the effective
+//! weapons to a record, reports links to keys no weapon defines and groups the linked
+//! weapons with the cycles found in each group.
+
+use crate::scanner;
+use crate::weapons::{Weapon, WeaponScanner};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+/// All chain groups of a scan
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainGraph {
+    /// Sorted by their first member's key
+    pub groups: Vec<ChainGroup>,
+    /// Links to keys no scanned weapon defines
+    pub dangling: Vec<DanglingLink>,
+    /// Weapon key → index into `groups`
+    pub group_of: BTreeMap<String, usize>,
+}
+
+/// Weapons connected by chain links
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainGroup {
+    /// Sorted by key
+    pub members: Vec<ChainMember>,
+    pub links: Vec<ChainLink>,
+    /// Sets of members that can reach each other, each sorted by key
+    pub cycles: Vec<Vec<String>>,
+    /// Whether switching can get from every member to every other one and back
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainMember {
+    pub key: String,
+    pub weapon_id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainLink {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DanglingLink {
+    pub from: String,
+    pub weapon_id: String,
+    /// Key as written in `<next_in_chain>`
+    pub to: String,
+}
+
+/// Resolve the chain links of the effective weapons of a scan.
+///
+/// `weapons` takes the rows of an effective scan the frontend already holds; without them
+/// the packages are scanned.
+#[tauri::command]
+pub async fn weapon_chains(
+    game_path: String,
+    directory: Option<String>,
+    weapons: Option<Vec<Weapon>>,
+) -> Result<ChainGraph, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let weapons = scanner::records_or_scan::<WeaponScanner>(weapons, &source_directory)?;
+    Ok(build_chain_graph(&weapons))
+}
+
+/// The chain group containing weapon `key`, if it links to or is linked from another weapon.
+#[tauri::command]
+pub async fn weapon_chain(
+    game_path: String,
+    directory: Option<String>,
+    key: String,
+    weapons: Option<Vec<Weapon>>,
+) -> Result<Option<ChainGroup>, String> {
+    let mut graph = weapon_chains(game_path, directory, weapons).await?;
+    Ok(graph
+        .group_of
+        .get(&key)
+        .copied()
+        .map(|index| graph.groups.swap_remove(index)))
+}
+
+/// Key a weapon is listed under: its `key`, or its file name for unkeyed files
+fn weapon_key(weapon: &Weapon) -> String {
+    weapon.key.clone().unwrap_or_else(|| file_name(weapon))
+}
+
+fn file_name(weapon: &Weapon) -> String {
+    Path::new(&weapon.file_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Build the chain graph of `weapons`, which should hold one record per key (an
+/// effective scan). Links match weapon keys first, then file names.
+pub(crate) fn build_chain_graph(weapons: &[Weapon]) -> ChainGraph {
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for (index, weapon) in weapons.iter().enumerate() {
+        by_key.entry(file_name(weapon)).or_insert(index);
+    }
+    for (index, weapon) in weapons.iter().enumerate() {
+        by_key.insert(weapon_key(weapon), index);
+    }
+
+    let mut edges: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    let mut dangling = Vec::new();
+    for (index, weapon) in weapons.iter().enumerate() {
+        for target in &weapon.chain_variants {
+            match by_key.get(target) {
+                Some(&to) => {
+                    edges.entry(index).or_default().insert(to);
+                }
+                None => dangling.push(DanglingLink {
+                    from: weapon_key(weapon),
+                    weapon_id: weapon.id.clone(),
+                    to: target.clone(),
+                }),
+            }
+        }
+    }
+
+    // Union-find over the links to get the groups
+    let mut parent: Vec<usize> = (0..weapons.len()).collect();
+    fn root(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+    for (&from, targets) in &edges {
+        for &to in targets {
+            let (a, b) = (root(&mut parent, from), root(&mut parent, to));
+            parent[a] = b;
+        }
+    }
+    let mut components: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    for (&from, targets) in &edges {
+        for node in std::iter::once(from).chain(targets.iter().copied()) {
+            let group = root(&mut parent, node);
+            components.entry(group).or_default().insert(node);
+        }
+    }
+
+    let mut groups: Vec<ChainGroup> = components
+        .into_values()
+        .map(|nodes| chain_group(weapons, &nodes, &edges))
+        .collect();
+    groups.sort_by(|a, b| a.members[0].key.cmp(&b.members[0].key));
+
+    let group_of = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(index, group)| group.members.iter().map(move |m| (m.key.clone(), index)))
+        .collect();
+
+    ChainGraph {
+        groups,
+        dangling,
+        group_of,
+    }
+}
+
+fn chain_group(
+    weapons: &[Weapon],
+    nodes: &BTreeSet<usize>,
+    edges: &BTreeMap<usize, BTreeSet<usize>>,
+) -> ChainGroup {
+    // Nodes reachable from each node through at least one link
+    let reach: BTreeMap<usize, BTreeSet<usize>> = nodes
+        .iter()
+        .map(|&node| {
+            let mut seen = BTreeSet::new();
+            let mut stack: Vec<usize> = edges.get(&node).into_iter().flatten().copied().collect();
+            while let Some(next) = stack.pop() {
+                if seen.insert(next) {
+                    stack.extend(edges.get(&next).into_iter().flatten().copied());
+                }
+            }
+            (node, seen)
+        })
+        .collect();
+
+    let mut cycles: Vec<Vec<String>> = Vec::new();
+    let mut in_cycle: BTreeSet<usize> = BTreeSet::new();
+    for &node in nodes {
+        if in_cycle.contains(&node) || !reach[&node].contains(&node) {
+            continue;
+        }
+        let cycle: BTreeSet<usize> = reach[&node]
+            .iter()
+            .copied()
+            .filter(|other| reach[other].contains(&node))
+            .collect();
+        let mut keys: Vec<String> = cycle.iter().map(|&i| weapon_key(&weapons[i])).collect();
+        keys.sort();
+        cycles.push(keys);
+        in_cycle.extend(cycle);
+    }
+
+    let mut members: Vec<ChainMember> = nodes
+        .iter()
+        .map(|&i| ChainMember {
+            key: weapon_key(&weapons[i]),
+            weapon_id: weapons[i].id.clone(),
+            name: weapons[i].name.clone(),
+        })
+        .collect();
+    members.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let mut links: Vec<ChainLink> = nodes
+        .iter()
+        .flat_map(|&from| {
+            edges
+                .get(&from)
+                .into_iter()
+                .flatten()
+                .map(move |&to| ChainLink {
+                    from: weapon_key(&weapons[from]),
+                    to: weapon_key(&weapons[to]),
+                })
+        })
+        .collect();
+    links.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+
+    ChainGroup {
+        closed: cycles.len() == 1 && cycles[0].len() == members.len(),
+        members,
+        links,
+        cycles,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weapons::test_weapon;
+
+    fn weapon(key: &str, chain: &[&str]) -> Weapon {
+        Weapon {
+            chain_variants: chain.iter().map(|k| k.to_string()).collect(),
+            ..test_weapon(key)
+        }
+    }
+
+    #[test]
+    fn groups_cycles_and_dangling_links() {
+        let weapons = [
+            weapon("m16.weapon", &["m16_gl.weapon"]),
+            weapon("m16_gl.weapon", &["m16.weapon"]),
+            weapon("ak47.weapon", &["ak47_gl.weapon"]),
+            weapon("ak47_gl.weapon", &[]),
+            weapon("g3.weapon", &["missing.weapon"]),
+        ];
+
+        let graph = build_chain_graph(&weapons);
+        assert_eq!(graph.groups.len(), 2);
+
+        let ak = &graph.groups[graph.group_of["ak47_gl.weapon"]];
+        assert_eq!(ak.members.len(), 2);
+        assert!(ak.cycles.is_empty());
+        assert!(!ak.closed);
+
+        let m16 = &graph.groups[graph.group_of["m16.weapon"]];
+        assert_eq!(m16.cycles, vec![vec!["m16.weapon", "m16_gl.weapon"]]);
+        assert!(m16.closed);
+
+        assert_eq!(graph.dangling.len(), 1);
+        assert_eq!(graph.dangling[0].to, "missing.weapon");
+        assert!(!graph.group_of.contains_key("g3.weapon"));
+    }
+}
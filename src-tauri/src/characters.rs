@@ -0,0 +1,419 @@
+//! Characters scanner module
+//!
+//! Scans RWR packages for `.character` files (the soldier definitions factions refer to with
+//! `<character filename="...">`) and returns their models, voice sets, skins and attribute
+//! modifiers. [`character_links`] links every character to the faction soldier groups using
+//! it and to the visual_items its skins refer to.
+
+use crate::diagnostics::{parse_recovering, parse_xml, read_file, Diagnostic};
+use crate::factions::{Faction, FactionScanner, RawFileRef};
+use crate::items::{Item, ItemScanner};
+use crate::packages::{KeyDefinition, Provenance};
+use crate::scan_cache::{Parsed, ScanCache};
+use crate::scan_jobs::ScanJobs;
+use crate::scanner::{self, PackageScanner, ScanOptions, ScanSummary};
+use crate::utils::{package_name_of, relative_file_path};
+use crate::ScanEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// Soldier character definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Character {
+    pub id: String,
+    /// Character file name (e.g. "default.character"), as factions refer to it
+    pub key: String,
+    pub name: String,
+    /// Model files (`<model filename="...">`)
+    pub models: Vec<String>,
+    /// Voice sets (`<voice file="...">`)
+    pub voices: Vec<String>,
+    /// Skin visual_items (`<skin file="...">`)
+    pub skins: Vec<String>,
+    pub modifiers: Vec<CharacterModifier>,
+    pub file_path: String,
+    pub source_file: String,
+    pub source_directory: String,
+    pub package_name: String,
+    /// Effective scans only: the package this definition was taken from and what it overrides
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provenance: Option<Provenance>,
+    /// The file is malformed XML; these fields were recovered from a repaired copy and
+    /// anything after the broken markup may be missing
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub partially_parsed: bool,
+}
+
+/// Attribute modifier (`<modifier class="..." value="..."/>`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterModifier {
+    pub class: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+}
+
+/// Result from character scanning
+#[derive(Debug, Serialize)]
+pub struct CharacterScanResult {
+    pub characters: Vec<Character>,
+    #[serde(flatten)]
+    pub summary: ScanSummary,
+}
+
+/// One character file name with everything referring to it
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterLink {
+    pub key: String,
+    /// Ids of the effective character records; empty if factions refer to a missing file
+    pub character_ids: Vec<String>,
+    pub used_by: Vec<SoldierRef>,
+    /// Skins of the character that no visual_item provides
+    pub missing_skins: Vec<String>,
+    /// Ids of the visual_items providing the character's skins
+    pub skin_item_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoldierRef {
+    pub faction_id: String,
+    pub faction_name: String,
+    pub soldier: String,
+}
+
+/// Raw character XML structure
+#[derive(Debug, Deserialize, Default)]
+struct RawCharacter {
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "model", default)]
+    models: Vec<RawFileRef>,
+    #[serde(rename = "voice", default)]
+    voices: Vec<RawFileRef>,
+    #[serde(rename = "skin", default)]
+    skins: Vec<RawFileRef>,
+    #[serde(rename = "modifier", default)]
+    modifiers: Vec<RawModifier>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawModifier {
+    #[serde(rename = "@class", default)]
+    class: Option<String>,
+    #[serde(rename = "@value", default)]
+    value: Option<f64>,
+}
+
+/// Scan all character files from a game/workshop directory.
+///
+/// The scan runs in the background; the returned job id can be passed to `cancel_scan`.
+#[tauri::command]
+pub async fn scan_characters(
+    game_path: String,
+    directory: Option<String>,
+    jobs: State<'_, ScanJobs>,
+    on_event: Channel<ScanEvent<Character>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    scanner::spawn_scan::<CharacterScanner>(&jobs, source_directory, on_event)
+}
+
+/// [`scan_characters`] in one payload, for WebViews that drop `Channel` messages
+/// (see `scan_weapons_collect`).
+#[tauri::command]
+pub async fn scan_characters_collect(
+    game_path: String,
+    directory: Option<String>,
+    effective: Option<bool>,
+) -> Result<CharacterScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        effective: effective.unwrap_or(false),
+        ..Default::default()
+    };
+    let scan = scanner::collect_scan::<CharacterScanner>(&source_directory, options)?;
+    Ok(CharacterScanResult {
+        characters: scan.records,
+        summary: scan.summary,
+    })
+}
+
+/// Link characters to the faction soldier groups using them and to their skin visual_items.
+///
+/// Works on the effective records of each scan and returns one entry per character file
+/// name that is either defined or referenced by a faction, sorted by key. Records the
+/// frontend already holds can be passed in; only the kinds left out are scanned.
+#[tauri::command]
+pub async fn character_links(
+    game_path: String,
+    directory: Option<String>,
+    characters: Option<Vec<Character>>,
+    factions: Option<Vec<Faction>>,
+    items: Option<Vec<Item>>,
+) -> Result<Vec<CharacterLink>, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let characters = scanner::records_or_scan::<CharacterScanner>(characters, &source_directory)?;
+    let factions = scanner::records_or_scan::<FactionScanner>(factions, &source_directory)?;
+    let items = scanner::records_or_scan::<ItemScanner>(items, &source_directory)?;
+
+    let mut links: BTreeMap<String, CharacterLink> = BTreeMap::new();
+
+    for character in &characters {
+        let entry = links
+            .entry(character.key.clone())
+            .or_insert_with(|| empty_link(&character.key));
+        entry.character_ids.push(character.id.clone());
+        for skin in &character.skins {
+            let providers: Vec<&str> = items
+                .iter()
+                .filter(|i| {
+                    i.item_type == "visual_item"
+                        && Path::new(&i.file_path)
+                            .file_name()
+                            .is_some_and(|name| name == skin.as_str())
+                })
+                .map(|i| i.id.as_str())
+                .collect();
+            if providers.is_empty() {
+                entry.missing_skins.push(skin.clone());
+            }
+            entry
+                .skin_item_ids
+                .extend(providers.into_iter().map(str::to_string));
+        }
+    }
+
+    for faction in &factions {
+        for soldier in &faction.soldiers {
+            let Some(character) = &soldier.character else {
+                continue;
+            };
+            links
+                .entry(character.clone())
+                .or_insert_with(|| empty_link(character))
+                .used_by
+                .push(SoldierRef {
+                    faction_id: faction.id.clone(),
+                    faction_name: faction.name.clone(),
+                    soldier: soldier.name.clone(),
+                });
+        }
+    }
+
+    Ok(links.into_values().collect())
+}
+
+fn empty_link(key: &str) -> CharacterLink {
+    CharacterLink {
+        key: key.to_string(),
+        character_ids: Vec::new(),
+        used_by: Vec::new(),
+        missing_skins: Vec::new(),
+        skin_item_ids: Vec::new(),
+    }
+}
+
+/// Character scanner for the shared scanning framework
+pub(crate) struct CharacterScanner;
+
+impl PackageScanner for CharacterScanner {
+    type Record = Character;
+
+    const KIND: &'static str = "characters";
+
+    fn matches(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "character")
+    }
+
+    fn parse_cached(
+        cache: &ScanCache<Character>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Character>, Diagnostic> {
+        parse_character_cached(cache, file, packages_root, index, source_directory)
+    }
+
+    fn record_id(record: &Character) -> &str {
+        &record.id
+    }
+
+    fn key_definition(record: &Character) -> Option<KeyDefinition> {
+        Some(KeyDefinition {
+            key: record.key.clone(),
+            id: record.id.clone(),
+            package_name: record.package_name.clone(),
+            file_path: record.file_path.clone(),
+            source_file: record.source_file.clone(),
+        })
+    }
+
+    fn set_provenance(record: &mut Character, provenance: Provenance) {
+        record.provenance = Some(provenance);
+    }
+}
+
+/// Parse a character file through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits.
+fn parse_character_cached(
+    cache: &ScanCache<Character>,
+    path: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<Character>, Diagnostic> {
+    let id = format!("{}_{}", path.to_string_lossy(), index);
+
+    let mut parsed = cache.get_or_parse(path, |_dependencies, diagnostics| {
+        parse_character_file(
+            path,
+            packages_root,
+            id.clone(),
+            source_directory,
+            diagnostics,
+        )
+        .map(|character| vec![character])
+    })?;
+
+    for character in &mut parsed.records {
+        character.id = id.clone();
+        character.source_directory = source_directory.to_string();
+    }
+    Ok(parsed)
+}
+
+/// Parse a single character file
+fn parse_character_file(
+    character_path: &Path,
+    packages_root: &Path,
+    id: String,
+    source_directory: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Character, Diagnostic> {
+    let content = read_file(character_path)?;
+    let (raw, partially_parsed) = parse_recovering(&content, diagnostics, |content| {
+        parse_xml::<RawCharacter>(content, character_path)
+    })?;
+
+    let names = |refs: Vec<RawFileRef>| -> Vec<String> {
+        refs.into_iter().filter_map(RawFileRef::into_name).collect()
+    };
+
+    let file_path = relative_file_path(character_path, packages_root);
+
+    let key = character_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Ok(Character {
+        id,
+        name: raw.name.unwrap_or_else(|| key.clone()),
+        key,
+        models: names(raw.models),
+        voices: names(raw.voices),
+        skins: names(raw.skins),
+        modifiers: raw
+            .modifiers
+            .into_iter()
+            .filter_map(|m| {
+                Some(CharacterModifier {
+                    class: m.class?,
+                    value: m.value,
+                })
+            })
+            .collect(),
+        file_path,
+        source_file: character_path.to_string_lossy().to_string(),
+        source_directory: source_directory.to_string(),
+        package_name: package_name_of(character_path, packages_root),
+        provenance: None,
+        partially_parsed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_file_references_and_modifiers() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let characters = packages.join("vanilla/characters");
+        std::fs::create_dir_all(&characters).unwrap();
+        let path = characters.join("default.character");
+        std::fs::write(
+            &path,
+            r#"<character>
+                <model filename="soldier.xml"/>
+                <voice file="voice_en.xml"/>
+                <skin filename="green.visual_item"/>
+                <skin filename=""/>
+                <modifier class="speed" value="1.1"/>
+                <modifier value="2.0"/>
+            </character>"#,
+        )
+        .unwrap();
+
+        let character =
+            parse_character_file(&path, packages, "default".into(), "/game", &mut Vec::new())
+                .unwrap();
+
+        assert_eq!(character.key, "default.character");
+        assert_eq!(character.name, "default.character");
+        assert_eq!(character.models, ["soldier.xml"]);
+        assert_eq!(character.voices, ["voice_en.xml"]);
+        assert_eq!(character.skins, ["green.visual_item"]);
+        assert_eq!(character.modifiers.len(), 1);
+        assert_eq!(character.modifiers[0].class, "speed");
+        assert_eq!(character.file_path, "vanilla/characters/default.character");
+    }
+
+    #[test]
+    fn parses_interleaved_elements() {
+        let packages = tempfile::tempdir().unwrap();
+        let characters = packages.path().join("vanilla/characters");
+        std::fs::create_dir_all(&characters).unwrap();
+        let path = characters.join("mixed.character");
+        std::fs::write(
+            &path,
+            r#"<character>
+                <skin filename="green.visual_item"/>
+                <modifier class="speed" value="1.1"/>
+                <model filename="soldier.xml"/>
+                <skin filename="desert.visual_item"/>
+                <voice file="voice_en.xml"/>
+                <modifier class="hit_success_probability" value="0.9"/>
+            </character>"#,
+        )
+        .unwrap();
+
+        let character = parse_character_file(
+            &path,
+            packages.path(),
+            "mixed".into(),
+            "/game",
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert!(!character.partially_parsed);
+        assert_eq!(character.skins, ["green.visual_item", "desert.visual_item"]);
+        assert_eq!(character.models, ["soldier.xml"]);
+        assert_eq!(character.voices, ["voice_en.xml"]);
+        let classes: Vec<_> = character
+            .modifiers
+            .iter()
+            .map(|m| m.class.as_str())
+            .collect();
+        assert_eq!(classes, ["speed", "hit_success_probability"]);
+    }
+}
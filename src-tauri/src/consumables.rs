@@ -0,0 +1,279 @@
+//! Item consumption graph
+//!
+//! `transform_on_consume="..."` replaces a carry_item with another one once it is used
+//! (a medikit becoming its used variant, ...), and `<modifier>`s with
+//! `input_character_state` / `output_character_state` move the soldier between states
+//! (wounded → healthy, ...). [`build_consume_graph`] follows the transform chain of every
+//! effective carry_item, reports targets no item defines and transform loops, and
+//! summarises the state changes each consumable applies.
+
+use crate::items::{Item, ItemScanner};
+use crate::scanner;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Consumption behaviour of every consumable of a scan
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsumeGraph {
+    /// Items that transform, are transformed into or change character state; sorted by key
+    pub consumables: Vec<Consumable>,
+    /// `transform_on_consume` targets no scanned carry_item defines
+    pub broken: Vec<BrokenTransform>,
+    /// Keys that transform back into themselves, each loop starting at its smallest key
+    pub cycles: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Consumable {
+    pub key: String,
+    pub item_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform_on_consume: Option<String>,
+    /// Keys the item turns into when consumed repeatedly, in order; stops at the first
+    /// item without a transform, a missing target or a key seen before
+    pub transform_chain: Vec<String>,
+    /// Keys of the items transforming into this one
+    pub transformed_from: Vec<String>,
+    pub state_changes: Vec<StateChange>,
+    /// Whether any modifier removes the item when it applies
+    pub consumes_item: bool,
+    /// Whether the transform chain ends in a loop
+    pub cyclic: bool,
+}
+
+/// A modifier of a consumable that acts on character state
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateChange {
+    pub modifier_class: String,
+    /// State the soldier must be in; any state when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_character_state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_character_state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    pub consumes_item: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenTransform {
+    pub from: String,
+    pub item_id: String,
+    /// Key as written in `transform_on_consume`
+    pub to: String,
+}
+
+/// Follow the transform chains of the effective carry_items of a scan.
+///
+/// `items` takes the rows of an effective scan the frontend already holds; without them
+/// the packages are scanned.
+#[tauri::command]
+pub async fn item_consumption(
+    game_path: String,
+    directory: Option<String>,
+    items: Option<Vec<Item>>,
+) -> Result<ConsumeGraph, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let items = scanner::records_or_scan::<ItemScanner>(items, &source_directory)?;
+    Ok(build_consume_graph(&items))
+}
+
+/// The consumption summary of carry_item `key`, if it takes part in consumption at all.
+#[tauri::command]
+pub async fn item_consumable(
+    game_path: String,
+    directory: Option<String>,
+    key: String,
+    items: Option<Vec<Item>>,
+) -> Result<Option<Consumable>, String> {
+    let graph = item_consumption(game_path, directory, items).await?;
+    Ok(graph.consumables.into_iter().find(|c| c.key == key))
+}
+
+fn state_changes(item: &Item) -> Vec<StateChange> {
+    item.modifiers
+        .iter()
+        .flatten()
+        .filter(|m| {
+            m.input_character_state.is_some()
+                || m.output_character_state.is_some()
+                || m.consumes_item == Some(true)
+        })
+        .map(|m| StateChange {
+            modifier_class: m.modifier_class.clone(),
+            input_character_state: m.input_character_state.clone(),
+            output_character_state: m.output_character_state.clone(),
+            value: m.value,
+            consumes_item: m.consumes_item.unwrap_or(false),
+        })
+        .collect()
+}
+
+/// Build the consumption graph of `items`, which should hold one record per key (an
+/// effective scan). Only keyed carry_items take part.
+pub(crate) fn build_consume_graph(items: &[Item]) -> ConsumeGraph {
+    let carry_items: HashMap<&str, &Item> = items
+        .iter()
+        .filter(|item| item.item_type == "carry_item")
+        .filter_map(|item| Some((item.key.as_deref()?, item)))
+        .collect();
+    let target = |key: &str| -> Option<&str> {
+        carry_items
+            .get(key)?
+            .transform_on_consume
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+    };
+
+    let mut broken = Vec::new();
+    let mut transformed_from: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    let mut keys: Vec<&str> = carry_items.keys().copied().collect();
+    keys.sort();
+    for &key in &keys {
+        let Some(to) = target(key) else {
+            continue;
+        };
+        if carry_items.contains_key(to) {
+            transformed_from
+                .entry(to)
+                .or_default()
+                .push(key.to_string());
+        } else {
+            broken.push(BrokenTransform {
+                from: key.to_string(),
+                item_id: carry_items[key].id.clone(),
+                to: to.to_string(),
+            });
+        }
+    }
+
+    let mut cycles: Vec<Vec<String>> = Vec::new();
+    let mut consumables = Vec::new();
+    for &key in &keys {
+        let item = carry_items[key];
+        let mut chain: Vec<String> = Vec::new();
+        let mut cyclic = false;
+        let mut current = key;
+        while let Some(next) = target(current).filter(|t| carry_items.contains_key(t)) {
+            if let Some(start) = chain.iter().position(|k| k == next) {
+                cyclic = true;
+                push_cycle(&mut cycles, &chain[start..]);
+                break;
+            }
+            if next == key {
+                cyclic = true;
+                push_cycle(&mut cycles, &[&[key.to_string()], &chain[..]].concat());
+                break;
+            }
+            chain.push(next.to_string());
+            current = next;
+        }
+
+        let state_changes = state_changes(item);
+        let sources = transformed_from.remove(key).unwrap_or_default();
+        if item.transform_on_consume.is_none() && sources.is_empty() && state_changes.is_empty() {
+            continue;
+        }
+        consumables.push(Consumable {
+            key: key.to_string(),
+            item_id: item.id.clone(),
+            name: item.name.clone(),
+            transform_on_consume: item.transform_on_consume.clone(),
+            transform_chain: chain,
+            transformed_from: sources,
+            consumes_item: state_changes.iter().any(|c| c.consumes_item),
+            state_changes,
+            cyclic,
+        });
+    }
+    cycles.sort();
+
+    ConsumeGraph {
+        consumables,
+        broken,
+        cycles,
+    }
+}
+
+/// Record a loop once, rotated to start at its smallest key
+fn push_cycle(cycles: &mut Vec<Vec<String>>, cycle: &[String]) {
+    let Some(start) = cycle
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, key)| *key)
+        .map(|(i, _)| i)
+    else {
+        return;
+    };
+    let rotated: Vec<String> = cycle[start..]
+        .iter()
+        .chain(&cycle[..start])
+        .cloned()
+        .collect();
+    if !cycles.contains(&rotated) {
+        cycles.push(rotated);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::{test_item, ItemModifier};
+
+    fn item(key: &str, transform: Option<&str>, modifiers: Vec<ItemModifier>) -> Item {
+        Item {
+            transform_on_consume: transform.map(str::to_string),
+            modifiers: Some(modifiers),
+            ..test_item(key)
+        }
+    }
+
+    #[test]
+    fn follows_transforms_and_summarises_state_changes() {
+        let heal = ItemModifier {
+            modifier_class: "hit_success_probability".to_string(),
+            value: None,
+            input_character_state: Some("wounded".to_string()),
+            output_character_state: Some("none".to_string()),
+            consumes_item: Some(true),
+        };
+        let items = [
+            item(
+                "medikit.carry_item",
+                Some("medikit_used.carry_item"),
+                vec![heal],
+            ),
+            item("medikit_used.carry_item", None, Vec::new()),
+            item("a.carry_item", Some("b.carry_item"), Vec::new()),
+            item("b.carry_item", Some("a.carry_item"), Vec::new()),
+            item("vest.carry_item", Some("missing.carry_item"), Vec::new()),
+            item("helmet.carry_item", None, Vec::new()),
+        ];
+
+        let graph = build_consume_graph(&items);
+        let find = |key: &str| graph.consumables.iter().find(|c| c.key == key);
+
+        let medikit = find("medikit.carry_item").unwrap();
+        assert_eq!(medikit.transform_chain, ["medikit_used.carry_item"]);
+        assert!(medikit.consumes_item && !medikit.cyclic);
+        assert_eq!(
+            medikit.state_changes[0].input_character_state.as_deref(),
+            Some("wounded")
+        );
+        let used = find("medikit_used.carry_item").unwrap();
+        assert_eq!(used.transformed_from, ["medikit.carry_item"]);
+
+        assert!(find("a.carry_item").unwrap().cyclic);
+        assert_eq!(graph.cycles, vec![vec!["a.carry_item", "b.carry_item"]]);
+
+        assert_eq!(graph.broken.len(), 1);
+        assert_eq!(graph.broken[0].to, "missing.carry_item");
+        assert!(find("helmet.carry_item").is_none());
+    }
+}
@@ -0,0 +1,242 @@
+//! Structured scan diagnostics
+//!
+//! Every problem found while scanning packages is reported as a [`Diagnostic`] with a stable
+//! code, the file and (when known) the line/column it refers to, and the template chain that
+//! was being resolved.
+
+use crate::xml_repair::repair_xml;
+use quick_xml::de::Deserializer;
+use quick_xml::DeError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Stable diagnostic codes; the serialized names are part of the frontend contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticCode {
+    /// The file could not be read from disk
+    ReadFailed,
+    /// Malformed XML (unclosed tags, bad attributes, ...)
+    XmlSyntax,
+    /// Well-formed XML that does not have the expected structure or value types
+    XmlStructure,
+    /// A `file="..."` template does not exist, neither next to the file nor in vanilla
+    TemplateNotFound,
+    /// A template chain refers back to itself
+    TemplateCycle,
+    /// A template chain is deeper than the supported limit
+    TemplateDepthExceeded,
+    /// A multi-entry carry_item template has no entry matching the inheriting item
+    TemplateSelection,
+    /// A file referenced by name (e.g. a faction's `.resources` file) does not exist
+    ReferenceNotFound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The file produced no records
+    Error,
+    /// Records were produced but may be incomplete
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    pub severity: Severity,
+    /// Absolute path of the file the diagnostic points at
+    pub file: String,
+    /// 1-based line, when the position is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based column (in characters), when the position is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    pub message: String,
+    /// Scanned file followed by every template resolved from it, outermost first
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub template_chain: Vec<String>,
+}
+
+impl Diagnostic {
+    /// New `error` diagnostic; callers downgrade it with [`Diagnostic::with_severity`]
+    /// when the record can still be produced.
+    pub fn error(code: DiagnosticCode, file: &Path, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity: Severity::Error,
+            file: file.to_string_lossy().to_string(),
+            line: None,
+            column: None,
+            message: message.into(),
+            template_chain: Vec::new(),
+        }
+    }
+
+    /// Point the diagnostic at byte `offset` of `content`
+    pub fn at(mut self, content: &str, offset: usize) -> Self {
+        let (line, column) = line_column(content, offset);
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Record the template chain: `file` itself followed by `templates` in resolution order.
+    ///
+    /// Missing templates are skipped unless they are last: those are lookup candidates that
+    /// were passed over, while the last one is the template that couldn't be found.
+    pub fn with_template_chain(mut self, file: &Path, templates: &[impl AsRef<Path>]) -> Self {
+        let last = templates.len().saturating_sub(1);
+        self.template_chain = std::iter::once(file)
+            .chain(
+                templates
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| (i, t.as_ref()))
+                    .filter(|(i, t)| *i == last || t.exists())
+                    .map(|(_, t)| t),
+            )
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        self
+    }
+}
+
+/// Deserialize `content` with quick-xml, turning failures into a positioned diagnostic.
+///
+/// Syntax errors point at the offending markup. Attribute errors are only detected once the
+/// deserializer reads the tag, so they and structural errors point at the end of the element
+/// being read.
+pub fn parse_xml<T: DeserializeOwned>(content: &str, file: &Path) -> Result<T, Diagnostic> {
+    let mut de = Deserializer::from_str(content);
+    T::deserialize(&mut de).map_err(|e| {
+        let reader = de.get_ref().get_ref();
+        let (code, offset) = match e {
+            DeError::InvalidXml(quick_xml::Error::InvalidAttr(_)) => {
+                (DiagnosticCode::XmlSyntax, reader.buffer_position())
+            }
+            DeError::InvalidXml(_) => (DiagnosticCode::XmlSyntax, reader.error_position()),
+            _ => (DiagnosticCode::XmlStructure, reader.buffer_position()),
+        };
+        Diagnostic::error(code, file, format!("XML parse error: {}", e))
+            .at(content, offset as usize)
+    })
+}
+
+/// Run `parse` on `content`, retrying on a [`repair_xml`] copy when it fails.
+///
+/// When only the repaired copy parses, the original failure is kept in `diagnostics` as a
+/// warning, still pointing at the broken markup, and `true` is returned alongside the value
+/// so the caller can mark the record as partially parsed.
+pub fn parse_recovering<T>(
+    content: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+    parse: impl Fn(&str) -> Result<T, Diagnostic>,
+) -> Result<(T, bool), Diagnostic> {
+    let diagnostic = match parse(content) {
+        Ok(value) => return Ok((value, false)),
+        Err(diagnostic) => diagnostic,
+    };
+    let repaired = repair_xml(content);
+    if repaired == content {
+        return Err(diagnostic);
+    }
+    let value = parse(&repaired).map_err(|_| diagnostic.clone())?;
+    let mut diagnostic = diagnostic.with_severity(Severity::Warning);
+    diagnostic.message = format!(
+        "Recovered from malformed XML, some fields may be missing ({})",
+        diagnostic.message
+    );
+    diagnostics.push(diagnostic);
+    Ok((value, true))
+}
+
+/// Read a file to a string, reporting failures as `read_failed`
+pub fn read_file(file: &Path) -> Result<String, Diagnostic> {
+    std::fs::read_to_string(file).map_err(|e| {
+        Diagnostic::error(
+            DiagnosticCode::ReadFailed,
+            file,
+            format!("Failed to read file: {}", e),
+        )
+    })
+}
+
+/// 1-based line and column of byte `offset` in `content`
+pub(crate) fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(content.len());
+    while !content.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Root {
+        #[serde(rename = "@value")]
+        _value: f64,
+    }
+
+    #[test]
+    fn line_column_is_one_based() {
+        let content = "<a>\n  <b/>\n</a>";
+        assert_eq!(line_column(content, 0), (1, 1));
+        assert_eq!(line_column(content, 6), (2, 3));
+        assert_eq!(line_column(content, 999), (3, 5));
+    }
+
+    #[test]
+    fn syntax_error_reports_position() {
+        let content = "<root value=\"1\">\n  <child>\n</root>";
+        let diagnostic = parse_xml::<Root>(content, Path::new("test.weapon")).unwrap_err();
+        assert_eq!(diagnostic.code, DiagnosticCode::XmlSyntax);
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.line, Some(3));
+    }
+
+    #[test]
+    fn structure_error_is_distinguished() {
+        let diagnostic =
+            parse_xml::<Root>("<root value=\"abc\"/>", Path::new("test.weapon")).unwrap_err();
+        assert_eq!(diagnostic.code, DiagnosticCode::XmlStructure);
+        assert_eq!(diagnostic.line, Some(1));
+    }
+
+    #[test]
+    fn recovers_malformed_xml_as_warning() {
+        let file = Path::new("test.weapon");
+        let mut diagnostics = Vec::new();
+        let content = "<root value=\"1\">\n  <child>\n</root>";
+        let (_, partial) =
+            parse_recovering(content, &mut diagnostics, |c| parse_xml::<Root>(c, file)).unwrap();
+        assert!(partial);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::XmlSyntax);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].line, Some(3));
+
+        let mut diagnostics = Vec::new();
+        let error = parse_recovering("<root value=\"abc\"/>", &mut diagnostics, |c| {
+            parse_xml::<Root>(c, file)
+        })
+        .unwrap_err();
+        assert_eq!(error.severity, Severity::Error);
+        assert!(diagnostics.is_empty());
+    }
+}
@@ -1,10 +1,29 @@
+use crate::diagnostics::Diagnostic;
+use crate::packages::DuplicateKey;
 use serde::Serialize;
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "event", content = "data", rename_all = "camelCase")]
 pub enum ScanEvent<T> {
     Chunk(Vec<T>),
-    Progress { current: usize, total: usize },
-    Error(String),
+    /// Watch mode: records re-parsed after their file or one of their templates changed
+    Updated(Vec<T>),
+    /// Watch mode: ids of records whose file was deleted or no longer produces them
+    Removed(Vec<String>),
+    Progress {
+        current: usize,
+        total: usize,
+    },
+    /// A file failed to parse (severity `error`) or parsed with problems (`warning`)
+    Diagnostic(Diagnostic),
+    /// Keys defined more than once across the scanned packages, sent once parsing is done
+    Duplicates(Vec<DuplicateKey>),
+    /// Files served from the persistent scan cache vs re-parsed, sent right before `Finished`
+    CacheStats {
+        hits: usize,
+        reparsed: usize,
+    },
     Finished,
+    /// Sent instead of `Finished` when the scan was stopped through `cancel_scan`
+    Cancelled,
 }
@@ -0,0 +1,682 @@
+//! Factions scanner module
+//!
+//! Scans RWR packages for `.faction` files and returns faction names, colours, ranks and
+//! soldier groups with the weapons/carry_items each group can spawn with. Soldier resources
+//! may be inline or in a separate `.resources` file; each `<resources>` element contributes
+//! its file's entries, then its inline ones, every block listing weapons before carry_items.
+//! [`faction_loadouts`] cross-links every referenced key to the scanned weapons and items.
+
+use crate::diagnostics::{
+    parse_recovering, parse_xml, read_file, Diagnostic, DiagnosticCode, Severity,
+};
+use crate::items::{Item, ItemScanner};
+use crate::packages::{KeyDefinition, Provenance};
+use crate::scan_cache::{Parsed, ScanCache};
+use crate::scan_jobs::ScanJobs;
+use crate::scanner::{self, PackageScanner, ScanOptions, ScanSummary};
+use crate::templates;
+use crate::utils::{package_name_of, relative_file_path};
+use crate::watch::{self, WatchRegistry};
+use crate::weapons::{Weapon, WeaponScanner};
+use crate::ScanEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// Faction definition with its soldier resources resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Faction {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub name: String,
+    /// RGB(A) components from `color="r g b [a]"` (0.0 - 1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Vec<f64>>,
+    /// Ranks in declaration order (ascending XP)
+    pub ranks: Vec<FactionRank>,
+    pub soldiers: Vec<SoldierGroup>,
+    pub file_path: String,
+    pub source_file: String,
+    pub source_directory: String,
+    pub package_name: String,
+    /// Effective scans only: the package this definition was taken from and what it overrides
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provenance: Option<Provenance>,
+    /// The file is malformed XML; these fields were recovered from a repaired copy and
+    /// anything after the broken markup may be missing
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub partially_parsed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FactionRank {
+    pub name: String,
+    /// XP threshold for the rank
+    pub xp: f64,
+}
+
+/// A `<soldier>` group of a faction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoldierGroup {
+    pub name: String,
+    /// Relative weight of this group when the faction spawns a soldier
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spawn_score: Option<f64>,
+    /// `.character` file the group uses (`<character filename="...">`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character: Option<String>,
+    /// `.models` file the group uses (`<models file="...">`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub models: Option<String>,
+    /// Per `<resources>` element: its file's entries, then its inline ones; weapons before
+    /// carry_items within each
+    pub resources: Vec<SoldierResource>,
+}
+
+/// A weapon or carry_item a soldier group can spawn with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoldierResource {
+    pub kind: ResourceKind,
+    pub key: String,
+    /// `spawn_score` of the entry, when set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Weapon,
+    CarryItem,
+}
+
+/// Result from faction scanning
+#[derive(Debug, Serialize)]
+pub struct FactionScanResult {
+    pub factions: Vec<Faction>,
+    #[serde(flatten)]
+    pub summary: ScanSummary,
+}
+
+/// Every faction soldier group that can spawn with one weapon/carry_item key
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadoutUsage {
+    pub kind: ResourceKind,
+    pub key: String,
+    /// Ids of the effective weapon/item records registering `key`; empty if none does
+    pub record_ids: Vec<String>,
+    pub spawns: Vec<FactionSpawn>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FactionSpawn {
+    pub faction_id: String,
+    pub faction_name: String,
+    pub soldier: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+}
+
+/// Raw faction XML structure
+#[derive(Debug, Deserialize, Default)]
+struct RawFaction {
+    #[serde(rename = "@key", default)]
+    key: Option<String>,
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "@color", default)]
+    color: Option<String>,
+    #[serde(rename = "rank", default)]
+    ranks: Vec<RawRank>,
+    #[serde(rename = "soldier", default)]
+    soldiers: Vec<RawSoldier>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawRank {
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "@xp", default)]
+    xp: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawSoldier {
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "@spawn_score", default)]
+    spawn_score: Option<f64>,
+    #[serde(rename = "character", default)]
+    character: Option<RawFileRef>,
+    #[serde(rename = "models", default)]
+    models: Option<RawFileRef>,
+    #[serde(rename = "resources", default)]
+    resources: Vec<RawResources>,
+}
+
+/// Reference to another file; the game accepts both `filename` and `file`
+#[derive(Debug, Deserialize, Default)]
+pub(crate) struct RawFileRef {
+    #[serde(rename = "@filename", default)]
+    filename: Option<String>,
+    #[serde(rename = "@file", default)]
+    file: Option<String>,
+}
+
+impl RawFileRef {
+    pub(crate) fn into_name(self) -> Option<String> {
+        self.filename.or(self.file).filter(|f| !f.is_empty())
+    }
+}
+
+/// `<resources>` element or the root of a `.resources` file
+#[derive(Debug, Deserialize, Default)]
+struct RawResources {
+    #[serde(rename = "@file", default)]
+    file: Option<String>,
+    #[serde(rename = "weapon", default)]
+    weapons: Vec<RawResourceEntry>,
+    #[serde(rename = "carry_item", default)]
+    carry_items: Vec<RawResourceEntry>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawResourceEntry {
+    #[serde(rename = "@key", default)]
+    key: Option<String>,
+    #[serde(rename = "@spawn_score", default)]
+    spawn_score: Option<f64>,
+}
+
+/// Scan all faction files from a game/workshop directory.
+///
+/// The scan runs in the background; the returned job id can be passed to `cancel_scan`.
+#[tauri::command]
+pub async fn scan_factions(
+    game_path: String,
+    directory: Option<String>,
+    jobs: State<'_, ScanJobs>,
+    on_event: Channel<ScanEvent<Faction>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    scanner::spawn_scan::<FactionScanner>(&jobs, source_directory, on_event)
+}
+
+/// [`scan_factions`] in one payload, for WebViews that drop `Channel` messages
+/// (see `scan_weapons_collect`).
+#[tauri::command]
+pub async fn scan_factions_collect(
+    game_path: String,
+    directory: Option<String>,
+    effective: Option<bool>,
+) -> Result<FactionScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        effective: effective.unwrap_or(false),
+        ..Default::default()
+    };
+    let scan = scanner::collect_scan::<FactionScanner>(&source_directory, options)?;
+    Ok(FactionScanResult {
+        factions: scan.records,
+        summary: scan.summary,
+    })
+}
+
+/// Watch the packages roots of a factions scan and stream add/update/remove deltas.
+#[tauri::command]
+pub async fn watch_factions(
+    game_path: String,
+    directory: Option<String>,
+    registry: State<'_, WatchRegistry>,
+    on_event: Channel<ScanEvent<Faction>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    watch::start::<FactionScanner>(&registry, source_directory, on_event)
+}
+
+/// Cross-link faction loadouts with the scanned weapons and carry_items.
+///
+/// Uses the effective records of each scan (what the game actually loads) and returns one
+/// entry per referenced key, sorted by kind and key. Keys no weapon/item registers come
+/// back with empty `record_ids`.
+///
+/// `factions`, `weapons` and `items` take the rows of effective scans the frontend already
+/// holds; only the kinds left out are scanned.
+#[tauri::command]
+pub async fn faction_loadouts(
+    game_path: String,
+    directory: Option<String>,
+    factions: Option<Vec<Faction>>,
+    weapons: Option<Vec<Weapon>>,
+    items: Option<Vec<Item>>,
+) -> Result<Vec<LoadoutUsage>, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let factions = scanner::records_or_scan::<FactionScanner>(factions, &source_directory)?;
+    let weapons = scanner::records_or_scan::<WeaponScanner>(weapons, &source_directory)?;
+    let items = scanner::records_or_scan::<ItemScanner>(items, &source_directory)?;
+
+    // Resource lists refer to weapons by file name ("ak47.weapon"), so index those too.
+    let mut records: HashMap<(ResourceKind, String), Vec<String>> = HashMap::new();
+    for weapon in &weapons {
+        let file_name = Path::new(&weapon.file_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string());
+        let mut keys: Vec<String> = weapon.key.iter().cloned().chain(file_name).collect();
+        keys.dedup();
+        for key in keys {
+            records
+                .entry((ResourceKind::Weapon, key))
+                .or_default()
+                .push(weapon.id.clone());
+        }
+    }
+    for item in items.iter().filter(|i| i.item_type == "carry_item") {
+        if let Some(key) = &item.key {
+            records
+                .entry((ResourceKind::CarryItem, key.clone()))
+                .or_default()
+                .push(item.id.clone());
+        }
+    }
+
+    let mut usages: HashMap<(ResourceKind, String), Vec<FactionSpawn>> = HashMap::new();
+    for faction in &factions {
+        for soldier in &faction.soldiers {
+            for resource in &soldier.resources {
+                usages
+                    .entry((resource.kind, resource.key.clone()))
+                    .or_default()
+                    .push(FactionSpawn {
+                        faction_id: faction.id.clone(),
+                        faction_name: faction.name.clone(),
+                        soldier: soldier.name.clone(),
+                        weight: resource.weight,
+                    });
+            }
+        }
+    }
+
+    let mut loadouts: Vec<LoadoutUsage> = usages
+        .into_iter()
+        .map(|((kind, key), spawns)| LoadoutUsage {
+            record_ids: records
+                .get(&(kind, key.clone()))
+                .cloned()
+                .unwrap_or_default(),
+            kind,
+            key,
+            spawns,
+        })
+        .collect();
+    loadouts.sort_by(|a, b| (a.kind, &a.key).cmp(&(b.kind, &b.key)));
+    Ok(loadouts)
+}
+
+/// Faction scanner for the shared scanning framework
+pub(crate) struct FactionScanner;
+
+impl PackageScanner for FactionScanner {
+    type Record = Faction;
+
+    const KIND: &'static str = "factions";
+
+    fn matches(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "faction")
+    }
+
+    fn parse_cached(
+        cache: &ScanCache<Faction>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Faction>, Diagnostic> {
+        parse_faction_cached(cache, file, packages_root, index, source_directory)
+    }
+
+    fn record_id(record: &Faction) -> &str {
+        &record.id
+    }
+
+    fn key_definition(record: &Faction) -> Option<KeyDefinition> {
+        Some(KeyDefinition {
+            key: record.key.clone()?,
+            id: record.id.clone(),
+            package_name: record.package_name.clone(),
+            file_path: record.file_path.clone(),
+            source_file: record.source_file.clone(),
+        })
+    }
+
+    fn set_provenance(record: &mut Faction, provenance: Provenance) {
+        record.provenance = Some(provenance);
+    }
+}
+
+/// Parse a faction file through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits.
+fn parse_faction_cached(
+    cache: &ScanCache<Faction>,
+    path: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<Faction>, Diagnostic> {
+    let id = format!("{}_{}", path.to_string_lossy(), index);
+
+    let mut parsed = cache.get_or_parse(path, |dependencies, diagnostics| {
+        parse_faction_file(
+            path,
+            packages_root,
+            id.clone(),
+            source_directory,
+            dependencies,
+            diagnostics,
+        )
+        .map(|faction| vec![faction])
+    })?;
+
+    for faction in &mut parsed.records {
+        faction.id = id.clone();
+        faction.source_directory = source_directory.to_string();
+    }
+    Ok(parsed)
+}
+
+/// Parse a single faction file.
+/// Every `.resources` file consulted is appended to `dependencies`; missing ones are
+/// reported as warnings in `diagnostics`.
+fn parse_faction_file(
+    faction_path: &Path,
+    packages_root: &Path,
+    id: String,
+    source_directory: &str,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Faction, Diagnostic> {
+    let content = read_file(faction_path)?;
+    let (raw, mut partially_parsed) = parse_recovering(&content, diagnostics, |content| {
+        parse_xml::<RawFaction>(content, faction_path)
+    })?;
+    let faction_dir = faction_path.parent().unwrap_or(packages_root);
+
+    let soldiers = raw
+        .soldiers
+        .into_iter()
+        .map(|soldier| {
+            let mut resources = Vec::new();
+            for raw_resources in soldier.resources {
+                if let Some(file) = &raw_resources.file {
+                    let chain_start = dependencies.len();
+                    match read_resources_file(faction_dir, file, dependencies, diagnostics) {
+                        Ok((external, repaired)) => {
+                            partially_parsed |= repaired;
+                            collect_resources(external, &mut resources);
+                        }
+                        Err(diagnostic) => diagnostics.push(
+                            diagnostic
+                                .with_severity(Severity::Warning)
+                                .with_template_chain(faction_path, &dependencies[chain_start..]),
+                        ),
+                    }
+                }
+                collect_resources(raw_resources, &mut resources);
+            }
+            SoldierGroup {
+                name: soldier.name.unwrap_or_default(),
+                spawn_score: soldier.spawn_score,
+                character: soldier.character.and_then(RawFileRef::into_name),
+                models: soldier.models.and_then(RawFileRef::into_name),
+                resources,
+            }
+        })
+        .collect();
+
+    let ranks = raw
+        .ranks
+        .into_iter()
+        .map(|r| FactionRank {
+            name: r.name.unwrap_or_default(),
+            xp: r.xp.unwrap_or(0.0),
+        })
+        .collect();
+
+    let color = raw.color.and_then(|c| {
+        c.split_whitespace()
+            .map(|v| v.parse::<f64>().ok())
+            .collect::<Option<Vec<f64>>>()
+            .filter(|v| !v.is_empty())
+    });
+
+    let file_path = relative_file_path(faction_path, packages_root);
+
+    let key = raw.key.filter(|k| !k.is_empty()).or_else(|| {
+        faction_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_string())
+    });
+
+    Ok(Faction {
+        id,
+        name: raw.name.unwrap_or_default(),
+        key,
+        color,
+        ranks,
+        soldiers,
+        file_path,
+        source_file: faction_path.to_string_lossy().to_string(),
+        source_directory: source_directory.to_string(),
+        package_name: package_name_of(faction_path, packages_root),
+        provenance: None,
+        partially_parsed,
+    })
+}
+
+/// Read a `.resources` file referenced from a faction, falling back to `vanilla/factions`.
+/// Malformed files are recovered like scans do; the flag tells whether that was needed.
+fn read_resources_file(
+    base_dir: &Path,
+    file: &str,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(RawResources, bool), Diagnostic> {
+    let path = templates::locate_dependency(base_dir, file, "factions", dependencies);
+
+    if !path.exists() {
+        return Err(Diagnostic::error(
+            DiagnosticCode::ReferenceNotFound,
+            &path,
+            format!("Resources file not found: {}", file),
+        ));
+    }
+
+    let content = read_file(&path)?;
+    parse_recovering(&content, diagnostics, |content| parse_xml(content, &path))
+}
+
+/// Append the weapons, then the carry_items of one resources block
+fn collect_resources(raw: RawResources, resources: &mut Vec<SoldierResource>) {
+    let entries = raw
+        .weapons
+        .into_iter()
+        .map(|e| (ResourceKind::Weapon, e))
+        .chain(
+            raw.carry_items
+                .into_iter()
+                .map(|e| (ResourceKind::CarryItem, e)),
+        );
+    for (kind, entry) in entries {
+        if let Some(key) = entry.key.filter(|k| !k.is_empty()) {
+            resources.push(SoldierResource {
+                kind,
+                key,
+                weight: entry.spawn_score,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_resources_files_with_vanilla_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let vanilla = packages.join("vanilla/factions");
+        let factions = packages.join("mod/factions");
+        std::fs::create_dir_all(&vanilla).unwrap();
+        std::fs::create_dir_all(&factions).unwrap();
+        std::fs::write(
+            vanilla.join("default.resources"),
+            r#"<resources><weapon key="ak47.weapon" spawn_score="2.0"/></resources>"#,
+        )
+        .unwrap();
+        let faction_path = factions.join("reds.faction");
+        std::fs::write(
+            &faction_path,
+            r#"<faction name="Reds" color="1.0 0.0 0.0">
+                <rank name="Private" xp="0.0"/>
+                <soldier name="rifleman" spawn_score="1.0">
+                    <character filename="default.character"/>
+                    <resources file="default.resources">
+                        <carry_item key="vest.carry_item"/>
+                    </resources>
+                    <resources file="missing.resources"/>
+                </soldier>
+            </faction>"#,
+        )
+        .unwrap();
+
+        let mut dependencies = Vec::new();
+        let mut diagnostics = Vec::new();
+        let faction = parse_faction_file(
+            &faction_path,
+            packages,
+            "reds".into(),
+            "/game",
+            &mut dependencies,
+            &mut diagnostics,
+        )
+        .unwrap();
+
+        assert_eq!(faction.key.as_deref(), Some("reds.faction"));
+        assert_eq!(faction.color, Some(vec![1.0, 0.0, 0.0]));
+        let soldier = &faction.soldiers[0];
+        assert_eq!(soldier.character.as_deref(), Some("default.character"));
+        let resources: Vec<_> = soldier
+            .resources
+            .iter()
+            .map(|r| (r.kind, r.key.as_str(), r.weight))
+            .collect();
+        assert_eq!(
+            resources,
+            [
+                (ResourceKind::Weapon, "ak47.weapon", Some(2.0)),
+                (ResourceKind::CarryItem, "vest.carry_item", None),
+            ]
+        );
+
+        // The local candidates are dependencies too, so adding one later invalidates the cache
+        assert!(dependencies.contains(&factions.join("default.resources")));
+        assert!(dependencies.contains(&vanilla.join("default.resources")));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::ReferenceNotFound);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn parses_interleaved_resources() {
+        let packages = tempfile::tempdir().unwrap();
+        let factions = packages.path().join("vanilla/factions");
+        std::fs::create_dir_all(&factions).unwrap();
+        let faction_path = factions.join("greens.faction");
+        std::fs::write(
+            &faction_path,
+            r#"<faction name="Greens">
+                <soldier name="rifleman">
+                    <resources>
+                        <weapon key="ak47.weapon"/>
+                        <carry_item key="vest.carry_item"/>
+                        <weapon key="m16.weapon"/>
+                        <carry_item key="medikit.carry_item"/>
+                    </resources>
+                </soldier>
+            </faction>"#,
+        )
+        .unwrap();
+
+        let faction = parse_faction_file(
+            &faction_path,
+            packages.path(),
+            "greens".into(),
+            "/game",
+            &mut Vec::new(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert!(!faction.partially_parsed);
+        let resources: Vec<_> = faction.soldiers[0]
+            .resources
+            .iter()
+            .map(|r| (r.kind, r.key.as_str()))
+            .collect();
+        assert_eq!(
+            resources,
+            [
+                (ResourceKind::Weapon, "ak47.weapon"),
+                (ResourceKind::Weapon, "m16.weapon"),
+                (ResourceKind::CarryItem, "vest.carry_item"),
+                (ResourceKind::CarryItem, "medikit.carry_item"),
+            ]
+        );
+    }
+
+    #[test]
+    fn recovers_malformed_resources_files() {
+        let packages = tempfile::tempdir().unwrap();
+        let factions = packages.path().join("vanilla/factions");
+        std::fs::create_dir_all(&factions).unwrap();
+        std::fs::write(
+            factions.join("greens.resources"),
+            r#"<resources><weapon key="ak47.weapon"></resources>"#,
+        )
+        .unwrap();
+        let faction_path = factions.join("greens.faction");
+        std::fs::write(
+            &faction_path,
+            r#"<faction name="Greens">
+                <soldier name="rifleman"><resources file="greens.resources"/></soldier>
+            </faction>"#,
+        )
+        .unwrap();
+
+        let mut diagnostics = Vec::new();
+        let faction = parse_faction_file(
+            &faction_path,
+            packages.path(),
+            "greens".into(),
+            "/game",
+            &mut Vec::new(),
+            &mut diagnostics,
+        )
+        .unwrap();
+
+        assert!(faction.partially_parsed);
+        assert_eq!(faction.soldiers[0].resources[0].key, "ak47.weapon");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+}
@@ -3,18 +3,27 @@
 //! Scans RWR game directory for item XML files (.carry_item, .visual_item, etc.),
 //! parses them, and returns structured item data to the frontend.
 
-use crate::utils::resolve_packages_dirs;
+use crate::attributes::{self, AttributeBag, AttributeTrace, Explanation, ListMerge, MergeRules};
+use crate::diagnostics::{
+    parse_recovering, parse_xml, read_file, Diagnostic, DiagnosticCode, Severity,
+};
+use crate::packages::{KeyDefinition, Provenance};
+use crate::resource_lists::{ResourceIndex, ResourceListKind, ResourceStatus};
+use crate::scan_cache::{Parsed, ScanCache};
+use crate::scan_jobs::ScanJobs;
+use crate::scanner::{self, PackageScanner, ScanOptions, ScanSummary};
+use crate::templates::{enter_template, inherit, Template};
+use crate::utils::{package_name_of, parse_flag, relative_file_path, resolve_texture_path};
+use crate::watch::{self, WatchRegistry};
 use crate::ScanEvent;
-use quick_xml::de::from_str;
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use tauri::ipc::Channel;
-use walkdir::WalkDir;
+use tauri::State;
 
-const BATCH_SIZE: usize = 50;
-const MAX_TEMPLATE_DEPTH: usize = 10;
+/// Folder of the vanilla package item templates fall back to
+const ITEMS_DIR: &str = "items";
 
 /// Unified item structure (all item types)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +80,20 @@ pub struct Item {
     pub capacity: Option<ItemCapacity>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub commonness: Option<ItemCommonness>,
+    /// The file is malformed XML; these fields were recovered from a repaired copy and
+    /// anything after the broken markup may be missing
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub partially_parsed: bool,
+    /// Effective scans only: the package this definition was taken from and what it overrides
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provenance: Option<Provenance>,
+    /// Whether the package's resource list registers this file; not cached, set per scan
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub resource_status: Option<ResourceStatus>,
+    /// Every XML attribute after template merging (e.g. `specification.stab_range`);
+    /// only sent when the scan asked for it
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub attributes: Option<AttributeBag>,
 }
 
 /// Item modifier
@@ -115,28 +138,23 @@ pub struct ItemCommonness {
     pub can_respawn_with: Option<bool>,
 }
 
-/// Error during item scanning
-#[derive(Debug, Serialize, Clone)]
-pub struct ScanError {
-    pub file: String,
-    pub error: String,
-    pub severity: String,
-}
-
 /// Item scan result
 #[derive(Debug, Serialize)]
 pub struct ItemScanResult {
     pub items: Vec<Item>,
-    pub errors: Vec<ScanError>,
-    #[serde(rename = "duplicateKeys")]
-    pub duplicate_keys: Vec<String>,
-    #[serde(rename = "scanTime")]
-    pub scan_time: u64,
+    #[serde(flatten)]
+    pub summary: ScanSummary,
 }
 
 /// Raw carry_item XML structure (for parsing)
 #[derive(Debug, Deserialize, Default)]
 struct RawCarryItem {
+    /// Every attribute of the element and the file declaring it, filled in after deserializing
+    #[serde(skip)]
+    attributes: AttributeTrace,
+    /// The file or a template of the chain was malformed and only parsed after repair
+    #[serde(skip)]
+    partially_parsed: bool,
     #[serde(rename = "@key", default)]
     key: Option<String>,
     #[serde(rename = "@file", default)]
@@ -225,8 +243,13 @@ struct RawModel {
 /// Raw visual_item XML structure (for parsing)
 #[derive(Debug, Deserialize, Default)]
 struct RawVisualItem {
+    /// Every attribute of the file and the file declaring it, filled in after deserializing
+    #[serde(skip)]
+    attributes: AttributeTrace,
+    /// The file was malformed and only parsed after repair
+    #[serde(skip)]
+    partially_parsed: bool,
     #[serde(rename = "@key", default)]
-    #[allow(dead_code)]
     key: Option<String>,
     #[serde(rename = "@file", default)]
     template_file: Option<String>,
@@ -253,223 +276,289 @@ struct RawEffect {
 /// `directory` / `game_path` may be either:
 /// - the `packages` directory
 /// - the game/workshop root that contains `media/` (we'll scan `media/packages`)
+///
+/// The scan runs in the background; the returned job id can be passed to `cancel_scan`.
+/// Every definition of a key is streamed; use `scan_items_collect` for the effective view.
 #[tauri::command]
 pub async fn scan_items(
     game_path: String,
     directory: Option<String>,
+    attributes: Option<bool>,
+    jobs: State<'_, ScanJobs>,
     on_event: Channel<ScanEvent<Item>>,
-) -> Result<(), String> {
-    let source_directory = directory.clone().unwrap_or_else(|| game_path.clone());
-    let package_roots = resolve_packages_dirs(Path::new(&source_directory));
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        attributes: attributes.unwrap_or(false),
+        ..Default::default()
+    };
+    scanner::spawn_scan_with::<ItemScanner>(&jobs, source_directory, options, on_event)
+}
 
-    #[cfg(debug_assertions)]
-    {
-        eprintln!(
-            "[scan_items] source_directory={} roots={}",
-            source_directory,
-            package_roots
-                .iter()
-                .map(|p| p.display().to_string())
-                .collect::<Vec<_>>()
-                .join(" | ")
-        );
-    }
+/// Fallback scan API (no IPC `Channel`).
+///
+/// With `effective` set, only the carry_item definition the game uses is returned for
+/// each key, carrying its `provenance`.
+#[tauri::command]
+pub async fn scan_items_collect(
+    game_path: String,
+    directory: Option<String>,
+    effective: Option<bool>,
+    attributes: Option<bool>,
+) -> Result<ItemScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        effective: effective.unwrap_or(false),
+        attributes: attributes.unwrap_or(false),
+    };
+    let scan = scanner::collect_scan::<ItemScanner>(&source_directory, options)?;
+    Ok(ItemScanResult {
+        items: scan.records,
+        summary: scan.summary,
+    })
+}
 
-    if package_roots.iter().all(|p| !p.exists()) {
-        let paths = package_roots
-            .iter()
-            .map(|p| p.display().to_string())
-            .collect::<Vec<_>>()
-            .join(", ");
-        return Err(format!("Directory not found (tried: {paths})"));
-    }
+/// Watch the packages roots of an items scan and stream add/update/remove deltas.
+///
+/// Call after `scan_items` with the same directory and channel; record ids match the
+/// scan's ids. Returns a watch id for `stop_watch`.
+#[tauri::command]
+pub async fn watch_items(
+    game_path: String,
+    directory: Option<String>,
+    attributes: Option<bool>,
+    registry: State<'_, WatchRegistry>,
+    on_event: Channel<ScanEvent<Item>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        attributes: attributes.unwrap_or(false),
+        ..Default::default()
+    };
+    watch::start_with::<ItemScanner>(&registry, source_directory, options, on_event)
+}
 
-    // Collect all relevant files across possible packages roots (macOS app bundle + external media).
-    let mut files: Vec<(PathBuf, PathBuf)> = Vec::new();
-    for root in &package_roots {
-        if !root.exists() {
-            continue;
-        }
-        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-            let path = entry.path();
-            if path
-                .extension()
-                .is_some_and(|ext| ext == "carry_item" || ext == "visual_item")
-            {
-                files.push((root.clone(), path.to_path_buf()));
-            }
-        }
+/// Explain where every attribute of an item file comes from along its `file="..."`
+/// template chain. `file_path` is the absolute path of a `.carry_item` or `.visual_item`
+/// file; carry_item files return one entry per `<carry_item>`.
+#[tauri::command]
+pub async fn explain_item(file_path: String) -> Result<Vec<Explanation>, String> {
+    let path = Path::new(&file_path);
+    let item_parent = path.parent().unwrap_or(path);
+
+    if path.extension().is_some_and(|ext| ext == "carry_item") {
+        let content = read_file(path).map_err(|diagnostic| diagnostic.message)?;
+        let raw_items =
+            parse_carry_items(&content, path).map_err(|diagnostic| diagnostic.message)?;
+        return Ok(raw_items
+            .into_iter()
+            .enumerate()
+            .map(|(index, raw_item)| {
+                let mut dependencies = Vec::new();
+                let mut errors = Vec::new();
+                let (raw, _) = resolve_carry_item(
+                    raw_item,
+                    index,
+                    path,
+                    item_parent,
+                    &mut dependencies,
+                    &mut errors,
+                );
+                Explanation::new(path, raw.key, &dependencies, raw.attributes, errors)
+            })
+            .collect());
     }
 
-    let send_event = |evt: ScanEvent<Item>| -> Result<(), String> {
-        on_event
-            .send(evt)
-            .map_err(|e| format!("Failed to send scan event: {e}"))
-    };
+    let mut dependencies = Vec::new();
+    let mut errors = Vec::new();
+    let raw = load_raw_visual_item(path, &mut dependencies, &mut errors)
+        .map_err(|diagnostic| diagnostic.message)?;
+    Ok(vec![Explanation::new(
+        path,
+        raw.key,
+        &dependencies,
+        raw.attributes,
+        errors,
+    )])
+}
 
-    #[cfg(debug_assertions)]
-    {
-        eprintln!("[scan_items] discovered_files={}", files.len());
+/// Export the effective carry_item `key` as a standalone `.carry_item` file with its
+/// template chain inlined. Writes to `output_path` when given and returns the XML either way.
+/// Like `export_weapon`, only attributes survive the export.
+///
+/// `items` takes the rows of an effective scan the frontend already holds; without them
+/// the packages are scanned to find `key`.
+#[tauri::command]
+pub async fn export_item(
+    game_path: String,
+    directory: Option<String>,
+    key: String,
+    output_path: Option<String>,
+    items: Option<Vec<Item>>,
+) -> Result<String, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let items = scanner::records_or_scan::<ItemScanner>(items, &source_directory)?;
+    let item = items
+        .iter()
+        .find(|i| i.item_type == "carry_item" && i.key.as_deref() == Some(key.as_str()))
+        .ok_or_else(|| format!("Item not found: {}", key))?;
+
+    // Items without a key attribute are found by their position in the file (id suffix)
+    let index = item.id.rsplit('_').next().and_then(|n| n.parse().ok());
+    let xml = standalone_carry_item(Path::new(&item.source_file), &key, index)?;
+    if let Some(output_path) = output_path {
+        std::fs::write(&output_path, &xml)
+            .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
     }
+    Ok(xml)
+}
 
-    if files.is_empty() {
-        // Nothing to scan, but still notify the frontend so it can stop loading.
-        send_event(ScanEvent::Finished)?;
-        return Ok(());
+/// `.carry_item` XML of the carry_item `key` (else the `index`-th one) in `path` with its
+/// template chain inlined. Malformed files are recovered like scans do; failed template
+/// resolution is an error.
+fn standalone_carry_item(path: &Path, key: &str, index: Option<usize>) -> Result<String, String> {
+    let content = read_file(path).map_err(|diagnostic| diagnostic.message)?;
+    let (raw_items, _) = parse_recovering(&content, &mut Vec::new(), |content| {
+        parse_carry_items(content, path)
+    })
+    .map_err(|diagnostic| diagnostic.message)?;
+    let index = raw_items
+        .iter()
+        .position(|raw| raw.key.as_deref() == Some(key))
+        .or(index)
+        .ok_or_else(|| format!("Item not found in {}: {}", path.display(), key))?;
+    let raw_item = raw_items
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| format!("Item not found in {}: {}", path.display(), key))?;
+
+    let (raw, template_error) = resolve_carry_item(
+        raw_item,
+        index,
+        path,
+        path.parent().unwrap_or(path),
+        &mut Vec::new(),
+        &mut Vec::new(),
+    );
+    if let Some(error) = template_error {
+        return Err(error);
     }
 
-    let total = files.len();
-    send_event(ScanEvent::Progress { current: 0, total })?;
-
-    // Parallel processing using rayon.
-    // Note: carry_item files may contain multiple <carry_item> elements, so we return Vec<Item>.
-    let all_results: Vec<Result<Vec<Item>, String>> = files
-        .into_par_iter()
-        .enumerate()
-        .map(|(index, (packages_root, path))| {
-            let file_str = path.to_string_lossy().to_string();
-            let is_carry_item = path.extension().is_some_and(|ext| ext == "carry_item");
-            let base_id = format!("{}_{}", file_str, index);
-
-            if is_carry_item {
-                parse_carry_item(&path, &packages_root, base_id, &source_directory)
-            } else {
-                parse_visual_item(&path, &packages_root, base_id, &source_directory)
-                    .map(|i| vec![i])
-            }
-        })
-        .collect();
+    let bag = attributes::standalone(&raw.attributes, path);
+    Ok(format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<carry_items>\n{}</carry_items>\n",
+        attributes::to_xml("carry_item", &bag, 1)
+    ))
+}
 
-    #[cfg(debug_assertions)]
-    {
-        let ok_count = all_results.iter().filter(|r| r.is_ok()).count();
-        let err_count = all_results.len() - ok_count;
-        let item_count: usize = all_results
-            .iter()
-            .filter_map(|r| r.as_ref().ok())
-            .map(|v| v.len())
-            .sum();
-        eprintln!(
-            "[scan_items] parsed_ok={} parsed_err={} parsed_items={}",
-            ok_count, err_count, item_count
-        );
-    }
+/// carry_item / visual_item scanner for the shared scanning framework
+pub(crate) struct ItemScanner;
 
-    // Send in batches
-    for (i, chunk) in all_results.chunks(BATCH_SIZE).enumerate() {
-        let items: Vec<Item> = chunk
-            .iter()
-            .filter_map(|r| r.as_ref().ok())
-            .flat_map(|v| v.iter().cloned())
-            .collect();
-        let errors: Vec<String> = chunk
-            .iter()
-            .filter_map(|r| r.as_ref().err().cloned())
-            .collect();
+impl PackageScanner for ItemScanner {
+    type Record = Item;
 
-        if !items.is_empty() {
-            send_event(ScanEvent::Chunk(items))?;
-        }
+    const KIND: &'static str = "items";
 
-        for error in errors {
-            send_event(ScanEvent::Error(error))?;
-        }
+    const RESOURCE_LIST: Option<ResourceListKind> = Some(ResourceListKind::CarryItems);
 
-        send_event(ScanEvent::Progress {
-            current: ((i + 1) * BATCH_SIZE).min(total),
-            total,
-        })?;
+    fn matches(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext == "carry_item" || ext == "visual_item")
     }
 
-    send_event(ScanEvent::Finished)?;
-
-    Ok(())
-}
+    fn parse_cached(
+        cache: &ScanCache<Item>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Item>, Diagnostic> {
+        parse_item_file_cached(cache, file, packages_root, index, source_directory)
+    }
 
-/// Fallback scan API (no IPC `Channel`).
-#[tauri::command]
-pub async fn scan_items_collect(
-    game_path: String,
-    directory: Option<String>,
-) -> Result<ItemScanResult, String> {
-    let started = std::time::Instant::now();
+    fn record_id(record: &Item) -> &str {
+        &record.id
+    }
 
-    let source_directory = directory.clone().unwrap_or_else(|| game_path.clone());
-    let package_roots = resolve_packages_dirs(Path::new(&source_directory));
+    fn apply_resource_index(record: &mut Item, index: &ResourceIndex) {
+        // visual_items are not registered through a list
+        if record.item_type == "carry_item" {
+            record.resource_status = Some(index.status(&record.source_file));
+        }
+    }
 
-    if package_roots.iter().all(|p| !p.exists()) {
-        let paths = package_roots
-            .iter()
-            .map(|p| p.display().to_string())
-            .collect::<Vec<_>>()
-            .join(", ");
-        return Err(format!("Directory not found (tried: {paths})"));
+    fn clear_attributes(record: &mut Item) {
+        record.attributes = None;
     }
 
-    let mut files: Vec<(PathBuf, PathBuf)> = Vec::new();
-    for root in &package_roots {
-        if !root.exists() {
-            continue;
-        }
-        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-            let path = entry.path();
-            if path
-                .extension()
-                .is_some_and(|ext| ext == "carry_item" || ext == "visual_item")
-            {
-                files.push((root.clone(), path.to_path_buf()));
-            }
+    /// Only carry_items are registered by key; visual_items are referenced by file name.
+    fn key_definition(record: &Item) -> Option<KeyDefinition> {
+        if record.item_type != "carry_item" {
+            return None;
         }
+        Some(KeyDefinition {
+            key: record.key.clone()?,
+            id: record.id.clone(),
+            package_name: record.package_name.clone(),
+            file_path: record.file_path.clone(),
+            source_file: record.source_file.clone(),
+        })
     }
 
-    if files.is_empty() {
-        return Ok(ItemScanResult {
-            items: vec![],
-            errors: vec![],
-            duplicate_keys: vec![],
-            scan_time: started.elapsed().as_millis() as u64,
-        });
+    fn set_provenance(record: &mut Item, provenance: Provenance) {
+        record.provenance = Some(provenance);
     }
+}
 
-    let all_results: Vec<Result<Vec<Item>, ScanError>> = files
-        .into_par_iter()
-        .enumerate()
-        .map(|(index, (packages_root, path))| {
-            let file_str = path.to_string_lossy().to_string();
-            let is_carry_item = path.extension().is_some_and(|ext| ext == "carry_item");
-            let base_id = format!("{}_{}", file_str, index);
-
-            let parsed = if is_carry_item {
-                parse_carry_item(&path, &packages_root, base_id, &source_directory)
-            } else {
-                parse_visual_item(&path, &packages_root, base_id, &source_directory)
-                    .map(|i| vec![i])
-            };
-
-            parsed.map_err(|e| ScanError {
-                file: file_str,
-                error: e,
-                severity: "error".to_string(),
-            })
-        })
-        .collect();
+/// Parse a carry_item/visual_item file through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits.
+fn parse_item_file_cached(
+    cache: &ScanCache<Item>,
+    path: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<Item>, Diagnostic> {
+    let file_str = path.to_string_lossy().to_string();
+    let is_carry_item = path.extension().is_some_and(|ext| ext == "carry_item");
+    let base_id = format!("{}_{}", file_str, index);
+
+    let mut parsed = cache.get_or_parse(path, |dependencies, diagnostics| {
+        if is_carry_item {
+            parse_carry_item(
+                path,
+                packages_root,
+                base_id.clone(),
+                source_directory,
+                dependencies,
+                diagnostics,
+            )
+        } else {
+            parse_visual_item(
+                path,
+                packages_root,
+                base_id.clone(),
+                source_directory,
+                dependencies,
+                diagnostics,
+            )
+            .map(|i| vec![i])
+        }
+    })?;
 
-    let items: Vec<Item> = all_results
-        .iter()
-        .filter_map(|r| r.as_ref().ok())
-        .flat_map(|v| v.iter().cloned())
-        .collect();
-    let errors: Vec<ScanError> = all_results
-        .iter()
-        .filter_map(|r| r.as_ref().err().cloned())
-        .collect();
+    let items = &mut parsed.records;
+    if is_carry_item {
+        for (i, item) in items.iter_mut().enumerate() {
+            item.id = format!("{}_{}", base_id, i);
+        }
+    } else if let Some(item) = items.first_mut() {
+        item.id = base_id;
+    }
+    for item in items.iter_mut() {
+        item.source_directory = source_directory.to_string();
+    }
 
-    Ok(ItemScanResult {
-        items,
-        errors,
-        duplicate_keys: vec![],
-        scan_time: started.elapsed().as_millis() as u64,
-    })
+    Ok(parsed)
 }
 
 #[derive(Debug, Clone)]
@@ -478,20 +567,8 @@ struct CarryTemplateSelector {
     index: usize,
 }
 
-fn resolve_item_template_path(base_dir: &Path, template_file: &str) -> PathBuf {
-    let candidate = base_dir.join(template_file);
-    if candidate.exists() {
-        return candidate;
-    }
-
-    if let Some(packages_dir) = base_dir.ancestors().nth(2) {
-        let vanilla_candidate = packages_dir.join("vanilla/items").join(template_file);
-        if vanilla_candidate.exists() {
-            return vanilla_candidate;
-        }
-    }
-
-    candidate
+fn selection_error(template_path: &Path, message: String) -> Diagnostic {
+    Diagnostic::error(DiagnosticCode::TemplateSelection, template_path, message)
 }
 
 fn resolve_carry_item_template(
@@ -499,32 +576,22 @@ fn resolve_carry_item_template(
     template_file: &str,
     selector: &CarryTemplateSelector,
     visited: &mut HashSet<PathBuf>,
-) -> Result<RawCarryItem, String> {
-    let template_path = resolve_item_template_path(base_dir, template_file);
-
-    if !visited.insert(template_path.clone()) {
-        return Err(format!(
-            "Circular carry_item template reference detected: {}",
-            template_file
-        ));
-    }
-
-    if visited.len() > MAX_TEMPLATE_DEPTH {
-        return Err(format!(
-            "carry_item template depth exceeded limit (>{})",
-            MAX_TEMPLATE_DEPTH
-        ));
-    }
-
-    let content = std::fs::read_to_string(&template_path).map_err(|e| {
-        format!(
-            "Failed to read carry_item template '{}': {e}",
-            template_path.display()
-        )
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(RawCarryItem, bool), Diagnostic> {
+    let template_path = enter_template(
+        base_dir,
+        template_file,
+        ITEMS_DIR,
+        "carry_item",
+        visited,
+        dependencies,
+    )?;
+
+    let content = read_file(&template_path)?;
+    let (mut template_items, mut repaired) = parse_recovering(&content, diagnostics, |content| {
+        parse_carry_items(content, &template_path)
     })?;
-    let root: RawCarryItemsRoot =
-        from_str(&content).map_err(|e| format!("carry_item template XML parse error: {e}"))?;
-    let mut template_items = root.items;
 
     let mut current = if let Some(key) = selector.key.as_ref() {
         template_items
@@ -539,18 +606,24 @@ fn resolve_carry_item_template(
             })
             .or_else(|| template_items.into_iter().nth(selector.index))
             .ok_or_else(|| {
-                format!(
-                    "Cannot select carry_item from template '{}': key='{}', index={}",
-                    template_path.display(),
-                    key,
-                    selector.index
+                selection_error(
+                    &template_path,
+                    format!(
+                        "Cannot select carry_item from template '{}': key='{}', index={}",
+                        template_path.display(),
+                        key,
+                        selector.index
+                    ),
                 )
             })?
     } else if template_items.len() == 1 {
         template_items.into_iter().next().ok_or_else(|| {
-            format!(
-                "Template '{}' does not contain carry_item entries",
-                template_path.display()
+            selection_error(
+                &template_path,
+                format!(
+                    "Template '{}' does not contain carry_item entries",
+                    template_path.display()
+                ),
             )
         })?
     } else if selector.index < template_items.len() {
@@ -558,90 +631,64 @@ fn resolve_carry_item_template(
             .into_iter()
             .nth(selector.index)
             .ok_or_else(|| {
-                format!(
-                    "Cannot select carry_item index {} from template '{}'",
-                    selector.index,
-                    template_path.display()
+                selection_error(
+                    &template_path,
+                    format!(
+                        "Cannot select carry_item index {} from template '{}'",
+                        selector.index,
+                        template_path.display()
+                    ),
                 )
             })?
     } else {
-        return Err(format!(
-            "Ambiguous carry_item template '{}': {} entries but index {} is out of range",
-            template_path.display(),
-            template_items.len(),
-            selector.index
+        return Err(selection_error(
+            &template_path,
+            format!(
+                "Ambiguous carry_item template '{}': {} entries but index {} is out of range",
+                template_path.display(),
+                template_items.len(),
+                selector.index
+            ),
         ));
     };
 
     if let Some(parent_file) = current.template_file.clone() {
-        let template_parent = template_path.parent().ok_or_else(|| {
-            format!(
-                "Cannot resolve parent directory for carry_item template '{}'",
-                template_path.display()
-            )
-        })?;
+        let template_parent = template_path.parent().unwrap_or(base_dir);
         let next_selector = CarryTemplateSelector {
             key: current.key.clone().or_else(|| selector.key.clone()),
             index: selector.index,
         };
-        let parent =
-            resolve_carry_item_template(template_parent, &parent_file, &next_selector, visited)?;
+        let (parent, parent_repaired) = resolve_carry_item_template(
+            template_parent,
+            &parent_file,
+            &next_selector,
+            visited,
+            dependencies,
+            diagnostics,
+        )?;
         current = merge_carry_item_attributes(parent, current);
+        repaired |= parent_repaired;
     }
 
     visited.remove(&template_path);
-    Ok(current)
+    Ok((current, repaired))
 }
 
-fn resolve_visual_item_template(
-    base_dir: &Path,
-    template_file: &str,
-    visited: &mut HashSet<PathBuf>,
-) -> Result<RawVisualItem, String> {
-    let template_path = resolve_item_template_path(base_dir, template_file);
-
-    if !visited.insert(template_path.clone()) {
-        return Err(format!(
-            "Circular visual_item template reference detected: {}",
-            template_file
-        ));
-    }
-
-    if visited.len() > MAX_TEMPLATE_DEPTH {
-        return Err(format!(
-            "visual_item template depth exceeded limit (>{})",
-            MAX_TEMPLATE_DEPTH
-        ));
-    }
-
-    let content = std::fs::read_to_string(&template_path).map_err(|e| {
-        format!(
-            "Failed to read visual_item template '{}': {e}",
-            template_path.display()
-        )
-    })?;
-    let mut current: RawVisualItem =
-        from_str(&content).map_err(|e| format!("visual_item template XML parse error: {e}"))?;
-
-    if let Some(parent_file) = current.template_file.clone() {
-        let template_parent = template_path.parent().ok_or_else(|| {
-            format!(
-                "Cannot resolve parent directory for visual_item template '{}'",
-                template_path.display()
-            )
-        })?;
-        let parent = resolve_visual_item_template(template_parent, &parent_file, visited)?;
-        current = merge_visual_item_attributes(parent, current);
-    }
-
-    visited.remove(&template_path);
-    Ok(current)
-}
+/// `<carry_item>` attribute paths merged like [`merge_carry_item_attributes`] merges the
+/// typed fields
+const CARRY_ITEM_MERGE: MergeRules = MergeRules {
+    lists: &[
+        (&["capacity"], ListMerge::Replace),
+        (&["modifier"], ListMerge::Replace),
+    ],
+    not_inherited: &["key"],
+};
 
 fn merge_carry_item_attributes(parent: RawCarryItem, mut child: RawCarryItem) -> RawCarryItem {
-    if child.key.is_none() {
-        child.key = parent.key;
-    }
+    child.attributes =
+        attributes::merge_traces(parent.attributes, child.attributes, &CARRY_ITEM_MERGE);
+    // The key is never inherited: a keyless variant registering its template's key would
+    // clash with the template's own definition
     if child.name.is_none() {
         child.name = parent.name;
     }
@@ -676,10 +723,34 @@ fn merge_carry_item_attributes(parent: RawCarryItem, mut child: RawCarryItem) ->
     child
 }
 
-fn merge_visual_item_attributes(parent: RawVisualItem, mut child: RawVisualItem) -> RawVisualItem {
-    if child.key.is_none() {
-        child.key = parent.key;
+impl Template for RawVisualItem {
+    const VANILLA_DIR: &'static str = ITEMS_DIR;
+    const LABEL: &'static str = "visual_item";
+
+    fn parse(content: &str, path: &Path) -> Result<Self, Diagnostic> {
+        let mut raw: RawVisualItem = parse_xml(content, path)?;
+        raw.attributes = visual_item_attributes(content, path);
+        Ok(raw)
     }
+
+    fn template_file(&self) -> Option<String> {
+        self.template_file.clone()
+    }
+
+    fn merge(parent: Self, child: Self) -> Self {
+        merge_visual_item_attributes(parent, child)
+    }
+}
+
+fn merge_visual_item_attributes(parent: RawVisualItem, mut child: RawVisualItem) -> RawVisualItem {
+    child.attributes = attributes::merge_traces(
+        parent.attributes,
+        child.attributes,
+        &MergeRules {
+            lists: &[(&["model"], ListMerge::Replace)],
+            not_inherited: &["key"],
+        },
+    );
     if child.template_file.is_none() {
         child.template_file = parent.template_file;
     }
@@ -763,19 +834,88 @@ fn merge_model(parent: Option<RawModel>, child: Option<RawModel>) -> Option<RawM
     }
 }
 
-/// Parse a carry_item XML file (may contain multiple carry_item elements)
+/// Merge the template chain of the `index`-th carry_item of `path` into it.
+/// Template failures leave the item as declared, are reported as warnings and come back as
+/// the template error message.
+fn resolve_carry_item(
+    mut raw: RawCarryItem,
+    index: usize,
+    path: &Path,
+    item_parent: &Path,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (RawCarryItem, Option<String>) {
+    let mut template_error = None;
+    if let Some(template_file) = raw.template_file.clone() {
+        let selector = CarryTemplateSelector {
+            key: raw.key.clone(),
+            index,
+        };
+        let chain_start = dependencies.len();
+        match resolve_carry_item_template(
+            item_parent,
+            &template_file,
+            &selector,
+            &mut HashSet::new(),
+            dependencies,
+            diagnostics,
+        ) {
+            Ok((parent, repaired)) => {
+                raw = merge_carry_item_attributes(parent, raw);
+                raw.partially_parsed |= repaired;
+            }
+            Err(diagnostic) => {
+                template_error = Some(format!(
+                    "Template resolution failed: {}",
+                    diagnostic.message
+                ));
+                diagnostics.push(
+                    diagnostic
+                        .with_severity(Severity::Warning)
+                        .with_template_chain(path, &dependencies[chain_start..]),
+                );
+            }
+        }
+    }
+    (raw, template_error)
+}
+
+/// Read a visual_item file and merge its template chain into it.
+/// Template failures leave the item as declared and are reported as warnings.
+fn load_raw_visual_item(
+    path: &Path,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<RawVisualItem, Diagnostic> {
+    let content = read_file(path)?;
+
+    let (mut raw, partially_parsed) = parse_recovering(&content, diagnostics, |content| {
+        RawVisualItem::parse(content, path)
+    })?;
+    raw.partially_parsed = partially_parsed;
+
+    let (mut raw, template_repaired) = inherit(raw, path, dependencies, diagnostics);
+    raw.partially_parsed |= template_repaired;
+    Ok(raw)
+}
+
+/// Parse a carry_item XML file (may contain multiple carry_item elements).
+/// Every template file consulted is appended to `dependencies`.
 fn parse_carry_item(
     path: &Path,
     input_path: &Path,
     base_id: String,
     source_directory: &str,
-) -> Result<Vec<Item>, String> {
-    let content =
-        std::fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
-
-    // Parse as <carry_items> root with multiple <carry_item> children
-    let raw_root: RawCarryItemsRoot =
-        from_str(&content).map_err(|e| format!("XML parse error: {}", e))?;
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Vec<Item>, Diagnostic> {
+    let content = read_file(path)?;
+
+    // Parse as <carry_items> root with multiple <carry_item> children, recovering what we
+    // can from malformed files
+    let (raw_items, partially_parsed) = parse_recovering(&content, diagnostics, |content| {
+        parse_carry_items(content, path)
+    })?;
 
     let file_name = path
         .file_stem()
@@ -783,46 +923,23 @@ fn parse_carry_item(
         .unwrap_or("")
         .to_string();
 
-    let package_name = path
-        .ancestors()
-        .skip_while(|p| *p != input_path)
-        .nth(1)
-        .and_then(|p| p.file_name())
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    let package_name = package_name_of(path, input_path);
 
-    // Calculate relative file path from packages directory
-    // e.g., "vanilla/items/vest2.carry_item"
-    let file_path = path
-        .strip_prefix(input_path)
-        .unwrap_or(path)
-        .to_string_lossy()
-        .trim_start_matches('/')
-        .to_string();
+    let file_path = relative_file_path(path, input_path);
 
     let mut items = Vec::new();
-    let item_parent = path
-        .parent()
-        .ok_or_else(|| "Cannot get parent directory of carry_item file".to_string())?;
-
-    for (index, raw_item) in raw_root.items.into_iter().enumerate() {
-        let mut raw = raw_item;
-
-        if let Some(template_file) = raw.template_file.clone() {
-            let selector = CarryTemplateSelector {
-                key: raw.key.clone(),
-                index,
-            };
-            if let Ok(parent) = resolve_carry_item_template(
-                item_parent,
-                &template_file,
-                &selector,
-                &mut HashSet::new(),
-            ) {
-                raw = merge_carry_item_attributes(parent, raw);
-            }
-        }
+    let item_parent = path.parent().unwrap_or(input_path);
+
+    for (index, mut raw_item) in raw_items.into_iter().enumerate() {
+        raw_item.partially_parsed = partially_parsed;
+        let (raw, _) = resolve_carry_item(
+            raw_item,
+            index,
+            path,
+            item_parent,
+            dependencies,
+            diagnostics,
+        );
 
         let modifiers = raw
             .modifiers
@@ -832,7 +949,7 @@ fn parse_carry_item(
                 value: m.value,
                 input_character_state: m.input_character_state.clone(),
                 output_character_state: m.output_character_state.clone(),
-                consumes_item: m.consumes_item.as_ref().and_then(|s| s.parse().ok()),
+                consumes_item: parse_flag(m.consumes_item.as_deref()),
             })
             .collect();
 
@@ -849,20 +966,14 @@ fn parse_carry_item(
             item_type: "carry_item".to_string(),
             encumbrance: raw.inventory.as_ref().and_then(|i| i.encumbrance),
             price: raw.inventory.as_ref().and_then(|i| i.price),
-            can_respawn_with: raw.commonness.as_ref().and_then(|c| {
-                c.can_respawn_with.as_ref().and_then(|s| match s.trim() {
-                    "1" => Some(true),
-                    "0" => Some(false),
-                    _ => None,
-                })
-            }),
-            in_stock: raw.commonness.as_ref().and_then(|c| {
-                c.in_stock.as_ref().and_then(|s| match s.trim() {
-                    "1" => Some(true),
-                    "0" => Some(false),
-                    _ => None,
-                })
-            }),
+            can_respawn_with: raw
+                .commonness
+                .as_ref()
+                .and_then(|c| parse_flag(c.can_respawn_with.as_deref())),
+            in_stock: raw
+                .commonness
+                .as_ref()
+                .and_then(|c| parse_flag(c.in_stock.as_deref())),
             file_path: file_path.clone(),
             source_file: path.display().to_string(),
             source_directory: source_directory.to_string(),
@@ -887,17 +998,13 @@ fn parse_carry_item(
             }),
             commonness: raw.commonness.as_ref().map(|rc| ItemCommonness {
                 value: rc.value,
-                in_stock: rc.in_stock.as_ref().and_then(|s| match s.trim() {
-                    "1" => Some(true),
-                    "0" => Some(false),
-                    _ => None,
-                }),
-                can_respawn_with: rc.can_respawn_with.as_ref().and_then(|s| match s.trim() {
-                    "1" => Some(true),
-                    "0" => Some(false),
-                    _ => None,
-                }),
+                in_stock: parse_flag(rc.in_stock.as_deref()),
+                can_respawn_with: parse_flag(rc.can_respawn_with.as_deref()),
             }),
+            partially_parsed: raw.partially_parsed,
+            provenance: None,
+            resource_status: None,
+            attributes: Some(attributes::resolved(&raw.attributes)),
         };
         items.push(item);
     }
@@ -905,29 +1012,17 @@ fn parse_carry_item(
     Ok(items)
 }
 
-/// Parse a visual_item XML file
+/// Parse a visual_item XML file.
+/// Every template file consulted is appended to `dependencies`.
 fn parse_visual_item(
     path: &Path,
     input_path: &Path,
     id: String,
     source_directory: &str,
-) -> Result<Item, String> {
-    let content =
-        std::fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let mut raw: RawVisualItem =
-        from_str(&content).map_err(|e| format!("XML parse error: {}", e))?;
-
-    if let Some(template_file) = raw.template_file.clone() {
-        let item_parent = path
-            .parent()
-            .ok_or_else(|| "Cannot get parent directory of visual_item file".to_string())?;
-        if let Ok(parent) =
-            resolve_visual_item_template(item_parent, &template_file, &mut HashSet::new())
-        {
-            raw = merge_visual_item_attributes(parent, raw);
-        }
-    }
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Item, Diagnostic> {
+    let raw = load_raw_visual_item(path, dependencies, diagnostics)?;
 
     let file_name = path
         .file_stem()
@@ -935,14 +1030,7 @@ fn parse_visual_item(
         .unwrap_or("")
         .to_string();
 
-    let package_name = path
-        .ancestors()
-        .skip_while(|p| *p != input_path)
-        .nth(1)
-        .and_then(|p| p.file_name())
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    let package_name = package_name_of(path, input_path);
 
     let mesh_filenames: Vec<String> = raw
         .models
@@ -950,14 +1038,7 @@ fn parse_visual_item(
         .filter_map(|m| m.mesh_filename.clone())
         .collect();
 
-    // Calculate relative file path from packages directory
-    // e.g., "vanilla/items/mesh_name.visual_item"
-    let file_path = path
-        .strip_prefix(input_path)
-        .unwrap_or(path)
-        .to_string_lossy()
-        .trim_start_matches('/')
-        .to_string();
+    let file_path = relative_file_path(path, input_path);
 
     Ok(Item {
         id,
@@ -983,9 +1064,35 @@ fn parse_visual_item(
         effect_ref: raw.effect.and_then(|e| e.effect_ref),
         capacity: None,
         commonness: None,
+        partially_parsed: raw.partially_parsed,
+        provenance: None,
+        resource_status: None,
+        attributes: Some(attributes::resolved(&raw.attributes)),
     })
 }
 
+/// Parse the `<carry_item>` entries of a file, each with its attribute bag
+fn parse_carry_items(content: &str, path: &Path) -> Result<Vec<RawCarryItem>, Diagnostic> {
+    let root: RawCarryItemsRoot = parse_xml(content, path)?;
+    let mut items = root.items;
+    for (item, trace) in
+        items
+            .iter_mut()
+            .zip(attributes::attribute_traces(content, "carry_item", path))
+    {
+        item.attributes = trace;
+    }
+    Ok(items)
+}
+
+/// Attribute trace of the `<visual_item>` root element
+fn visual_item_attributes(content: &str, file: &Path) -> AttributeTrace {
+    attributes::attribute_traces(content, "visual_item", file)
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
 /// Root container for carry_item XML files
 #[derive(Debug, Deserialize)]
 struct RawCarryItemsRoot {
@@ -1000,32 +1107,8 @@ pub async fn get_item_texture_path(
     item_file_path: String,
     icon_filename: String,
 ) -> Result<String, String> {
-    use std::path::PathBuf;
-
-    let item_path = PathBuf::from(&item_file_path);
-    let item_dir = item_path
-        .parent()
-        .ok_or("Invalid item path: cannot get parent directory")?;
-
     // textures/ is a sibling to items/ folder
-    let textures_dir = item_dir
-        .parent()
-        .ok_or("Invalid item path: cannot get grandparent directory")?
-        .join("textures");
-
-    let icon_path = textures_dir.join(&icon_filename);
-
-    if !icon_path.exists() {
-        return Err(format!(
-            "Icon file not found: {} (expected at: {})",
-            icon_filename,
-            icon_path.display()
-        ));
-    }
-
-    let canonical = icon_path
-        .canonicalize()
-        .map_err(|e| format!("Failed to resolve icon path: {}", e))?;
+    let canonical = resolve_texture_path(Path::new(&item_file_path), &icon_filename)?;
 
     Ok(canonical.to_string_lossy().to_string())
 }
@@ -1039,28 +1122,9 @@ pub async fn get_item_icon_base64(
 ) -> Result<String, String> {
     use base64::Engine;
     use std::fs;
-    use std::path::PathBuf;
-
-    let item_path = PathBuf::from(&item_file_path);
-    let item_dir = item_path
-        .parent()
-        .ok_or("Invalid item path: cannot get parent directory")?;
 
     // textures/ is a sibling to items/ folder
-    let textures_dir = item_dir
-        .parent()
-        .ok_or("Invalid item path: cannot get grandparent directory")?
-        .join("textures");
-
-    let icon_path = textures_dir.join(&icon_filename);
-
-    if !icon_path.exists() {
-        return Err(format!(
-            "Icon file not found: {} (expected at: {})",
-            icon_filename,
-            icon_path.display()
-        ));
-    }
+    let icon_path = resolve_texture_path(Path::new(&item_file_path), &icon_filename)?;
 
     // Read image file
     let image_data =
@@ -1080,3 +1144,271 @@ pub async fn get_item_icon_base64(
 
     Ok(format!("data:{};base64,{}", mime_type, base64_string))
 }
+
+/// Carry_item `key` of the vanilla package with every other field empty, for tests
+#[cfg(test)]
+pub(crate) fn test_item(key: &str) -> Item {
+    Item {
+        id: key.to_string(),
+        key: Some(key.to_string()),
+        name: key.to_string(),
+        item_type: "carry_item".to_string(),
+        encumbrance: None,
+        price: None,
+        can_respawn_with: None,
+        in_stock: None,
+        file_path: format!("vanilla/items/{}", key),
+        source_file: String::new(),
+        source_directory: String::new(),
+        package_name: "vanilla".to_string(),
+        slot: None,
+        transform_on_consume: None,
+        time_to_live: None,
+        draggable: None,
+        modifiers: None,
+        hud_icon: None,
+        model_filename: None,
+        mesh_filenames: None,
+        effect_ref: None,
+        capacity: None,
+        commonness: None,
+        partially_parsed: false,
+        provenance: None,
+        resource_status: None,
+        attributes: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_items_built_on_a_repaired_template() {
+        let packages = tempfile::tempdir().unwrap();
+        let items = packages.path().join("vanilla/items");
+        std::fs::create_dir_all(&items).unwrap();
+        std::fs::write(
+            items.join("vest.carry_item"),
+            r#"<carry_items><carry_item key="vest.carry_item" name="Salt & Pepper" /></carry_items>"#,
+        )
+        .unwrap();
+        let variant = items.join("variant.carry_item");
+        std::fs::write(
+            &variant,
+            r#"<carry_items><carry_item key="variant.carry_item" file="vest.carry_item" /></carry_items>"#,
+        )
+        .unwrap();
+
+        let mut diagnostics = Vec::new();
+        let item = parse_carry_item(
+            &variant,
+            packages.path(),
+            "variant".to_string(),
+            "",
+            &mut Vec::new(),
+            &mut diagnostics,
+        )
+        .unwrap()
+        .remove(0);
+
+        assert_eq!(item.name, "Salt & Pepper");
+        assert!(item.partially_parsed);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn reads_consumes_item_as_a_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let items = packages.join("vanilla/items");
+        std::fs::create_dir_all(&items).unwrap();
+        let path = items.join("medikit.carry_item");
+        std::fs::write(
+            &path,
+            r#"<carry_items>
+                <carry_item key="medikit.carry_item" name="Medikit">
+                    <modifier class="wound" input_character_state="wounded" output_character_state="normal" consumes_item="1" />
+                    <modifier class="wound" input_character_state="unwounded" consumes_item="0" />
+                </carry_item>
+            </carry_items>"#,
+        )
+        .unwrap();
+
+        let parsed = parse_carry_item(
+            &path,
+            packages,
+            "medikit".to_string(),
+            &packages.to_string_lossy(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+        let modifiers = parsed[0].modifiers.as_deref().unwrap_or_default();
+        let flags: Vec<_> = modifiers.iter().map(|m| m.consumes_item).collect();
+        assert_eq!(flags, [Some(true), Some(false)]);
+    }
+
+    #[test]
+    fn attribute_bag_agrees_with_the_typed_merge() {
+        let packages = tempfile::tempdir().unwrap();
+        let items = packages.path().join("vanilla/items");
+        std::fs::create_dir_all(&items).unwrap();
+        std::fs::write(
+            items.join("vest.carry_item"),
+            r#"<carry_items>
+                <carry_item key="vest.carry_item" name="Vest" slot="1">
+                    <inventory encumbrance="10" price="2" />
+                    <modifier class="speed" value="-0.1" />
+                    <modifier class="hit_success_probability" value="0.5" />
+                </carry_item>
+            </carry_items>"#,
+        )
+        .unwrap();
+        let variant = items.join("variant.carry_item");
+        std::fs::write(
+            &variant,
+            r#"<carry_items>
+                <carry_item file="vest.carry_item" name="Variant">
+                    <modifier class="speed" value="-0.2" />
+                </carry_item>
+            </carry_items>"#,
+        )
+        .unwrap();
+
+        let item = parse_carry_item(
+            &variant,
+            packages.path(),
+            "variant".to_string(),
+            "",
+            &mut Vec::new(),
+            &mut Vec::new(),
+        )
+        .unwrap()
+        .remove(0);
+        let bag = item.attributes.clone().unwrap();
+
+        assert!(!bag.contains_key("key"));
+        assert_eq!(item.key.as_deref(), Some("variant_0"));
+        assert_eq!(bag["name"], item.name);
+        assert_eq!(bag["slot"], "1");
+        assert_eq!(bag["inventory.encumbrance"], "10");
+        assert_eq!(item.encumbrance, Some(10.0));
+
+        let modifiers = item.modifiers.as_deref().unwrap_or_default();
+        assert_eq!(modifiers.len(), 1);
+        assert_eq!(bag["modifier.class"], modifiers[0].modifier_class);
+        assert_eq!(bag["modifier.value"], "-0.2");
+        assert!(!bag.keys().any(|path| path.starts_with("modifier[1]")));
+    }
+
+    #[test]
+    fn keyless_variants_do_not_duplicate_their_template_key() {
+        let game = tempfile::tempdir().unwrap();
+        let items = game.path().join("packages/vanilla/items");
+        std::fs::create_dir_all(&items).unwrap();
+        std::fs::write(
+            items.join("vest.carry_item"),
+            r#"<carry_items><carry_item key="vest.carry_item" name="Vest" /></carry_items>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            items.join("variant.carry_item"),
+            r#"<carry_items><carry_item file="vest.carry_item" name="Variant" /></carry_items>"#,
+        )
+        .unwrap();
+
+        let scan = scanner::collect_scan::<ItemScanner>(
+            &game.path().to_string_lossy(),
+            Default::default(),
+        )
+        .unwrap();
+        let mut keys: Vec<_> = scan.records.iter().filter_map(|i| i.key.clone()).collect();
+        keys.sort();
+        assert_eq!(keys, ["variant_0", "vest.carry_item"]);
+        assert!(scan.summary.duplicate_keys.is_empty());
+    }
+
+    #[test]
+    fn effective_scan_keeps_a_template_and_its_keyless_variant() {
+        let game = tempfile::tempdir().unwrap();
+        let items = game.path().join("packages/vanilla/items");
+        std::fs::create_dir_all(&items).unwrap();
+        std::fs::write(
+            items.join("vest.carry_item"),
+            r#"<carry_items><carry_item key="vest.carry_item" name="Vest" /></carry_items>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            items.join("variant.carry_item"),
+            r#"<carry_items><carry_item file="vest.carry_item" name="Variant" /></carry_items>"#,
+        )
+        .unwrap();
+
+        let options = ScanOptions {
+            effective: true,
+            ..Default::default()
+        };
+        let scan =
+            scanner::collect_scan::<ItemScanner>(&game.path().to_string_lossy(), options).unwrap();
+        let mut names: Vec<_> = scan.records.iter().map(|i| i.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["Variant", "Vest"]);
+        assert!(scan
+            .records
+            .iter()
+            .all(|i| i.provenance.as_ref().is_some_and(|p| p.shadowed.is_empty())));
+    }
+
+    #[test]
+    fn exports_a_keyless_variant_without_its_template_key() {
+        let game = tempfile::tempdir().unwrap();
+        let items = game.path().join("packages/vanilla/items");
+        std::fs::create_dir_all(&items).unwrap();
+        std::fs::write(
+            items.join("vest.carry_item"),
+            r#"<carry_items>
+                <carry_item key="vest.carry_item" name="Vest"><inventory encumbrance="10" /></carry_item>
+            </carry_items>"#,
+        )
+        .unwrap();
+        let variant = items.join("variant.carry_item");
+        std::fs::write(
+            &variant,
+            r#"<carry_items><carry_item file="vest.carry_item" name="Vest & Co" /></carry_items>"#,
+        )
+        .unwrap();
+
+        let game_path = game.path().to_string_lossy().to_string();
+        let scan = scanner::collect_scan::<ItemScanner>(&game_path, Default::default()).unwrap();
+        let key_of = |name: &str| {
+            scan.records
+                .iter()
+                .find(|i| i.name == name)
+                .and_then(|i| i.key.clone())
+                .unwrap()
+        };
+        let export = |key: String| {
+            tauri::async_runtime::block_on(export_item(game_path.clone(), None, key, None, None))
+        };
+
+        let template_xml = export(key_of("Vest")).unwrap();
+        let xml = export(key_of("Vest & Co")).unwrap();
+        assert_ne!(xml, template_xml);
+        let bag = attributes::attribute_bags(&xml, "carry_item").remove(0);
+        assert_eq!(bag.get("key"), None);
+        assert_eq!(bag["name"], "Vest & Co");
+        assert_eq!(bag["inventory.encumbrance"], "10");
+        let template_bag = attributes::attribute_bags(&template_xml, "carry_item").remove(0);
+        assert_eq!(template_bag["key"], "vest.carry_item");
+        assert_eq!(template_bag["name"], "Vest");
+
+        std::fs::write(
+            &variant,
+            r#"<carry_items><carry_item file="missing.carry_item" /></carry_items>"#,
+        )
+        .unwrap();
+        assert!(export(key_of("Vest & Co")).is_err());
+    }
+}
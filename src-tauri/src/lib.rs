@@ -1,13 +1,35 @@
+mod attributes;
+mod calls;
+mod chains;
+mod characters;
+mod consumables;
+mod diagnostics;
 mod directories;
 mod events;
+mod factions;
 mod hotkeys;
 mod items;
+mod lint;
+mod maps;
+mod metrics;
+mod packages;
 mod ping;
+mod projectiles;
+mod references;
+mod resource_lists;
 mod rwrmi;
+mod scan_cache;
+mod scan_jobs;
+mod scanner;
+mod schema;
 mod steam_launch;
+mod templates;
 mod utils;
+mod vehicles;
 mod version_check;
+mod watch;
 mod weapons;
+mod xml_repair;
 
 pub use events::ScanEvent;
 
@@ -268,6 +290,8 @@ pub fn run() {
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_dialog::init())
+        .manage(scan_jobs::ScanJobs::default())
+        .manage(watch::WatchRegistry::default())
         .invoke_handler(tauri::generate_handler![
             proxy_fetch,
             get_system_theme,
@@ -297,13 +321,49 @@ pub fn run() {
             weapons::validate_game_path,
             weapons::scan_weapons,
             weapons::scan_weapons_collect,
+            weapons::watch_weapons,
+            weapons::explain_weapon,
+            weapons::export_weapon,
+            chains::weapon_chains,
+            chains::weapon_chain,
             weapons::open_file_in_editor,
             weapons::get_texture_path,
             weapons::get_weapon_icon_base64,
             items::scan_items,
             items::scan_items_collect,
+            items::watch_items,
+            items::explain_item,
+            items::export_item,
+            consumables::item_consumption,
+            consumables::item_consumable,
+            references::scan_references,
+            references::find_references,
+            lint::lint_package,
+            schema::infer_attribute_schema,
+            schema::check_attributes,
             items::get_item_texture_path,
             items::get_item_icon_base64,
+            projectiles::scan_projectiles,
+            projectiles::scan_projectiles_collect,
+            projectiles::watch_projectiles,
+            vehicles::scan_vehicles,
+            vehicles::scan_vehicles_collect,
+            vehicles::watch_vehicles,
+            factions::scan_factions,
+            factions::scan_factions_collect,
+            factions::watch_factions,
+            factions::faction_loadouts,
+            maps::scan_maps,
+            maps::scan_maps_collect,
+            calls::scan_calls,
+            calls::scan_calls_collect,
+            calls::watch_calls,
+            characters::scan_characters,
+            characters::scan_characters_collect,
+            characters::character_links,
+            scan_cache::clear_scan_cache,
+            scan_jobs::cancel_scan,
+            watch::stop_watch,
             directories::validate_directory,
             directories::validate_game_install_directory,
             steam_launch::steam_check_rwr_available,
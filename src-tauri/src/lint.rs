@@ -0,0 +1,299 @@
+//! Package linter
+//!
+//! [`lint_package`] reports every reference of one package that the game would fail to
+//! resolve, so a modder can fix them before shipping:
+//!
+//! - template, projectile and resource list problems, as reported by the weapon, item and
+//!   projectile scans
+//! - hud icons `get_texture_path` can't find in the sibling `textures/` folder
+//! - carry_item `model_filename`s and visual_item `mesh_filenames`
+//! - visual_item effect refs
+//! - `<sound fileref="...">` files of weapons and items
+//!
+//! Models, meshes, effects and sounds are looked up by file name in every package of the
+//! scan, since the game resolves them across packages.
+
+use crate::attributes::AttributeBag;
+use crate::diagnostics::{Diagnostic, DiagnosticCode, Severity};
+use crate::items::{scan_items_collect, Item};
+use crate::projectiles::scan_projectiles_collect;
+use crate::scanner::existing_package_roots;
+use crate::utils::resolve_texture_path;
+use crate::weapons::{scan_weapons_collect, Weapon};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Attributes of a `<sound>` element naming a sound file
+const SOUND_FILE_ATTRIBUTES: &[&str] = &["fileref", "filename", "file"];
+
+/// Lint report of one package
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageLint {
+    pub package_name: String,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub info_count: usize,
+    /// Most severe first; severities without issues are left out
+    pub groups: Vec<SeverityGroup>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeverityGroup {
+    pub severity: Severity,
+    /// Sorted by file
+    pub files: Vec<FileIssues>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileIssues {
+    /// Absolute path of the package file the issues belong to
+    pub file: String,
+    pub issues: Vec<Diagnostic>,
+}
+
+/// Report the unresolved references of package `package_name` below the packages roots of
+/// `directory` (or `game_path`).
+#[tauri::command]
+pub async fn lint_package(
+    game_path: String,
+    directory: Option<String>,
+    package_name: String,
+) -> Result<PackageLint, String> {
+    let source_directory = directory.clone().unwrap_or_else(|| game_path.clone());
+    let package_roots = existing_package_roots(&source_directory)?;
+    let package_dirs: Vec<PathBuf> = package_roots
+        .iter()
+        .map(|root| root.join(&package_name))
+        .filter(|dir| dir.is_dir())
+        .collect();
+    if package_dirs.is_empty() {
+        return Err(format!("Package not found: {}", package_name));
+    }
+
+    let weapons =
+        scan_weapons_collect(game_path.clone(), directory.clone(), None, Some(true)).await?;
+    let items = scan_items_collect(game_path.clone(), directory.clone(), None, Some(true)).await?;
+    let projectiles = scan_projectiles_collect(game_path, directory, None).await?;
+
+    let in_package = |file: &str| {
+        package_dirs
+            .iter()
+            .any(|dir| Path::new(file).starts_with(dir))
+    };
+    let file_names = file_names(&package_roots);
+
+    let mut issues: Vec<Diagnostic> = weapons
+        .summary
+        .errors
+        .into_iter()
+        .chain(items.summary.errors)
+        .chain(projectiles.summary.errors)
+        .filter(|d| in_package(linted_file(d)))
+        .collect();
+    for weapon in weapons
+        .weapons
+        .iter()
+        .filter(|w| in_package(&w.source_file))
+    {
+        issues.extend(lint_weapon(weapon, &file_names));
+    }
+    for item in items.items.iter().filter(|i| in_package(&i.source_file)) {
+        issues.extend(lint_item(item, &file_names));
+    }
+
+    Ok(group_issues(package_name, issues))
+}
+
+/// File names found below every packages root
+fn file_names(package_roots: &[PathBuf]) -> HashSet<String> {
+    package_roots
+        .iter()
+        .flat_map(|root| WalkDir::new(root).into_iter().filter_map(|e| e.ok()))
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect()
+}
+
+/// The package file a diagnostic is about: the scanned file for template problems
+/// (which point at the missing template), otherwise the file it points at
+fn linted_file(diagnostic: &Diagnostic) -> &str {
+    diagnostic
+        .template_chain
+        .first()
+        .unwrap_or(&diagnostic.file)
+}
+
+fn lint_weapon(weapon: &Weapon, file_names: &HashSet<String>) -> Vec<Diagnostic> {
+    let file = Path::new(&weapon.source_file);
+    let mut issues: Vec<Diagnostic> = weapon
+        .hud_icon
+        .iter()
+        .filter_map(|icon| missing_hud_icon(file, icon))
+        .collect();
+    if let Some(bag) = &weapon.attributes {
+        issues.extend(missing_sounds(file, bag, file_names));
+    }
+    issues
+}
+
+fn lint_item(item: &Item, file_names: &HashSet<String>) -> Vec<Diagnostic> {
+    let file = Path::new(&item.source_file);
+    let mut issues: Vec<Diagnostic> = item
+        .hud_icon
+        .iter()
+        .filter_map(|icon| missing_hud_icon(file, icon))
+        .collect();
+
+    let models = item
+        .model_filename
+        .iter()
+        .chain(item.mesh_filenames.iter().flatten());
+    for model in models {
+        if !file_names.contains(model.as_str()) {
+            issues.push(missing(
+                file,
+                Severity::Error,
+                format!("Model file not found: {}", model),
+            ));
+        }
+    }
+    if let Some(effect) = &item.effect_ref {
+        if !file_names.contains(effect.as_str()) {
+            issues.push(missing(
+                file,
+                Severity::Warning,
+                format!("Effect not found: {}", effect),
+            ));
+        }
+    }
+    if let Some(bag) = &item.attributes {
+        issues.extend(missing_sounds(file, bag, file_names));
+    }
+    issues
+}
+
+fn missing_hud_icon(file: &Path, icon: &str) -> Option<Diagnostic> {
+    resolve_texture_path(file, icon)
+        .err()
+        .map(|message| missing(file, Severity::Warning, message))
+}
+
+/// `<sound>` elements (`sound`, `sound[1]`, `projectile.sound`, ...) naming missing files
+fn missing_sounds(
+    file: &Path,
+    bag: &AttributeBag,
+    file_names: &HashSet<String>,
+) -> Vec<Diagnostic> {
+    bag.iter()
+        .filter(|(path, _)| {
+            let mut segments = path.rsplit('.');
+            let attribute = segments.next().unwrap_or_default();
+            let element = segments.next().unwrap_or_default();
+            let element = element.split('[').next().unwrap_or_default();
+            element == "sound" && SOUND_FILE_ATTRIBUTES.contains(&attribute)
+        })
+        .filter(|(_, sound)| !file_names.contains(sound.as_str()))
+        .map(|(_, sound)| {
+            missing(
+                file,
+                Severity::Warning,
+                format!("Sound file not found: {}", sound),
+            )
+        })
+        .collect()
+}
+
+fn missing(file: &Path, severity: Severity, message: String) -> Diagnostic {
+    Diagnostic::error(DiagnosticCode::ReferenceNotFound, file, message).with_severity(severity)
+}
+
+/// Group issues by severity, most severe first, then by file
+fn group_issues(package_name: String, issues: Vec<Diagnostic>) -> PackageLint {
+    let count = |severity: Severity| issues.iter().filter(|d| d.severity == severity).count();
+    let (error_count, warning_count, info_count) = (
+        count(Severity::Error),
+        count(Severity::Warning),
+        count(Severity::Info),
+    );
+
+    let mut groups = Vec::new();
+    for severity in [Severity::Error, Severity::Warning, Severity::Info] {
+        let mut files: BTreeMap<String, Vec<Diagnostic>> = BTreeMap::new();
+        for issue in issues.iter().filter(|d| d.severity == severity) {
+            files
+                .entry(linted_file(issue).to_string())
+                .or_default()
+                .push(issue.clone());
+        }
+        if files.is_empty() {
+            continue;
+        }
+        groups.push(SeverityGroup {
+            severity,
+            files: files
+                .into_iter()
+                .map(|(file, issues)| FileIssues { file, issues })
+                .collect(),
+        });
+    }
+
+    PackageLint {
+        package_name,
+        error_count,
+        warning_count,
+        info_count,
+        groups,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_sound_files_in_bags() {
+        let bag: AttributeBag = [
+            ("sound.fileref", "fire.wav"),
+            ("sound[1].fileref", "missing.wav"),
+            ("sound.key", "fire"),
+            ("projectile.sound.fileref", "boom.wav"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let file_names: HashSet<String> = ["fire.wav".to_string()].into();
+
+        let issues = missing_sounds(Path::new("a.weapon"), &bag, &file_names);
+        let messages: Vec<&str> = issues.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Sound file not found: boom.wav",
+                "Sound file not found: missing.wav"
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_by_severity_then_file() {
+        let issues = vec![
+            missing(Path::new("b.weapon"), Severity::Warning, "x".into()),
+            missing(Path::new("a.weapon"), Severity::Warning, "y".into()),
+            missing(Path::new("b.weapon"), Severity::Error, "z".into()),
+        ];
+        let lint = group_issues("mymod".into(), issues);
+        assert_eq!((lint.error_count, lint.warning_count), (1, 2));
+        assert_eq!(lint.groups[0].severity, Severity::Error);
+        let files: Vec<&str> = lint.groups[1]
+            .files
+            .iter()
+            .map(|f| f.file.as_str())
+            .collect();
+        assert_eq!(files, ["a.weapon", "b.weapon"]);
+    }
+}
@@ -0,0 +1,402 @@
+//! Maps scanner module
+//!
+//! Finds map directories (`<package>/maps/<map>/objects.svg`) and reads the map's objects
+//! layer (bases, spawn points, map size) and configuration (name, factions used), plus the
+//! overview image shipped with the map.
+
+use crate::diagnostics::{
+    parse_recovering, parse_xml, read_file, Diagnostic, DiagnosticCode, Severity,
+};
+use crate::packages::{KeyDefinition, Provenance};
+use crate::scan_cache::{Parsed, ScanCache};
+use crate::scan_jobs::ScanJobs;
+use crate::scanner::{self, PackageScanner, ScanOptions, ScanSummary};
+use crate::utils::{package_name_of, relative_file_path};
+use crate::ScanEvent;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// File that marks a folder below `maps/` as a map directory
+const OBJECTS_FILE: &str = "objects.svg";
+
+/// Map configuration file names, in lookup order
+const CONFIG_FILES: [&str; 2] = ["map.xml", "map_config.xml"];
+
+/// Overview image file names, in lookup order
+const OVERVIEW_IMAGES: [&str; 2] = ["map_view.png", "_rwr_map_view.png"];
+
+/// Map directory with its objects and configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameMap {
+    pub id: String,
+    /// Map directory name (e.g. "map1")
+    pub key: String,
+    pub name: String,
+    /// Path the game servers report as `map_id` (e.g. "media/packages/vanilla/maps/map1")
+    pub map_id: String,
+    pub bases: Vec<MapObject>,
+    pub spawn_points: Vec<MapObject>,
+    /// Map size from the objects layer (`<svg width/height>`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<f64>,
+    /// Faction files listed in the map configuration
+    pub factions: Vec<String>,
+    /// Absolute path of the overview image, if the map ships one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overview_image: Option<String>,
+    /// Absolute path of the configuration file, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_file: Option<String>,
+    /// Map directory relative to the packages directory (e.g. "vanilla/maps/map1")
+    pub file_path: String,
+    /// Absolute path of the map's `objects.svg`
+    pub source_file: String,
+    pub source_directory: String,
+    pub package_name: String,
+    /// Effective scans only: the package this definition was taken from and what it overrides
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provenance: Option<Provenance>,
+}
+
+/// An object placed in the objects layer (`inkscape:label="#base"`, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapObject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
+}
+
+/// Result from map scanning
+#[derive(Debug, Serialize)]
+pub struct MapScanResult {
+    pub maps: Vec<GameMap>,
+    #[serde(flatten)]
+    pub summary: ScanSummary,
+}
+
+/// Raw map configuration XML structure
+#[derive(Debug, Deserialize, Default)]
+struct RawMapConfig {
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "faction", default)]
+    factions: Vec<RawMapFaction>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawMapFaction {
+    #[serde(rename = "@file", default)]
+    file: Option<String>,
+}
+
+/// Objects layer contents
+#[derive(Debug, Default)]
+struct MapObjects {
+    width: Option<f64>,
+    height: Option<f64>,
+    bases: Vec<MapObject>,
+    spawn_points: Vec<MapObject>,
+}
+
+/// Scan all map directories from a game/workshop directory.
+///
+/// The scan runs in the background; the returned job id can be passed to `cancel_scan`.
+#[tauri::command]
+pub async fn scan_maps(
+    game_path: String,
+    directory: Option<String>,
+    jobs: State<'_, ScanJobs>,
+    on_event: Channel<ScanEvent<GameMap>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    scanner::spawn_scan::<MapScanner>(&jobs, source_directory, on_event)
+}
+
+/// [`scan_maps`] in one payload, for WebViews that drop `Channel` messages
+/// (see `scan_weapons_collect`).
+#[tauri::command]
+pub async fn scan_maps_collect(
+    game_path: String,
+    directory: Option<String>,
+    effective: Option<bool>,
+) -> Result<MapScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        effective: effective.unwrap_or(false),
+        ..Default::default()
+    };
+    let scan = scanner::collect_scan::<MapScanner>(&source_directory, options)?;
+    Ok(MapScanResult {
+        maps: scan.records,
+        summary: scan.summary,
+    })
+}
+
+/// Map scanner for the shared scanning framework; each map is discovered through its
+/// `objects.svg`, the configuration and overview image are tracked as dependencies.
+pub(crate) struct MapScanner;
+
+impl PackageScanner for MapScanner {
+    type Record = GameMap;
+
+    const KIND: &'static str = "maps";
+
+    fn matches(path: &Path) -> bool {
+        path.file_name().is_some_and(|name| name == OBJECTS_FILE)
+            && path
+                .parent()
+                .and_then(Path::parent)
+                .and_then(Path::file_name)
+                .is_some_and(|name| name == "maps")
+    }
+
+    fn parse_cached(
+        cache: &ScanCache<GameMap>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<GameMap>, Diagnostic> {
+        parse_map_cached(cache, file, packages_root, index, source_directory)
+    }
+
+    fn record_id(record: &GameMap) -> &str {
+        &record.id
+    }
+
+    fn key_definition(record: &GameMap) -> Option<KeyDefinition> {
+        Some(KeyDefinition {
+            key: record.key.clone(),
+            id: record.id.clone(),
+            package_name: record.package_name.clone(),
+            file_path: record.file_path.clone(),
+            source_file: record.source_file.clone(),
+        })
+    }
+
+    fn set_provenance(record: &mut GameMap, provenance: Provenance) {
+        record.provenance = Some(provenance);
+    }
+}
+
+/// Parse a map directory through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits.
+fn parse_map_cached(
+    cache: &ScanCache<GameMap>,
+    objects_path: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<GameMap>, Diagnostic> {
+    let id = format!("{}_{}", objects_path.to_string_lossy(), index);
+
+    let mut parsed = cache.get_or_parse(objects_path, |dependencies, diagnostics| {
+        parse_map_dir(
+            objects_path,
+            packages_root,
+            id.clone(),
+            source_directory,
+            dependencies,
+            diagnostics,
+        )
+        .map(|map| vec![map])
+    })?;
+
+    for map in &mut parsed.records {
+        map.id = id.clone();
+        map.source_directory = source_directory.to_string();
+    }
+    Ok(parsed)
+}
+
+/// Read one map directory.
+/// Every configuration/overview candidate is appended to `dependencies` so the cache
+/// notices when one appears or changes; a broken configuration is reported as a warning.
+fn parse_map_dir(
+    objects_path: &Path,
+    packages_root: &Path,
+    id: String,
+    source_directory: &str,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<GameMap, Diagnostic> {
+    let map_dir = objects_path.parent().unwrap_or(packages_root);
+    let content = read_file(objects_path)?;
+    let objects = read_objects(&content, objects_path)?;
+
+    let config_path = find_first(map_dir, &CONFIG_FILES, dependencies);
+    let config = match &config_path {
+        Some(path) => match read_file(path)
+            .and_then(|c| parse_recovering(&c, diagnostics, |c| parse_xml::<RawMapConfig>(c, path)))
+        {
+            Ok((config, _)) => config,
+            Err(diagnostic) => {
+                diagnostics.push(diagnostic.with_severity(Severity::Warning));
+                RawMapConfig::default()
+            }
+        },
+        None => RawMapConfig::default(),
+    };
+
+    // Resolved like item textures: absolute, canonical path of an existing file
+    let overview_image = find_first(map_dir, &OVERVIEW_IMAGES, dependencies)
+        .and_then(|p| p.canonicalize().ok())
+        .map(|p| p.to_string_lossy().to_string());
+
+    let key = map_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // Map directory relative to the packages directory, e.g. "vanilla/maps/map1"
+    let file_path = relative_file_path(map_dir, packages_root).replace('\\', "/");
+
+    Ok(GameMap {
+        id,
+        name: config.name.unwrap_or_else(|| key.clone()),
+        key,
+        map_id: format!("media/packages/{}", file_path),
+        bases: objects.bases,
+        spawn_points: objects.spawn_points,
+        width: objects.width,
+        height: objects.height,
+        factions: config
+            .factions
+            .into_iter()
+            .filter_map(|f| f.file)
+            .filter(|f| !f.is_empty())
+            .collect(),
+        overview_image,
+        config_file: config_path.map(|p| p.to_string_lossy().to_string()),
+        file_path,
+        source_file: objects_path.to_string_lossy().to_string(),
+        source_directory: source_directory.to_string(),
+        package_name: package_name_of(objects_path, packages_root),
+        provenance: None,
+    })
+}
+
+/// First of `names` that exists in `dir`; every candidate checked becomes a dependency
+fn find_first(dir: &Path, names: &[&str], dependencies: &mut Vec<PathBuf>) -> Option<PathBuf> {
+    for name in names {
+        let candidate = dir.join(name);
+        dependencies.push(candidate.clone());
+        if candidate.exists() {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// Read the map size and the labelled objects from `objects.svg`.
+///
+/// Objects are recognised by their Inkscape label (`#base`, `#spawn_point`); other
+/// elements are ignored.
+fn read_objects(content: &str, file: &Path) -> Result<MapObjects, Diagnostic> {
+    let mut reader = Reader::from_str(content);
+    let mut objects = MapObjects::default();
+
+    loop {
+        let event = reader.read_event().map_err(|e| {
+            Diagnostic::error(
+                DiagnosticCode::XmlSyntax,
+                file,
+                format!("XML parse error: {}", e),
+            )
+            .at(content, reader.error_position() as usize)
+        })?;
+
+        match event {
+            Event::Start(e) | Event::Empty(e) => {
+                if e.name().as_ref() == b"svg" {
+                    objects.width = attribute(&e, b"width").and_then(|v| parse_length(&v));
+                    objects.height = attribute(&e, b"height").and_then(|v| parse_length(&v));
+                    continue;
+                }
+                let Some(label) = attribute(&e, b"inkscape:label") else {
+                    continue;
+                };
+                let object = || MapObject {
+                    id: attribute(&e, b"id"),
+                    x: attribute(&e, b"x").and_then(|v| parse_length(&v)),
+                    y: attribute(&e, b"y").and_then(|v| parse_length(&v)),
+                };
+                match label.trim() {
+                    "#base" => objects.bases.push(object()),
+                    "#spawn_point" | "#spawnpoint" => objects.spawn_points.push(object()),
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(objects)
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .filter_map(|a| a.ok())
+        .find(|a| a.key.as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.to_string())
+}
+
+/// SVG lengths may carry a unit suffix ("2048px")
+fn parse_length(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%')
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_size_and_labelled_objects() {
+        let svg = r##"<svg width="2048px" height="1536">
+            <g inkscape:label="#layer">
+                <rect id="base1" inkscape:label="#base" x="10.5" y="20"/>
+                <rect id="sp1" inkscape:label=" #spawn_point " x="30" y="40"/>
+                <rect id="sp2" inkscape:label="#spawnpoint"/>
+                <rect id="other" inkscape:label="#armory" x="1" y="1"/>
+            </g>
+        </svg>"##;
+        let objects = read_objects(svg, Path::new("objects.svg")).unwrap();
+
+        assert_eq!(objects.width, Some(2048.0));
+        assert_eq!(objects.height, Some(1536.0));
+        assert_eq!(objects.bases.len(), 1);
+        assert_eq!(objects.bases[0].id.as_deref(), Some("base1"));
+        assert_eq!(objects.bases[0].x, Some(10.5));
+        let spawn_ids: Vec<_> = objects
+            .spawn_points
+            .iter()
+            .map(|s| s.id.as_deref())
+            .collect();
+        assert_eq!(spawn_ids, [Some("sp1"), Some("sp2")]);
+    }
+
+    #[test]
+    fn reports_broken_objects_layer_with_position() {
+        let diagnostic = read_objects("<svg>\n<g></svg>", Path::new("objects.svg")).unwrap_err();
+        assert_eq!(diagnostic.code, DiagnosticCode::XmlSyntax);
+        assert_eq!(diagnostic.line, Some(2));
+    }
+}
@@ -0,0 +1,200 @@
+//! Derived weapon performance metrics
+//!
+//! Computed from the fields `parse_weapon_file` extracts, so balance discussions don't have
+//! to redo the arithmetic by hand. `kill_probability` is the chance a single hit kills an
+//! unarmored soldier; values of 1 or more always kill. A trigger pull fires
+//! `projectiles_per_shot` projectiles (shotgun pellets), and it kills if any of them does.
+
+use crate::weapons::Weapon;
+use serde::{Deserialize, Serialize};
+
+/// Reload duration used for sustained DPS. Reload timings live in the soldier animation
+/// files, which the toolbox doesn't parse, so every weapon uses the same typical value.
+pub const ASSUMED_RELOAD_TIME: f64 = 2.5;
+
+/// Performance figures of one weapon
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeaponMetrics {
+    /// `60 / retrigger_time`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounds_per_minute: Option<f64>,
+    /// Expected kills per second while the trigger is held, ignoring reloads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_dps: Option<f64>,
+    /// Expected kills per second over full magazine + reload cycles
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sustained_dps: Option<f64>,
+    /// Reload duration `sustained_dps` assumes, in seconds
+    pub reload_time: f64,
+    /// Expected number of trigger pulls until a kill, every projectile hitting
+    /// (`1 / kill chance per pull`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shots_to_kill: Option<f64>,
+    /// Expected seconds from the first to the killing shot, all shots hitting
+    /// (`(shots_to_kill - 1) * retrigger_time`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_kill: Option<f64>,
+    /// Kill probability over projectile flight time; empty without kill decay times
+    pub damage_falloff: Vec<FalloffPoint>,
+    pub stances: Vec<StanceMetrics>,
+}
+
+/// Kill probability of a hit after `time` seconds of flight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FalloffPoint {
+    pub time: f64,
+    /// `time * projectile_speed`, when the speed is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f64>,
+    pub kill_probability: f64,
+}
+
+/// Accuracy in one stance, scaled by the weapon's `accuracy_factor`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StanceMetrics {
+    pub stance: String,
+    pub effective_accuracy: f64,
+    /// Expected shots fired until a kill, counting misses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shots_to_kill: Option<f64>,
+}
+
+/// Compute the metrics of a parsed weapon
+pub(crate) fn weapon_metrics(weapon: &Weapon) -> WeaponMetrics {
+    let retrigger_time = Some(weapon.retrigger_time).filter(|t| *t > 0.0);
+    let projectiles_per_shot = weapon.projectiles_per_shot.unwrap_or(1.0).max(1.0);
+    // Chance that at least one projectile of the pull kills
+    let kill_probability = Some(weapon.kill_probability.min(1.0))
+        .filter(|p| *p > 0.0)
+        .map(|p| 1.0 - (1.0 - p).powf(projectiles_per_shot));
+    let magazine_size = Some(weapon.magazine_size).filter(|m| *m > 0.0);
+
+    let shots_to_kill = kill_probability.map(|p| 1.0 / p);
+    let burst_dps = retrigger_time.zip(kill_probability).map(|(t, p)| p / t);
+    let sustained_dps = retrigger_time
+        .zip(kill_probability)
+        .zip(magazine_size)
+        .map(|((t, p), m)| m * p / (m * t + ASSUMED_RELOAD_TIME));
+    let time_to_kill = shots_to_kill
+        .zip(retrigger_time)
+        .map(|(shots, t)| (shots - 1.0) * t);
+
+    let accuracy_factor = weapon.accuracy_factor.unwrap_or(1.0);
+    let stances = weapon
+        .stance_accuracies
+        .iter()
+        .map(|s| {
+            let effective_accuracy = (s.accuracy * accuracy_factor).clamp(0.0, 1.0);
+            StanceMetrics {
+                stance: s.stance.clone(),
+                effective_accuracy,
+                shots_to_kill: shots_to_kill
+                    .filter(|_| effective_accuracy > 0.0)
+                    .map(|shots| shots / effective_accuracy),
+            }
+        })
+        .collect();
+
+    WeaponMetrics {
+        rounds_per_minute: retrigger_time.map(|t| 60.0 / t),
+        burst_dps,
+        sustained_dps,
+        reload_time: ASSUMED_RELOAD_TIME,
+        shots_to_kill,
+        time_to_kill,
+        damage_falloff: damage_falloff(weapon),
+        stances,
+    }
+}
+
+/// Hits keep full kill probability until `kill_decay_start_time`, then fall linearly to
+/// zero at `kill_decay_end_time`.
+fn damage_falloff(weapon: &Weapon) -> Vec<FalloffPoint> {
+    let Some(projectile) = &weapon.projectile else {
+        return Vec::new();
+    };
+    let (Some(start), Some(end)) = (
+        projectile.kill_decay_start_time,
+        projectile.kill_decay_end_time,
+    ) else {
+        return Vec::new();
+    };
+    if end < start {
+        return Vec::new();
+    }
+
+    let full = weapon.kill_probability.min(1.0);
+    let point = |time: f64, kill_probability: f64| FalloffPoint {
+        time,
+        distance: weapon.projectile_speed.map(|speed| time * speed),
+        kill_probability,
+    };
+    let mut points = Vec::new();
+    if start > 0.0 {
+        points.push(point(0.0, full));
+    }
+    points.push(point(start, full));
+    if end > start {
+        points.push(point((start + end) / 2.0, full / 2.0));
+    }
+    points.push(point(end, 0.0));
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projectiles::{test_projectile, Projectile};
+    use crate::weapons::{test_weapon, StanceAccuracy};
+
+    #[test]
+    fn derives_metrics_from_weapon_fields() {
+        let mut weapon = Weapon {
+            magazine_size: 30.0,
+            kill_probability: 0.5,
+            retrigger_time: 0.1,
+            projectile_speed: Some(100.0),
+            stance_accuracies: vec![StanceAccuracy {
+                stance: "standing".to_string(),
+                accuracy: 0.5,
+            }],
+            ..test_weapon("a.weapon")
+        };
+        weapon.projectile = Some(Projectile {
+            kill_decay_start_time: Some(0.2),
+            kill_decay_end_time: Some(0.6),
+            ..test_projectile("p.projectile")
+        });
+
+        let metrics = weapon_metrics(&weapon);
+        assert_eq!(metrics.rounds_per_minute, Some(600.0));
+        assert_eq!(metrics.shots_to_kill, Some(2.0));
+        assert_eq!(metrics.time_to_kill, Some(0.1));
+        assert_eq!(metrics.stances[0].shots_to_kill, Some(4.0));
+        let falloff: Vec<(f64, f64)> = metrics
+            .damage_falloff
+            .iter()
+            .map(|p| (p.time, p.kill_probability))
+            .collect();
+        assert_eq!(falloff, [(0.0, 0.5), (0.2, 0.5), (0.4, 0.25), (0.6, 0.0)]);
+        assert_eq!(metrics.damage_falloff[3].distance, Some(60.0));
+
+        // Every pellet of a shot gets its own chance to kill
+        weapon.projectiles_per_shot = Some(2.0);
+        let metrics = weapon_metrics(&weapon);
+        assert_eq!(metrics.shots_to_kill, Some(1.0 / 0.75));
+        assert_eq!(metrics.burst_dps, Some(7.5));
+        // Falloff stays per projectile
+        assert_eq!(metrics.damage_falloff[0].kill_probability, 0.5);
+
+        // Kill probabilities above 1 still kill at most once
+        weapon.kill_probability = 2.0;
+        assert_eq!(
+            weapon_metrics(&weapon).damage_falloff[0].kill_probability,
+            1.0
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Package load order and duplicate key detection
+//!
+//! RWR registers weapons and items by key. When several packages (or several files in one
+//! package) define the same key, only one definition is used in game; the others are
+//! silently shadowed. [`LoadOrder`] decides which one wins; the effective overlay keeps only
+//! the winners and records what each of them shadowed.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Base game package every other package builds on
+pub const VANILLA_PACKAGE: &str = "vanilla";
+
+/// One definition of a key, as reported in duplicate and provenance listings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyDefinition {
+    pub key: String,
+    /// Id of the scanned record holding this definition
+    pub id: String,
+    pub package_name: String,
+    /// Path relative to the packages directory (e.g. "vanilla/weapons/ak47.weapon")
+    pub file_path: String,
+    pub source_file: String,
+}
+
+/// A key defined more than once across the scanned packages
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateKey {
+    pub key: String,
+    /// Every definition in load order; the last one is the one the game uses
+    pub definitions: Vec<KeyDefinition>,
+    /// `file_path` of the definition the game uses
+    pub effective_file: String,
+    pub effective_package: String,
+}
+
+/// Which package an effective record was taken from and which definitions it overrides.
+///
+/// The winner is picked by [`LoadOrder`], which approximates the game's precedence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    pub package_name: String,
+    pub file_path: String,
+    /// Definitions of the same key hidden by this one, in load order
+    pub shadowed: Vec<KeyDefinition>,
+}
+
+/// Package precedence for one scan.
+///
+/// `vanilla` loads first and every other package loads after it in name order; a later
+/// definition of a key overrides an earlier one. The same package found under several
+/// packages roots (see `resolve_packages_dirs`) follows root order, so external media
+/// overrides the app bundle. Definitions inside one package follow their path order.
+///
+/// This is an assumption, not a documented game rule: RWR loads the packages enabled for the
+/// running campaign or server, which a scan of the packages folder can't see. Overhauls
+/// loaded ahead of a workshop package with a later name are reported the wrong way round.
+pub struct LoadOrder {
+    roots: Vec<PathBuf>,
+}
+
+impl LoadOrder {
+    pub fn new(roots: &[PathBuf]) -> Self {
+        Self {
+            roots: roots.to_vec(),
+        }
+    }
+
+    pub fn cmp(&self, a: &KeyDefinition, b: &KeyDefinition) -> Ordering {
+        let rank = |d: &KeyDefinition| d.package_name != VANILLA_PACKAGE;
+        rank(a)
+            .cmp(&rank(b))
+            .then_with(|| a.package_name.cmp(&b.package_name))
+            .then_with(|| {
+                self.root_index(&a.source_file)
+                    .cmp(&self.root_index(&b.source_file))
+            })
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    }
+
+    fn root_index(&self, source_file: &str) -> usize {
+        let file = Path::new(source_file);
+        self.roots
+            .iter()
+            .position(|root| file.starts_with(root))
+            .unwrap_or(self.roots.len())
+    }
+}
+
+/// Group definitions by key and report every key defined more than once, sorted by key
+pub fn find_duplicate_keys(
+    definitions: Vec<KeyDefinition>,
+    load_order: &LoadOrder,
+) -> Vec<DuplicateKey> {
+    let mut by_key: HashMap<String, Vec<KeyDefinition>> = HashMap::new();
+    for definition in definitions {
+        by_key
+            .entry(definition.key.clone())
+            .or_default()
+            .push(definition);
+    }
+
+    let mut duplicates: Vec<DuplicateKey> = by_key
+        .into_iter()
+        .filter(|(_, defs)| defs.len() > 1)
+        .filter_map(|(key, mut definitions)| {
+            definitions.sort_by(|a, b| load_order.cmp(a, b));
+            let effective = definitions.last()?;
+            Some(DuplicateKey {
+                effective_file: effective.file_path.clone(),
+                effective_package: effective.package_name.clone(),
+                key,
+                definitions,
+            })
+        })
+        .collect();
+
+    duplicates.sort_by(|a, b| a.key.cmp(&b.key));
+    duplicates
+}
+
+/// Reduce `records` to one effective record per key.
+///
+/// Every keyed record that survives gets a [`Provenance`] through `set_provenance`;
+/// records without a key are passed through unchanged. Original order is kept.
+pub fn overlay<T>(
+    records: Vec<T>,
+    load_order: &LoadOrder,
+    definition: impl Fn(&T) -> Option<KeyDefinition>,
+    set_provenance: impl Fn(&mut T, Provenance),
+) -> Vec<T> {
+    let definitions: Vec<Option<KeyDefinition>> = records.iter().map(&definition).collect();
+
+    let mut by_key: HashMap<&str, Vec<(usize, &KeyDefinition)>> = HashMap::new();
+    for (index, def) in definitions.iter().enumerate() {
+        if let Some(def) = def {
+            by_key
+                .entry(def.key.as_str())
+                .or_default()
+                .push((index, def));
+        }
+    }
+
+    let mut provenance: HashMap<usize, Provenance> = HashMap::new();
+    for mut group in by_key.into_values() {
+        group.sort_by(|(_, a), (_, b)| load_order.cmp(a, b));
+        let Some(((winner, winner_def), shadowed)) = group.split_last() else {
+            continue;
+        };
+        provenance.insert(
+            *winner,
+            Provenance {
+                package_name: winner_def.package_name.clone(),
+                file_path: winner_def.file_path.clone(),
+                shadowed: shadowed.iter().map(|(_, d)| (*d).clone()).collect(),
+            },
+        );
+    }
+
+    records
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, mut record)| {
+            if definitions[index].is_none() {
+                return Some(record);
+            }
+            let provenance = provenance.remove(&index)?;
+            set_provenance(&mut record, provenance);
+            Some(record)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(key: &str, package_name: &str, file: &str) -> KeyDefinition {
+        KeyDefinition {
+            key: key.to_string(),
+            id: format!("{}/{}", package_name, file),
+            package_name: package_name.to_string(),
+            file_path: format!("{}/{}", package_name, file),
+            source_file: format!("/packages/{}/{}", package_name, file),
+        }
+    }
+
+    #[test]
+    fn unique_keys_are_not_reported() {
+        let duplicates = find_duplicate_keys(
+            vec![
+                definition("ak47.weapon", "vanilla", "weapons/ak47.weapon"),
+                definition("m16.weapon", "vanilla", "weapons/m16.weapon"),
+            ],
+            &LoadOrder::new(&[]),
+        );
+        assert!(duplicates.is_empty());
+    }
+
+    #[test]
+    fn mod_package_overrides_vanilla() {
+        let duplicates = find_duplicate_keys(
+            vec![
+                definition("ak47.weapon", "zmod", "weapons/ak47.weapon"),
+                definition("ak47.weapon", "vanilla", "weapons/ak47.weapon"),
+                definition("ak47.weapon", "amod", "weapons/ak.weapon"),
+            ],
+            &LoadOrder::new(&[]),
+        );
+
+        assert_eq!(duplicates.len(), 1);
+        let packages: Vec<&str> = duplicates[0]
+            .definitions
+            .iter()
+            .map(|d| d.package_name.as_str())
+            .collect();
+        assert_eq!(packages, vec!["vanilla", "amod", "zmod"]);
+        assert_eq!(duplicates[0].effective_package, "zmod");
+        assert_eq!(duplicates[0].effective_file, "zmod/weapons/ak47.weapon");
+    }
+
+    #[test]
+    fn overlay_keeps_winner_with_shadowed_definitions() {
+        let records = vec![
+            definition("ak47.weapon", "mymod", "weapons/ak47.weapon"),
+            definition("m16.weapon", "vanilla", "weapons/m16.weapon"),
+            definition("ak47.weapon", "vanilla", "weapons/ak47.weapon"),
+        ];
+
+        let mut effective: Vec<(KeyDefinition, Option<Provenance>)> = overlay(
+            records.into_iter().map(|d| (d, None)).collect(),
+            &LoadOrder::new(&[]),
+            |(d, _)| Some(d.clone()),
+            |(_, p), provenance| *p = Some(provenance),
+        );
+
+        assert_eq!(effective.len(), 2);
+        let (ak47, provenance) = effective.remove(0);
+        let provenance = provenance.unwrap();
+        assert_eq!(ak47.package_name, "mymod");
+        assert_eq!(provenance.package_name, "mymod");
+        assert_eq!(provenance.shadowed.len(), 1);
+        assert_eq!(provenance.shadowed[0].package_name, "vanilla");
+        assert!(effective[0].1.as_ref().unwrap().shadowed.is_empty());
+    }
+}
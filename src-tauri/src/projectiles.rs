@@ -0,0 +1,431 @@
+//! Projectiles scanner module
+//!
+//! Scans RWR packages for `.projectile` files (grenades, rockets, launcher rounds, bullets),
+//! resolves their `file="..."` template inheritance and returns structured projectile data.
+//! Weapons reference projectiles with `<projectile file="...">`; the same resolution is used
+//! to attach the resolved projectile to each weapon.
+
+use crate::diagnostics::{parse_recovering, parse_xml, read_file, Diagnostic};
+use crate::packages::{KeyDefinition, Provenance};
+use crate::scan_cache::{Parsed, ScanCache};
+use crate::scan_jobs::ScanJobs;
+use crate::scanner::{self, PackageScanner, ScanOptions, ScanSummary};
+use crate::templates::{inherit, Template};
+use crate::utils::{package_name_of, relative_file_path};
+use crate::watch::{self, WatchRegistry};
+use crate::ScanEvent;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// Projectile with its template chain resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Projectile {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub name: String,
+    /// Projectile class from the root element (e.g. "grenade", "bullet", "rocket")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    /// `<result class="...">` (e.g. "blast", "hit", "spawn")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_class: Option<String>,
+    /// `<result radius="...">` for blast results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blast_radius: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub damage: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kill_probability: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kill_decay_start_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kill_decay_end_time: Option<f64>,
+    /// Seconds until the projectile triggers or expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_live: Option<f64>,
+    /// `<trigger class="...">` (e.g. "time", "impact")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hud_icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encumbrance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    pub file_path: String,
+    pub source_file: String,
+    pub source_directory: String,
+    pub package_name: String,
+    /// Effective scans only: the package this definition was taken from and what it overrides
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provenance: Option<Provenance>,
+    /// The file is malformed XML; these fields were recovered from a repaired copy and
+    /// anything after the broken markup may be missing
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub partially_parsed: bool,
+}
+
+/// Result from projectile scanning
+#[derive(Debug, Serialize)]
+pub struct ProjectileScanResult {
+    pub projectiles: Vec<Projectile>,
+    #[serde(flatten)]
+    pub summary: ScanSummary,
+}
+
+/// Raw projectile XML structure, used both for `.projectile` files and for the
+/// `<projectile>` element inside weapons. In both cases `file` names the projectile
+/// the element inherits from.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub(crate) struct RawProjectile {
+    #[serde(rename = "@file", default)]
+    pub(crate) file: Option<String>,
+    #[serde(rename = "@key", default)]
+    pub(crate) key: Option<String>,
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "@class", default)]
+    class: Option<String>,
+    #[serde(rename = "@time_to_live", default)]
+    time_to_live: Option<f64>,
+    #[serde(rename = "result", default)]
+    pub(crate) result: Option<RawProjectileResult>,
+    #[serde(rename = "trigger", default)]
+    trigger: Option<RawTrigger>,
+    #[serde(rename = "hud_icon", default)]
+    hud_icon: Option<RawHudIcon>,
+    #[serde(rename = "inventory", default)]
+    inventory: Option<RawInventory>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub(crate) struct RawProjectileResult {
+    #[serde(rename = "@class", default)]
+    class: Option<String>,
+    #[serde(rename = "@kill_probability", default)]
+    pub(crate) kill_probability: Option<f64>,
+    #[serde(rename = "@kill_decay_start_time", default)]
+    kill_decay_start_time: Option<f64>,
+    #[serde(rename = "@kill_decay_end_time", default)]
+    kill_decay_end_time: Option<f64>,
+    #[serde(rename = "@radius", default)]
+    radius: Option<f64>,
+    #[serde(rename = "@damage", default)]
+    damage: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+struct RawTrigger {
+    #[serde(rename = "@class", default)]
+    class: Option<String>,
+    #[serde(rename = "@time_to_live", default)]
+    time_to_live: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+struct RawHudIcon {
+    #[serde(rename = "@filename", default)]
+    filename: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+struct RawInventory {
+    #[serde(rename = "@encumbrance", default)]
+    encumbrance: Option<f64>,
+    #[serde(rename = "@price", default)]
+    price: Option<f64>,
+}
+
+/// Scan all projectile files from a game/workshop directory.
+///
+/// The scan runs in the background; the returned job id can be passed to `cancel_scan`.
+#[tauri::command]
+pub async fn scan_projectiles(
+    game_path: String,
+    directory: Option<String>,
+    jobs: State<'_, ScanJobs>,
+    on_event: Channel<ScanEvent<Projectile>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    scanner::spawn_scan::<ProjectileScanner>(&jobs, source_directory, on_event)
+}
+
+/// [`scan_projectiles`] in one payload, for WebViews that drop `Channel` messages
+/// (see `scan_weapons_collect`).
+#[tauri::command]
+pub async fn scan_projectiles_collect(
+    game_path: String,
+    directory: Option<String>,
+    effective: Option<bool>,
+) -> Result<ProjectileScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        effective: effective.unwrap_or(false),
+        ..Default::default()
+    };
+    let scan = scanner::collect_scan::<ProjectileScanner>(&source_directory, options)?;
+    Ok(ProjectileScanResult {
+        projectiles: scan.records,
+        summary: scan.summary,
+    })
+}
+
+/// Watch the packages roots of a projectiles scan and stream add/update/remove deltas.
+#[tauri::command]
+pub async fn watch_projectiles(
+    game_path: String,
+    directory: Option<String>,
+    registry: State<'_, WatchRegistry>,
+    on_event: Channel<ScanEvent<Projectile>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    watch::start::<ProjectileScanner>(&registry, source_directory, on_event)
+}
+
+/// Projectile scanner for the shared scanning framework
+pub(crate) struct ProjectileScanner;
+
+impl PackageScanner for ProjectileScanner {
+    type Record = Projectile;
+
+    const KIND: &'static str = "projectiles";
+
+    fn matches(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "projectile")
+    }
+
+    fn parse_cached(
+        cache: &ScanCache<Projectile>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Projectile>, Diagnostic> {
+        parse_projectile_cached(cache, file, packages_root, index, source_directory)
+    }
+
+    fn record_id(record: &Projectile) -> &str {
+        &record.id
+    }
+
+    fn key_definition(record: &Projectile) -> Option<KeyDefinition> {
+        Some(KeyDefinition {
+            key: record.key.clone()?,
+            id: record.id.clone(),
+            package_name: record.package_name.clone(),
+            file_path: record.file_path.clone(),
+            source_file: record.source_file.clone(),
+        })
+    }
+
+    fn set_provenance(record: &mut Projectile, provenance: Provenance) {
+        record.provenance = Some(provenance);
+    }
+}
+
+/// Parse a projectile file through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits.
+fn parse_projectile_cached(
+    cache: &ScanCache<Projectile>,
+    path: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<Projectile>, Diagnostic> {
+    let id = format!("{}_{}", path.to_string_lossy(), index);
+
+    let mut parsed = cache.get_or_parse(path, |dependencies, diagnostics| {
+        let content = read_file(path)?;
+        let (raw, mut partially_parsed) = parse_recovering(&content, diagnostics, |content| {
+            parse_xml::<RawProjectile>(content, path)
+        })?;
+        let (raw, template_repaired) = inherit(raw, path, dependencies, diagnostics);
+        partially_parsed |= template_repaired;
+        let mut projectile =
+            build_projectile(raw, id.clone(), path, packages_root, source_directory);
+        projectile.partially_parsed = partially_parsed;
+        Ok::<_, Diagnostic>(vec![projectile])
+    })?;
+
+    for projectile in &mut parsed.records {
+        projectile.id = id.clone();
+        projectile.source_directory = source_directory.to_string();
+    }
+    Ok(parsed)
+}
+
+/// Projectile files live next to the weapons using them, also in vanilla
+impl Template for RawProjectile {
+    const VANILLA_DIR: &'static str = "weapons";
+    const LABEL: &'static str = "projectile";
+
+    fn parse(content: &str, path: &Path) -> Result<Self, Diagnostic> {
+        parse_xml(content, path)
+    }
+
+    fn template_file(&self) -> Option<String> {
+        self.file.clone()
+    }
+
+    fn merge(parent: Self, child: Self) -> Self {
+        merge_projectile_attributes(parent, child)
+    }
+}
+
+/// Child values win; missing ones are taken from `parent`
+pub(crate) fn merge_projectile_attributes(
+    parent: RawProjectile,
+    mut child: RawProjectile,
+) -> RawProjectile {
+    child.name = child.name.or(parent.name);
+    child.class = child.class.or(parent.class);
+    child.time_to_live = child.time_to_live.or(parent.time_to_live);
+    child.hud_icon = child.hud_icon.or(parent.hud_icon);
+    child.inventory = child.inventory.or(parent.inventory);
+
+    child.result = match (parent.result, child.result) {
+        (Some(p), Some(mut c)) => {
+            c.class = c.class.or(p.class);
+            c.kill_probability = c.kill_probability.or(p.kill_probability);
+            c.kill_decay_start_time = c.kill_decay_start_time.or(p.kill_decay_start_time);
+            c.kill_decay_end_time = c.kill_decay_end_time.or(p.kill_decay_end_time);
+            c.radius = c.radius.or(p.radius);
+            c.damage = c.damage.or(p.damage);
+            Some(c)
+        }
+        (p, c) => c.or(p),
+    };
+
+    child.trigger = match (parent.trigger, child.trigger) {
+        (Some(p), Some(mut c)) => {
+            c.class = c.class.or(p.class);
+            c.time_to_live = c.time_to_live.or(p.time_to_live);
+            Some(c)
+        }
+        (p, c) => c.or(p),
+    };
+
+    child
+}
+
+/// Convert a resolved raw projectile into the frontend record.
+/// `source` is the file the projectile is defined in.
+pub(crate) fn build_projectile(
+    raw: RawProjectile,
+    id: String,
+    source: &Path,
+    packages_root: &Path,
+    source_directory: &str,
+) -> Projectile {
+    let result = raw.result.unwrap_or_default();
+    let trigger = raw.trigger.unwrap_or_default();
+
+    let file_path = relative_file_path(source, packages_root);
+
+    let key = raw.key.filter(|k| !k.is_empty()).or_else(|| {
+        ProjectileScanner::matches(source)
+            .then(|| source.file_name().and_then(|n| n.to_str()))
+            .flatten()
+            .map(|s| s.to_string())
+    });
+
+    Projectile {
+        id,
+        name: raw.name.or_else(|| key.clone()).unwrap_or_default(),
+        key,
+        class: raw.class,
+        result_class: result.class,
+        blast_radius: result.radius,
+        damage: result.damage,
+        kill_probability: result.kill_probability,
+        kill_decay_start_time: result.kill_decay_start_time,
+        kill_decay_end_time: result.kill_decay_end_time,
+        time_to_live: trigger.time_to_live.or(raw.time_to_live),
+        trigger_class: trigger.class,
+        hud_icon: raw
+            .hud_icon
+            .and_then(|h| h.filename)
+            .filter(|s| !s.is_empty()),
+        encumbrance: raw.inventory.as_ref().and_then(|i| i.encumbrance),
+        price: raw.inventory.as_ref().and_then(|i| i.price),
+        file_path,
+        source_file: source.to_string_lossy().to_string(),
+        source_directory: source_directory.to_string(),
+        package_name: package_name_of(source, packages_root),
+        provenance: None,
+        partially_parsed: false,
+    }
+}
+
+/// Projectile `key` of the vanilla package with every other field empty, for tests
+#[cfg(test)]
+pub(crate) fn test_projectile(key: &str) -> Projectile {
+    Projectile {
+        id: key.to_string(),
+        key: Some(key.to_string()),
+        name: key.to_string(),
+        class: None,
+        result_class: None,
+        blast_radius: None,
+        damage: None,
+        kill_probability: None,
+        kill_decay_start_time: None,
+        kill_decay_end_time: None,
+        time_to_live: None,
+        trigger_class: None,
+        hud_icon: None,
+        encumbrance: None,
+        price: None,
+        file_path: format!("vanilla/weapons/{}", key),
+        source_file: String::new(),
+        source_directory: String::new(),
+        package_name: "vanilla".to_string(),
+        provenance: None,
+        partially_parsed: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(xml: &str) -> RawProjectile {
+        RawProjectile::parse(xml, Path::new("test.projectile")).unwrap()
+    }
+
+    #[test]
+    fn merges_template_underneath_the_projectile() {
+        let parent = raw(
+            r#"<projectile key="base.projectile" name="Grenade" class="grenade">
+            <result class="blast" radius="5.0" damage="2.0" kill_probability="1.0"/>
+            <trigger class="time" time_to_live="3.0"/>
+            <inventory encumbrance="1.0" price="2.0"/>
+        </projectile>"#,
+        );
+        let child = raw(r#"<projectile file="base.projectile" name="Frag">
+            <result radius="8.0"/>
+        </projectile>"#);
+
+        let root = Path::new("/packages");
+        let source = root.join("vanilla/weapons/frag.projectile");
+        let merged = RawProjectile::merge(parent, child);
+        let projectile = build_projectile(merged, "frag".into(), &source, root, "/game");
+
+        assert_eq!(projectile.name, "Frag");
+        assert_eq!(projectile.class.as_deref(), Some("grenade"));
+        assert_eq!(projectile.result_class.as_deref(), Some("blast"));
+        assert_eq!(projectile.blast_radius, Some(8.0));
+        assert_eq!(projectile.damage, Some(2.0));
+        assert_eq!(projectile.trigger_class.as_deref(), Some("time"));
+        assert_eq!(projectile.time_to_live, Some(3.0));
+        assert_eq!(projectile.price, Some(2.0));
+        // The key is never inherited; files fall back to their own name
+        assert_eq!(projectile.key.as_deref(), Some("frag.projectile"));
+        assert_eq!(projectile.file_path, "vanilla/weapons/frag.projectile");
+        assert_eq!(projectile.package_name, "vanilla");
+    }
+}
@@ -0,0 +1,453 @@
+//! Cross-reference index
+//!
+//! Records every place a package file points at a weapon, item, projectile, vehicle or
+//! texture by name: templates (`file="..."`), `<projectile file>`, chain links,
+//! `transform_on_consume`, faction resources, resource lists, character skins, call spawns,
+//! vehicle turret weapons and hud icons. Each file's references
+//! go through the scan cache like any other scanned record, so rebuilding the index only
+//! re-reads files that changed. [`find_references`] answers "what breaks if I rename or
+//! delete this key?".
+//!
+//! References are indexed by the file name of their target (`textures/hud.png` →
+//! `hud.png`), matching how the game resolves most of them.
+
+use crate::diagnostics::{line_column, read_file, Diagnostic, DiagnosticCode, Severity};
+use crate::packages::KeyDefinition;
+use crate::resource_lists::ResourceListKind;
+use crate::scan_cache::{CacheStats, Parsed, ScanCache};
+use crate::scanner::{self, PackageScanner};
+use crate::utils::{package_name_of, relative_file_path};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Extensions of the files that can hold references
+const REFERENCING_EXTENSIONS: &[&str] = &[
+    "weapon",
+    "projectile",
+    "carry_item",
+    "visual_item",
+    "faction",
+    "resources",
+    "character",
+    "vehicle",
+    "call",
+    "xml",
+];
+
+/// Elements that define a record and may extend a template with `file="..."`
+const RECORD_ELEMENTS: &[&str] = &[
+    "weapon",
+    "projectile",
+    "carry_item",
+    "visual_item",
+    "vehicle",
+    "call",
+];
+
+/// How a file refers to its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    /// `<weapon file="...">`: the record extends a template
+    Template,
+    /// `<projectile file="...">` inside a weapon
+    Projectile,
+    /// `<next_in_chain key="...">` / `<nextInChain key="...">`
+    ChainLink,
+    /// `<carry_item transform_on_consume="...">`
+    TransformOnConsume,
+    /// Entry of `all_weapons.xml` / `all_carry_items.xml`
+    ResourceList,
+    /// `<weapon key="...">` / `<carry_item key="...">` in a faction or `.resources` file
+    FactionResource,
+    /// `<resources file="...">` in a faction
+    ResourcesFile,
+    /// `<character filename="...">` (or `file`) in a faction
+    Character,
+    /// `<skin file="...">` (or `filename`) in a character
+    Skin,
+    /// `<projectile file|key="...">` / `<vehicle file|key="...">` spawned by a call
+    CallSpawn,
+    /// `<turret weapon_key="...">` / `<turret><weapon key="..."/>` on a vehicle
+    MountedWeapon,
+    /// `<hud_icon filename="...">`
+    HudIcon,
+}
+
+/// One attribute pointing at another file or key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reference {
+    pub id: String,
+    /// File name of the target, the index key
+    pub key: String,
+    /// Attribute value as written
+    pub target: String,
+    pub kind: ReferenceKind,
+    pub element: String,
+    pub attribute: String,
+    /// 1-based position of the referencing element
+    pub line: usize,
+    pub column: usize,
+    pub file_path: String,
+    pub source_file: String,
+    pub source_directory: String,
+    pub package_name: String,
+}
+
+/// Result of building the cross-reference index
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceScanResult {
+    /// Target key → every reference to it, in file then document order
+    pub references: BTreeMap<String, Vec<Reference>>,
+    /// Files that could not be read completely
+    pub errors: Vec<Diagnostic>,
+    pub scan_time: u64,
+    pub cache_stats: CacheStats,
+}
+
+pub(crate) struct ReferenceScanner;
+
+impl PackageScanner for ReferenceScanner {
+    type Record = Reference;
+
+    const KIND: &'static str = "references";
+
+    fn matches(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| REFERENCING_EXTENSIONS.iter().any(|e| ext == *e))
+    }
+
+    fn parse_cached(
+        cache: &ScanCache<Reference>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Reference>, Diagnostic> {
+        parse_references_cached(cache, file, packages_root, index, source_directory)
+    }
+
+    fn record_id(record: &Reference) -> &str {
+        &record.id
+    }
+
+    fn key_definition(_record: &Reference) -> Option<KeyDefinition> {
+        None
+    }
+}
+
+/// Index every reference found below the packages roots of `directory` (or `game_path`).
+#[tauri::command]
+pub async fn scan_references(
+    game_path: String,
+    directory: Option<String>,
+) -> Result<ReferenceScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let scan = scanner::collect_scan::<ReferenceScanner>(&source_directory, Default::default())?;
+
+    let mut references: BTreeMap<String, Vec<Reference>> = BTreeMap::new();
+    for reference in scan.records {
+        references
+            .entry(reference.key.clone())
+            .or_default()
+            .push(reference);
+    }
+
+    Ok(ReferenceScanResult {
+        references,
+        errors: scan.summary.errors,
+        scan_time: scan.summary.scan_time,
+        cache_stats: scan.summary.cache_stats,
+    })
+}
+
+/// Every reference to `key` (a weapon/item/projectile key or a file name; any folder part
+/// is ignored).
+#[tauri::command]
+pub async fn find_references(
+    game_path: String,
+    directory: Option<String>,
+    key: String,
+) -> Result<Vec<Reference>, String> {
+    let mut scan = scan_references(game_path, directory).await?;
+    Ok(scan
+        .references
+        .remove(&reference_key(&key))
+        .unwrap_or_default())
+}
+
+/// Index key of a target: its file name
+fn reference_key(target: &str) -> String {
+    Path::new(target.trim())
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Extract the references of a file through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits.
+fn parse_references_cached(
+    cache: &ScanCache<Reference>,
+    path: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<Reference>, Diagnostic> {
+    let mut parsed = cache.get_or_parse(path, |_dependencies, diagnostics| {
+        let content = read_file(path)?;
+        let (found, error) = extract_references(&content, path);
+        diagnostics.extend(error);
+
+        let file_path = relative_file_path(path, packages_root);
+        let package_name = package_name_of(path, packages_root);
+        Ok::<_, Diagnostic>(
+            found
+                .into_iter()
+                .map(|found| Reference {
+                    id: String::new(),
+                    key: reference_key(&found.target),
+                    target: found.target,
+                    kind: found.kind,
+                    element: found.element,
+                    attribute: found.attribute,
+                    line: found.line,
+                    column: found.column,
+                    file_path: file_path.clone(),
+                    source_file: path.to_string_lossy().to_string(),
+                    source_directory: String::new(),
+                    package_name: package_name.clone(),
+                })
+                .collect(),
+        )
+    })?;
+
+    for (position, reference) in parsed.records.iter_mut().enumerate() {
+        reference.id = format!("{}_{}_{}", path.to_string_lossy(), index, position);
+        reference.source_directory = source_directory.to_string();
+    }
+    Ok(parsed)
+}
+
+/// A reference before it is tied to its package
+struct FoundReference {
+    target: String,
+    kind: ReferenceKind,
+    element: String,
+    attribute: String,
+    line: usize,
+    column: usize,
+}
+
+/// References in `content`, in document order. Malformed XML ends the walk early; the
+/// references found up to that point are returned with a warning.
+fn extract_references(content: &str, path: &Path) -> (Vec<FoundReference>, Option<Diagnostic>) {
+    let list_file = ResourceListKind::Weapons.is_list_file(path)
+        || ResourceListKind::CarryItems.is_list_file(path);
+    let mut reader = Reader::from_str(content);
+    let mut ancestors: Vec<String> = Vec::new();
+    let mut found = Vec::new();
+
+    loop {
+        let offset = reader.buffer_position() as usize;
+        let (start, is_empty) = match reader.read_event() {
+            Ok(Event::Start(start)) => (start, false),
+            Ok(Event::Empty(start)) => (start, true),
+            Ok(Event::End(_)) => {
+                ancestors.pop();
+                continue;
+            }
+            Ok(Event::Eof) => return (found, None),
+            Err(e) => {
+                let diagnostic = Diagnostic::error(
+                    DiagnosticCode::XmlSyntax,
+                    path,
+                    format!(
+                        "XML parse error, references after this point are missing: {}",
+                        e
+                    ),
+                )
+                .with_severity(Severity::Warning)
+                .at(content, reader.error_position() as usize);
+                return (found, Some(diagnostic));
+            }
+            Ok(_) => continue,
+        };
+        let element = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+
+        for attribute in start.attributes().flatten() {
+            let name = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            let Some(kind) = classify(&element, &name, &ancestors, list_file) else {
+                continue;
+            };
+            let target = attribute
+                .unescape_value()
+                .map(|v| v.trim().to_string())
+                .unwrap_or_default();
+            if target.is_empty() {
+                continue;
+            }
+            let (line, column) = line_column(content, offset);
+            found.push(FoundReference {
+                target,
+                kind,
+                element: element.clone(),
+                attribute: name,
+                line,
+                column,
+            });
+        }
+
+        if !is_empty {
+            ancestors.push(element);
+        }
+    }
+}
+
+/// What `element@attribute` refers to, given the elements enclosing it
+fn classify(
+    element: &str,
+    attribute: &str,
+    ancestors: &[String],
+    list_file: bool,
+) -> Option<ReferenceKind> {
+    // Records sit at the root or directly inside a plural container (`<carry_items>`)
+    let is_record = RECORD_ELEMENTS.contains(&element)
+        && ancestors.iter().all(|a| {
+            a.strip_suffix('s')
+                .is_some_and(|singular| singular == element)
+        });
+    let in_resources = ancestors.iter().any(|a| a == "resources");
+    let in_call = ancestors.first().is_some_and(|root| root == "call");
+    let in_turret = ancestors.last().is_some_and(|parent| parent == "turret");
+
+    match (element, attribute) {
+        ("weapon" | "carry_item", "file") if list_file => Some(ReferenceKind::ResourceList),
+        (_, "file") if is_record => Some(ReferenceKind::Template),
+        ("projectile" | "vehicle", "file" | "key") if in_call => Some(ReferenceKind::CallSpawn),
+        ("projectile", "file") => Some(ReferenceKind::Projectile),
+        ("resources", "file") => Some(ReferenceKind::ResourcesFile),
+        ("skin", "file" | "filename") => Some(ReferenceKind::Skin),
+        ("turret", "weapon_key") => Some(ReferenceKind::MountedWeapon),
+        ("weapon", "key") if in_turret => Some(ReferenceKind::MountedWeapon),
+        ("next_in_chain" | "nextInChain", "key") => Some(ReferenceKind::ChainLink),
+        ("weapon" | "carry_item" | "projectile", "key") if in_resources => {
+            Some(ReferenceKind::FactionResource)
+        }
+        ("carry_item", "transform_on_consume") => Some(ReferenceKind::TransformOnConsume),
+        ("character", "filename" | "file") => Some(ReferenceKind::Character),
+        ("hud_icon", "filename") => Some(ReferenceKind::HudIcon),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(content: &str, path: &str) -> Vec<(ReferenceKind, String)> {
+        let (found, error) = extract_references(content, Path::new(path));
+        assert!(error.is_none());
+        found.into_iter().map(|f| (f.kind, f.target)).collect()
+    }
+
+    #[test]
+    fn classifies_references_by_element_and_context() {
+        let weapon = r#"<weapon file="base.weapon" key="ak47.weapon">
+            <projectile file="bullet.projectile" />
+            <hud_icon filename="hud_ak47.png" />
+            <next_in_chain key="ak47_gl.weapon" />
+        </weapon>"#;
+        assert_eq!(
+            kinds(weapon, "vanilla/weapons/ak47.weapon"),
+            [
+                (ReferenceKind::Template, "base.weapon".to_string()),
+                (ReferenceKind::Projectile, "bullet.projectile".to_string()),
+                (ReferenceKind::HudIcon, "hud_ak47.png".to_string()),
+                (ReferenceKind::ChainLink, "ak47_gl.weapon".to_string()),
+            ]
+        );
+
+        let list = r#"<carry_items><carry_item file="vest.carry_item" /></carry_items>"#;
+        assert_eq!(
+            kinds(list, "vanilla/items/all_carry_items.xml")[0].0,
+            ReferenceKind::ResourceList
+        );
+        assert_eq!(
+            kinds(list, "vanilla/items/vest.carry_item")[0].0,
+            ReferenceKind::Template
+        );
+
+        let faction = r#"<faction><soldier>
+            <resources file="rifleman.resources" />
+            <resources><carry_item key="vest.carry_item" /></resources>
+        </soldier></faction>"#;
+        assert_eq!(
+            kinds(faction, "vanilla/factions/green.faction")
+                .into_iter()
+                .map(|(kind, _)| kind)
+                .collect::<Vec<_>>(),
+            [ReferenceKind::ResourcesFile, ReferenceKind::FactionResource]
+        );
+    }
+
+    #[test]
+    fn classifies_skins_call_spawns_and_mounted_weapons() {
+        let just_kinds = |content: &str, path: &str| -> Vec<ReferenceKind> {
+            kinds(content, path)
+                .into_iter()
+                .map(|(kind, _)| kind)
+                .collect()
+        };
+
+        let character = r#"<character>
+            <skin file="soldier.skin" /><skin filename="officer.skin" />
+        </character>"#;
+        assert_eq!(
+            just_kinds(character, "vanilla/characters/default.character"),
+            [ReferenceKind::Skin, ReferenceKind::Skin]
+        );
+
+        let faction = r#"<faction><soldier>
+            <character filename="default.character" /><character file="officer.character" />
+        </soldier></faction>"#;
+        assert_eq!(
+            just_kinds(faction, "vanilla/factions/green.faction"),
+            [ReferenceKind::Character, ReferenceKind::Character]
+        );
+
+        let call = r#"<call file="base.call" key="artillery.call">
+            <round><projectile file="shell.projectile" /></round>
+            <vehicle key="jeep.vehicle" />
+        </call>"#;
+        assert_eq!(
+            kinds(call, "vanilla/calls/artillery.call"),
+            [
+                (ReferenceKind::Template, "base.call".to_string()),
+                (ReferenceKind::CallSpawn, "shell.projectile".to_string()),
+                (ReferenceKind::CallSpawn, "jeep.vehicle".to_string()),
+            ]
+        );
+
+        let vehicle = r#"<vehicle key="jeep.vehicle">
+            <turret weapon_key="m2.weapon"><weapon key="mk19.weapon" /></turret>
+        </vehicle>"#;
+        assert_eq!(
+            kinds(vehicle, "vanilla/vehicles/jeep.vehicle"),
+            [
+                (ReferenceKind::MountedWeapon, "m2.weapon".to_string()),
+                (ReferenceKind::MountedWeapon, "mk19.weapon".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_ignore_folders() {
+        assert_eq!(reference_key("textures/hud.png"), "hud.png");
+        assert_eq!(reference_key(" ak47.weapon "), "ak47.weapon");
+    }
+}
@@ -0,0 +1,206 @@
+//! Package resource lists
+//!
+//! The game does not load every `.weapon` / `.carry_item` on disk: each package lists the
+//! files it registers in `weapons/all_weapons.xml` and `items/all_carry_items.xml`. A
+//! [`ResourceIndex`] reads those lists for one scan so records can be flagged as referenced
+//! or orphaned, and reports list entries pointing at files that don't exist.
+//!
+//! Template-only files (only used through `file="..."`) are normally not listed and show
+//! up as orphaned too.
+
+use crate::diagnostics::{
+    parse_recovering, parse_xml, read_file, Diagnostic, DiagnosticCode, Severity,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// A kind of resource list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceListKind {
+    Weapons,
+    CarryItems,
+}
+
+impl ResourceListKind {
+    /// List file location inside a package folder
+    fn list_path(self) -> &'static str {
+        match self {
+            ResourceListKind::Weapons => "weapons/all_weapons.xml",
+            ResourceListKind::CarryItems => "items/all_carry_items.xml",
+        }
+    }
+
+    /// Whether `path` is a list file of this kind
+    pub fn is_list_file(self, path: &Path) -> bool {
+        path.ends_with(self.list_path())
+    }
+}
+
+/// Whether the game loads a scanned file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceStatus {
+    /// Listed in its package's resource list
+    Referenced,
+    /// Present on disk but not listed; the game does not register it
+    Orphaned,
+}
+
+/// Resource list XML structure (`<weapons>` / `<carry_items>` root)
+#[derive(Debug, Deserialize, Default)]
+struct RawResourceList {
+    #[serde(rename = "weapon", default)]
+    weapons: Vec<RawListEntry>,
+    #[serde(rename = "carry_item", default)]
+    carry_items: Vec<RawListEntry>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawListEntry {
+    #[serde(rename = "@file", default)]
+    file: Option<String>,
+}
+
+/// Listed files of one kind across all packages of a scan
+#[derive(Debug, Default)]
+pub struct ResourceIndex {
+    /// [`list_key`]s of every listed file
+    listed: HashSet<String>,
+    /// Unreadable lists and entries pointing at missing files
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ResourceIndex {
+    /// Read the `kind` list of every package below `package_roots`
+    pub fn load(kind: ResourceListKind, package_roots: &[PathBuf]) -> Self {
+        let mut index = Self::default();
+
+        for root in package_roots {
+            let Ok(packages) = std::fs::read_dir(root) else {
+                continue;
+            };
+            let mut list_files: Vec<PathBuf> = packages
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path().join(kind.list_path()))
+                .filter(|path| path.is_file())
+                .collect();
+            list_files.sort();
+
+            for list_file in list_files {
+                index.read_list(kind, &list_file);
+            }
+        }
+
+        index
+    }
+
+    fn read_list(&mut self, kind: ResourceListKind, list_file: &Path) {
+        let parsed = read_file(list_file).and_then(|content| {
+            parse_recovering(&content, &mut self.diagnostics, |content| {
+                parse_xml::<RawResourceList>(content, list_file)
+            })
+            .map(|(raw, _)| (content, raw))
+        });
+        let (content, raw) = match parsed {
+            Ok(parsed) => parsed,
+            Err(diagnostic) => {
+                self.diagnostics
+                    .push(diagnostic.with_severity(Severity::Warning));
+                return;
+            }
+        };
+
+        let entries = match kind {
+            ResourceListKind::Weapons => raw.weapons,
+            ResourceListKind::CarryItems => raw.carry_items,
+        };
+        let list_dir = list_file.parent().unwrap_or(list_file);
+
+        for file in entries.into_iter().filter_map(|e| e.file) {
+            let path = list_dir.join(file.replace('\\', "/"));
+            if !exists_ignoring_case(&path) {
+                let mut diagnostic = Diagnostic::error(
+                    DiagnosticCode::ReferenceNotFound,
+                    list_file,
+                    format!("Listed file does not exist: {}", file),
+                )
+                .with_severity(Severity::Warning);
+                if let Some(offset) = content.find(&format!("\"{}\"", file)) {
+                    diagnostic = diagnostic.at(&content, offset);
+                }
+                self.diagnostics.push(diagnostic);
+            }
+            self.listed.insert(list_key(&path));
+        }
+    }
+
+    pub fn status(&self, source_file: &str) -> ResourceStatus {
+        if self.listed.contains(&list_key(Path::new(source_file))) {
+            ResourceStatus::Referenced
+        } else {
+            ResourceStatus::Orphaned
+        }
+    }
+}
+
+/// Key a listed or scanned file is matched by. List entries are written by hand and the game
+/// resolves them like Windows does, so `.` components and case don't matter.
+fn list_key(path: &Path) -> String {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .to_lowercase()
+}
+
+/// Whether `path` exists, comparing its file name case-insensitively
+fn exists_ignoring_case(path: &Path) -> bool {
+    if path.is_file() {
+        return true;
+    }
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let name = name.to_string_lossy().to_lowercase();
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().to_lowercase() == name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_listed_files_regardless_of_dot_components_and_case() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let weapons = packages.join("vanilla/weapons");
+        std::fs::create_dir_all(&weapons).unwrap();
+        for file in ["ak47.weapon", "m4.weapon", "base.weapon"] {
+            std::fs::write(weapons.join(file), "<weapon/>").unwrap();
+        }
+        std::fs::write(
+            weapons.join("all_weapons.xml"),
+            r#"<weapons>
+                <weapon file="./ak47.weapon"/>
+                <weapon file="M4.weapon"/>
+                <weapon file="missing.weapon"/>
+            </weapons>"#,
+        )
+        .unwrap();
+
+        let index = ResourceIndex::load(ResourceListKind::Weapons, &[packages.to_path_buf()]);
+        let status = |file: &str| index.status(&weapons.join(file).to_string_lossy());
+
+        assert_eq!(status("ak47.weapon"), ResourceStatus::Referenced);
+        assert_eq!(status("m4.weapon"), ResourceStatus::Referenced);
+        assert_eq!(status("base.weapon"), ResourceStatus::Orphaned);
+        assert_eq!(index.diagnostics.len(), 1);
+        assert_eq!(index.diagnostics[0].code, DiagnosticCode::ReferenceNotFound);
+        assert_eq!(index.diagnostics[0].line, Some(4));
+    }
+}
@@ -0,0 +1,343 @@
+//! Persistent incremental scan cache
+//!
+//! Stores parsed scan records on disk, keyed by file path + mtime + size, together with
+//! content digests of every template file the records were resolved from. A rescan only
+//! re-parses files that changed on disk or whose template chain changed.
+
+use crate::diagnostics::Diagnostic;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// Bump whenever the on-disk format or any cached record shape changes.
+const CACHE_VERSION: u32 = 13;
+const CACHE_FOLDER: &str = "rwr-toolbox";
+const SCAN_CACHE_FOLDER: &str = "scan-cache";
+
+/// Suffix of the next temporary file `save` writes before renaming it into place
+static NEXT_TEMP_FILE: AtomicUsize = AtomicUsize::new(0);
+
+/// Records parsed from one file, plus non-fatal diagnostics (e.g. template warnings)
+#[derive(Debug, Clone)]
+pub struct Parsed<T> {
+    pub records: Vec<T>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Modification time (ms since epoch) and size of a file on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub modified: u64,
+    pub size: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_millis() as u64;
+        Some(Self {
+            modified,
+            size: metadata.len(),
+        })
+    }
+}
+
+/// Template file a cached entry was resolved from.
+/// `digest` is `None` when the template did not exist at scan time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Dependency {
+    path: PathBuf,
+    digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry<T> {
+    stamp: FileStamp,
+    dependencies: Vec<Dependency>,
+    records: Vec<T>,
+    /// Warnings produced while parsing, replayed on cache hits
+    diagnostics: Vec<Diagnostic>,
+}
+
+type EntryMap<T> = HashMap<PathBuf, CacheEntry<T>>;
+
+/// On-disk layout; `E` is the entry map (owned when loading, borrowed when saving)
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile<E> {
+    version: u32,
+    source_directory: String,
+    entries: E,
+}
+
+/// Cache hit statistics reported back to the frontend
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: usize,
+    pub reparsed: usize,
+}
+
+/// Incremental cache for one scan kind (weapons, items, ...) and one source directory.
+///
+/// Entries loaded from disk are only carried over to the next save when they are looked up
+/// during the current scan, so files deleted from the packages folder drop out automatically.
+pub struct ScanCache<T> {
+    path: Option<PathBuf>,
+    source_directory: String,
+    previous: EntryMap<T>,
+    current: Mutex<EntryMap<T>>,
+    digests: Mutex<HashMap<PathBuf, Option<String>>>,
+    hits: AtomicUsize,
+    reparsed: AtomicUsize,
+}
+
+impl<T> ScanCache<T>
+where
+    T: Clone + Serialize + DeserializeOwned,
+{
+    /// Load the cache for `kind` scans of `source_directory`.
+    /// A missing, unreadable or outdated cache file yields an empty cache.
+    pub fn load(kind: &str, source_directory: &str) -> Self {
+        Self::load_from(cache_file_path(kind, source_directory), source_directory)
+    }
+
+    /// Load the cache stored at `path`; `None` keeps the cache in memory only.
+    fn load_from(path: Option<PathBuf>, source_directory: &str) -> Self {
+        let previous = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str::<CacheFile<EntryMap<T>>>(&content).ok())
+            .filter(|file| {
+                file.version == CACHE_VERSION && file.source_directory == source_directory
+            })
+            .map(|file| file.entries)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            source_directory: source_directory.to_string(),
+            previous,
+            current: Mutex::new(HashMap::new()),
+            digests: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            reparsed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Return the cached records for `file` if it and all its templates are unchanged,
+    /// otherwise run `parse` and remember its result.
+    ///
+    /// `parse` receives a dependency list to fill with every template path it resolved and a
+    /// list for non-fatal diagnostics. Failed parses are never cached, so broken files are
+    /// retried on every scan.
+    pub fn get_or_parse<E>(
+        &self,
+        file: &Path,
+        parse: impl FnOnce(&mut Vec<PathBuf>, &mut Vec<Diagnostic>) -> Result<Vec<T>, E>,
+    ) -> Result<Parsed<T>, E> {
+        let stamp = FileStamp::of(file);
+
+        if let (Some(stamp), Some(entry)) = (stamp, self.previous.get(file)) {
+            if entry.stamp == stamp && self.dependencies_unchanged(&entry.dependencies) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                let parsed = Parsed {
+                    records: entry.records.clone(),
+                    diagnostics: entry.diagnostics.clone(),
+                };
+                self.insert(file, entry.clone());
+                return Ok(parsed);
+            }
+        }
+
+        self.reparsed.fetch_add(1, Ordering::Relaxed);
+        let mut dependency_paths = Vec::new();
+        let mut diagnostics = Vec::new();
+        let records = parse(&mut dependency_paths, &mut diagnostics)?;
+
+        if let Some(stamp) = stamp {
+            let dependencies = dependency_paths
+                .into_iter()
+                .map(|path| Dependency {
+                    digest: self.digest(&path),
+                    path,
+                })
+                .collect();
+            self.insert(
+                file,
+                CacheEntry {
+                    stamp,
+                    dependencies,
+                    records: records.clone(),
+                    diagnostics: diagnostics.clone(),
+                },
+            );
+        }
+
+        Ok(Parsed {
+            records,
+            diagnostics,
+        })
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            reparsed: self.reparsed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Write every entry used during this scan back to disk.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let current = self.current.lock().map_err(|e| e.to_string())?;
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            source_directory: self.source_directory.clone(),
+            entries: &*current,
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create scan cache directory: {}", e))?;
+        }
+        let json = serde_json::to_string(&file)
+            .map_err(|e| format!("Failed to serialize scan cache: {}", e))?;
+
+        // Scans of the same directory (a table, a lint, a watch session) may save at the same
+        // time; each writes its own file and the last rename wins instead of interleaving.
+        let temp_path = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            NEXT_TEMP_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&temp_path, json)
+            .and_then(|()| std::fs::rename(&temp_path, path))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&temp_path);
+                format!("Failed to write scan cache: {}", e)
+            })
+    }
+
+    /// Template files the current entry for `file` was resolved from
+    pub fn dependencies(&self, file: &Path) -> Vec<PathBuf> {
+        self.current
+            .lock()
+            .ok()
+            .and_then(|current| {
+                current
+                    .get(file)
+                    .map(|entry| entry.dependencies.iter().map(|d| d.path.clone()).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Drop everything known about `path` so the next lookup re-parses it and
+    /// re-hashes it when used as a template.
+    pub fn invalidate(&mut self, path: &Path) {
+        self.previous.remove(path);
+        if let Ok(current) = self.current.get_mut() {
+            current.remove(path);
+        }
+        if let Ok(digests) = self.digests.get_mut() {
+            digests.remove(path);
+        }
+    }
+
+    fn insert(&self, file: &Path, entry: CacheEntry<T>) {
+        if let Ok(mut current) = self.current.lock() {
+            current.insert(file.to_path_buf(), entry);
+        }
+    }
+
+    fn dependencies_unchanged(&self, dependencies: &[Dependency]) -> bool {
+        dependencies
+            .iter()
+            .all(|dep| self.digest(&dep.path) == dep.digest)
+    }
+
+    /// md5 of a template file's content, memoized for the duration of the scan
+    /// (the same template is typically shared by dozens of files).
+    fn digest(&self, path: &Path) -> Option<String> {
+        if let Some(digest) = self.digests.lock().ok().and_then(|d| d.get(path).cloned()) {
+            return digest;
+        }
+
+        let digest = std::fs::read(path)
+            .ok()
+            .map(|content| format!("{:x}", md5::compute(content)));
+        if let Ok(mut digests) = self.digests.lock() {
+            digests.insert(path.to_path_buf(), digest.clone());
+        }
+        digest
+    }
+}
+
+/// Folder the caches are persisted in; test builds keep every cache in memory so they never
+/// touch the user's cache
+fn cache_dir() -> Option<PathBuf> {
+    if cfg!(test) {
+        return None;
+    }
+    dirs::cache_dir().map(|dir| dir.join(CACHE_FOLDER).join(SCAN_CACHE_FOLDER))
+}
+
+/// One cache file per scan kind and source directory,
+/// e.g. `weapons-<md5 of directory>.json`
+fn cache_file_path(kind: &str, source_directory: &str) -> Option<PathBuf> {
+    let key = format!("{:x}", md5::compute(source_directory.as_bytes()));
+    cache_dir().map(|dir| dir.join(format!("{}-{}.json", kind, key)))
+}
+
+/// Delete every persisted scan cache, forcing the next scans to re-parse all files
+#[tauri::command]
+pub async fn clear_scan_cache() -> Result<(), String> {
+    let Some(dir) = cache_dir() else {
+        return Ok(());
+    };
+    if !dir.exists() {
+        return Ok(());
+    }
+    std::fs::remove_dir_all(&dir).map_err(|e| format!("Failed to clear scan cache: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_entries_are_hits_on_the_next_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.weapon");
+        std::fs::write(&file, "<weapon/>").unwrap();
+        let cache_path = dir.path().join("weapons.json");
+
+        let cache = ScanCache::<String>::load_from(Some(cache_path.clone()), "packages");
+        cache
+            .get_or_parse(&file, |_, _| Ok::<_, ()>(vec!["a".to_string()]))
+            .unwrap();
+        cache.save().unwrap();
+
+        let cache = ScanCache::<String>::load_from(Some(cache_path), "packages");
+        let parsed = cache
+            .get_or_parse(&file, |_, _| Err::<Vec<String>, _>("re-parsed"))
+            .unwrap();
+        assert_eq!(parsed.records, ["a"]);
+        assert_eq!(cache.stats().hits, 1);
+
+        // Only the cache file itself is left behind
+        let files: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(files.len(), 2);
+    }
+}
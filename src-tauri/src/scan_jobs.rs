@@ -0,0 +1,72 @@
+//! Scan job registry
+//!
+//! Every streaming scan runs in the background under a job id. `cancel_scan` flips the job's
+//! cancel token; discovery and the rayon parse loop poll it and stop early, after which the
+//! frontend receives `ScanEvent::Cancelled` instead of `Finished`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+/// Shared cancellation flag polled by long-running scan work
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Active scan jobs (managed Tauri state)
+#[derive(Default)]
+pub struct ScanJobs {
+    next_id: AtomicU32,
+    active: Arc<Mutex<HashMap<u32, CancelToken>>>,
+}
+
+/// Handle owned by a running scan; unregisters the job when dropped
+pub struct ScanJob {
+    pub id: u32,
+    pub token: CancelToken,
+    active: Arc<Mutex<HashMap<u32, CancelToken>>>,
+}
+
+impl ScanJobs {
+    pub fn register(&self) -> ScanJob {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = CancelToken::default();
+        if let Ok(mut active) = self.active.lock() {
+            active.insert(id, token.clone());
+        }
+        ScanJob {
+            id,
+            token,
+            active: Arc::clone(&self.active),
+        }
+    }
+}
+
+impl Drop for ScanJob {
+    fn drop(&mut self) {
+        if let Ok(mut active) = self.active.lock() {
+            active.remove(&self.id);
+        }
+    }
+}
+
+/// Cancel a running scan started by `scan_weapons` / `scan_items`.
+/// Cancelling a job that already finished is a no-op.
+#[tauri::command]
+pub async fn cancel_scan(job_id: u32, jobs: State<'_, ScanJobs>) -> Result<(), String> {
+    let active = jobs.active.lock().map_err(|e| e.to_string())?;
+    if let Some(token) = active.get(&job_id) {
+        token.cancel();
+    }
+    Ok(())
+}
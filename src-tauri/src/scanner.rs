@@ -0,0 +1,508 @@
+//! Shared package scanning framework
+//!
+//! File-type specific scanners (weapons, items, ...) implement [`PackageScanner`] so that
+//! discovery, caching, streaming, cancellation and live watching only have to be written once.
+
+use crate::diagnostics::Diagnostic;
+use crate::packages::{
+    find_duplicate_keys, overlay, DuplicateKey, KeyDefinition, LoadOrder, Provenance,
+};
+use crate::resource_lists::{ResourceIndex, ResourceListKind};
+use crate::scan_cache::{CacheStats, Parsed, ScanCache};
+use crate::scan_jobs::{CancelToken, ScanJobs};
+use crate::utils::resolve_packages_dirs;
+use crate::ScanEvent;
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::ipc::Channel;
+use walkdir::WalkDir;
+
+/// Files per `Chunk` event
+const BATCH_SIZE: usize = 50;
+
+/// Parsed files that may wait in the queue before the parse workers block
+const QUEUE_CAPACITY: usize = BATCH_SIZE * 4;
+
+/// Longest time a partially filled batch is held back before being sent
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A file type found inside `packages/` that can be parsed into frontend records
+pub(crate) trait PackageScanner {
+    type Record: Clone + Serialize + DeserializeOwned + Send + Sync + 'static;
+
+    /// Short name used for cache files and debug logs (e.g. "weapons")
+    const KIND: &'static str;
+
+    /// Whether `path` is a file this scanner parses
+    fn matches(path: &Path) -> bool;
+
+    /// Parse one discovered file through the scan cache.
+    /// `index` is the file's position in discovery order and is used to build record ids.
+    fn parse_cached(
+        cache: &ScanCache<Self::Record>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Self::Record>, Diagnostic>;
+
+    /// Stable id of a record, as sent to the frontend
+    fn record_id(record: &Self::Record) -> &str;
+
+    /// The key a record registers in game, if it takes part in duplicate key detection
+    fn key_definition(record: &Self::Record) -> Option<KeyDefinition>;
+
+    /// Resource list that decides which of the scanned files the game loads, if any
+    const RESOURCE_LIST: Option<ResourceListKind> = None;
+
+    /// Flag a record as referenced/orphaned; only called when `RESOURCE_LIST` is set
+    fn apply_resource_index(_record: &mut Self::Record, _index: &ResourceIndex) {}
+
+    /// Drop the record's attribute bag, for scans that didn't ask for it
+    fn clear_attributes(_record: &mut Self::Record) {}
+
+    /// Record which package an effective scan took the record from
+    fn set_provenance(_record: &mut Self::Record, _provenance: Provenance) {}
+}
+
+/// Per-scan switches chosen by the frontend
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ScanOptions {
+    /// Keep the flattened attribute bag of every record (see `attributes`)
+    pub attributes: bool,
+    /// Keep only the definition the game loads for each key, with its provenance.
+    /// Only honoured by [`collect_scan`]: overlaying needs every record up front.
+    pub effective: bool,
+}
+
+/// Everything besides the records that a [`collect_scan`] reports to the frontend
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanSummary {
+    /// Parse errors, template warnings and resource list problems
+    pub errors: Vec<Diagnostic>,
+    /// Keys defined in more than one file, with the definition the game uses
+    pub duplicate_keys: Vec<DuplicateKey>,
+    /// Scan duration in milliseconds
+    pub scan_time: u64,
+    /// How many files were served from the scan cache vs re-parsed
+    pub cache_stats: CacheStats,
+}
+
+/// Records of a [`collect_scan`] and what else it found
+pub(crate) struct Collected<T> {
+    pub records: Vec<T>,
+    pub summary: ScanSummary,
+}
+
+/// Read the resource lists gating `S` files, if there are any for this file type
+pub(crate) fn resource_index<S: PackageScanner>(roots: &[PathBuf]) -> Option<ResourceIndex> {
+    S::RESOURCE_LIST.map(|kind| ResourceIndex::load(kind, roots))
+}
+
+/// Apply the per-scan parts of freshly parsed (or cached) records before they are sent
+pub(crate) fn finish_records<S: PackageScanner>(
+    records: &mut [S::Record],
+    resource_index: Option<&ResourceIndex>,
+    options: ScanOptions,
+) {
+    for record in records {
+        if let Some(index) = resource_index {
+            S::apply_resource_index(record, index);
+        }
+        if !options.attributes {
+            S::clear_attributes(record);
+        }
+    }
+}
+
+/// Resolve the packages roots for `source_directory`, failing when none of them exist
+pub(crate) fn existing_package_roots(source_directory: &str) -> Result<Vec<PathBuf>, String> {
+    let package_roots = resolve_packages_dirs(Path::new(source_directory));
+
+    if package_roots.iter().all(|p| !p.exists()) {
+        let paths = package_roots
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!("Directory not found (tried: {paths})"));
+    }
+
+    Ok(package_roots.into_iter().filter(|p| p.exists()).collect())
+}
+
+/// Collect all files matching `S` across packages roots as `(packages_root, file)` pairs.
+/// Stops early (returning a partial list) once `cancel` is set.
+pub(crate) fn discover_files<S: PackageScanner>(
+    roots: &[PathBuf],
+    cancel: &CancelToken,
+) -> Vec<(PathBuf, PathBuf)> {
+    let mut files = Vec::new();
+    for root in roots {
+        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+            if cancel.is_cancelled() {
+                return files;
+            }
+            if S::matches(entry.path()) {
+                files.push((root.clone(), entry.path().to_path_buf()));
+            }
+        }
+    }
+    files
+}
+
+/// Discover, parse and finish every `S` file below the packages roots of `source_directory`
+/// and return them in one payload.
+///
+/// Backs the `scan_*_collect` commands (for WebViews that don't deliver `Channel` messages
+/// reliably) and every command that needs the records themselves.
+pub(crate) fn collect_scan<S: PackageScanner>(
+    source_directory: &str,
+    options: ScanOptions,
+) -> Result<Collected<S::Record>, String> {
+    let started = std::time::Instant::now();
+
+    let package_roots = existing_package_roots(source_directory)?;
+    let files = discover_files::<S>(&package_roots, &CancelToken::default());
+
+    let cache = ScanCache::load(S::KIND, source_directory);
+    let results: Vec<Result<Parsed<S::Record>, Diagnostic>> = files
+        .into_par_iter()
+        .enumerate()
+        .map(|(index, (packages_root, file))| {
+            S::parse_cached(&cache, &file, &packages_root, index, source_directory)
+        })
+        .collect();
+
+    let cache_stats = cache.stats();
+    if let Err(_e) = cache.save() {
+        #[cfg(debug_assertions)]
+        eprintln!("[collect_{}] cache save failed: {}", S::KIND, _e);
+    }
+
+    let mut records: Vec<S::Record> = Vec::new();
+    let mut errors: Vec<Diagnostic> = Vec::new();
+    for result in results {
+        match result {
+            Ok(parsed) => {
+                records.extend(parsed.records);
+                errors.extend(parsed.diagnostics);
+            }
+            Err(diagnostic) => errors.push(diagnostic),
+        }
+    }
+
+    let resource_index = resource_index::<S>(&package_roots);
+    finish_records::<S>(&mut records, resource_index.as_ref(), options);
+    errors.extend(
+        resource_index
+            .into_iter()
+            .flat_map(|index| index.diagnostics),
+    );
+
+    let load_order = LoadOrder::new(&package_roots);
+    let duplicate_keys = find_duplicate_keys(
+        records.iter().filter_map(S::key_definition).collect(),
+        &load_order,
+    );
+    if options.effective {
+        records = overlay(records, &load_order, S::key_definition, S::set_provenance);
+    }
+
+    Ok(Collected {
+        records,
+        summary: ScanSummary {
+            errors,
+            duplicate_keys,
+            scan_time: started.elapsed().as_millis() as u64,
+            cache_stats,
+        },
+    })
+}
+
+/// `records` when the frontend already holds them, else the records of a fresh effective
+/// [`collect_scan`] of `source_directory`.
+///
+/// Lets commands that combine several record kinds reuse the rows the tables already show
+/// instead of rescanning every package on each query.
+pub(crate) fn records_or_scan<S: PackageScanner>(
+    records: Option<Vec<S::Record>>,
+    source_directory: &str,
+) -> Result<Vec<S::Record>, String> {
+    if let Some(records) = records {
+        return Ok(records);
+    }
+    let options = ScanOptions {
+        effective: true,
+        ..Default::default()
+    };
+    Ok(collect_scan::<S>(source_directory, options)?.records)
+}
+
+/// Start a background streaming scan of `source_directory` and return its job id.
+///
+/// Fails immediately when no packages root exists; everything else is reported
+/// through `on_event`, ending with either `Finished` or `Cancelled`.
+pub(crate) fn spawn_scan<S>(
+    jobs: &ScanJobs,
+    source_directory: String,
+    on_event: Channel<ScanEvent<S::Record>>,
+) -> Result<u32, String>
+where
+    S: PackageScanner + 'static,
+{
+    spawn_scan_with::<S>(jobs, source_directory, ScanOptions::default(), on_event)
+}
+
+/// [`spawn_scan`] with non-default [`ScanOptions`]
+pub(crate) fn spawn_scan_with<S>(
+    jobs: &ScanJobs,
+    source_directory: String,
+    options: ScanOptions,
+    on_event: Channel<ScanEvent<S::Record>>,
+) -> Result<u32, String>
+where
+    S: PackageScanner + 'static,
+{
+    let package_roots = existing_package_roots(&source_directory)?;
+
+    #[cfg(debug_assertions)]
+    {
+        eprintln!(
+            "[scan_{}] source_directory={} roots={}",
+            S::KIND,
+            source_directory,
+            package_roots
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" | ")
+        );
+    }
+
+    let job = jobs.register();
+    let job_id = job.id;
+
+    std::thread::spawn(move || {
+        if let Err(_e) = stream_scan::<S>(
+            &package_roots,
+            &source_directory,
+            options,
+            &on_event,
+            &job.token,
+        ) {
+            #[cfg(debug_assertions)]
+            eprintln!("[scan_{}] job {} aborted: {}", S::KIND, job.id, _e);
+        }
+    });
+
+    Ok(job_id)
+}
+
+/// Discover and parse every `S` file, sending results over `on_event` as they finish.
+///
+/// Rayon workers push each parsed file into a bounded queue; this thread drains it into
+/// `Chunk` events of up to `BATCH_SIZE` files, so the first rows arrive while the rest of
+/// the packages folder is still being parsed and memory stays bounded by the queue.
+///
+/// Streamed scans have no effective mode: which definition of a key wins is only known
+/// once every file is parsed, after the rows have been sent.
+fn stream_scan<S: PackageScanner>(
+    package_roots: &[PathBuf],
+    source_directory: &str,
+    options: ScanOptions,
+    on_event: &Channel<ScanEvent<S::Record>>,
+    cancel: &CancelToken,
+) -> Result<(), String> {
+    let send_event = |evt: ScanEvent<S::Record>| -> Result<(), String> {
+        on_event
+            .send(evt)
+            .map_err(|e| format!("Failed to send scan event: {e}"))
+    };
+
+    // Collect all files across possible packages roots (macOS app bundle + external media).
+    let files = discover_files::<S>(package_roots, cancel);
+    if cancel.is_cancelled() {
+        return send_event(ScanEvent::Cancelled);
+    }
+
+    #[cfg(debug_assertions)]
+    {
+        eprintln!("[scan_{}] discovered_files={}", S::KIND, files.len());
+    }
+
+    if files.is_empty() {
+        // Nothing to scan, but still notify the frontend so it can stop loading.
+        return send_event(ScanEvent::Finished);
+    }
+
+    let total = files.len();
+    send_event(ScanEvent::Progress { current: 0, total })?;
+
+    let resource_index = resource_index::<S>(package_roots);
+    for diagnostic in resource_index.iter().flat_map(|i| &i.diagnostics) {
+        send_event(ScanEvent::Diagnostic(diagnostic.clone()))?;
+    }
+
+    let cache = ScanCache::load(S::KIND, source_directory);
+    let (tx, rx) = mpsc::sync_channel::<Result<Parsed<S::Record>, Diagnostic>>(QUEUE_CAPACITY);
+
+    let streamed = std::thread::scope(|scope| {
+        let cache = &cache;
+        scope.spawn(move || {
+            // `try_for_each_with` stops handing out work once cancelled or once the
+            // receiving side gave up (frontend dropped the channel).
+            let _ = files.into_par_iter().enumerate().try_for_each_with(
+                tx,
+                |tx, (index, (packages_root, file))| {
+                    if cancel.is_cancelled() {
+                        return Err(());
+                    }
+                    let result =
+                        S::parse_cached(cache, &file, &packages_root, index, source_directory);
+                    tx.send(result).map_err(|_| ())
+                },
+            );
+        });
+
+        // `rx` is dropped when draining stops early, which unblocks and stops the workers.
+        drain_results::<S>(
+            rx,
+            total,
+            resource_index.as_ref(),
+            options,
+            &send_event,
+            cancel,
+        )
+    });
+    let definitions = streamed?;
+
+    if cancel.is_cancelled() {
+        // The cache only holds the files visited so far; keep the previous one on disk.
+        return send_event(ScanEvent::Cancelled);
+    }
+
+    send_event(ScanEvent::Duplicates(find_duplicate_keys(
+        definitions,
+        &LoadOrder::new(package_roots),
+    )))?;
+
+    let cache_stats = cache.stats();
+    if let Err(_e) = cache.save() {
+        #[cfg(debug_assertions)]
+        eprintln!("[scan_{}] cache save failed: {}", S::KIND, _e);
+    }
+
+    #[cfg(debug_assertions)]
+    {
+        eprintln!(
+            "[scan_{}] cache_hits={} reparsed={}",
+            S::KIND,
+            cache_stats.hits,
+            cache_stats.reparsed
+        );
+    }
+
+    send_event(ScanEvent::CacheStats {
+        hits: cache_stats.hits,
+        reparsed: cache_stats.reparsed,
+    })?;
+    send_event(ScanEvent::Finished)
+}
+
+/// Forward parse results from the worker queue as `Chunk` / `Diagnostic` / `Progress` events,
+/// finishing records (resource status, attribute bags) on the way.
+/// Returns the key definitions of every streamed record for duplicate detection.
+///
+/// A batch is flushed once it holds `BATCH_SIZE` files or when no result arrived for
+/// `FLUSH_INTERVAL`, so slow files (deep template chains, cold disk) don't stall the UI.
+fn drain_results<S: PackageScanner>(
+    rx: mpsc::Receiver<Result<Parsed<S::Record>, Diagnostic>>,
+    total: usize,
+    resource_index: Option<&ResourceIndex>,
+    options: ScanOptions,
+    send_event: &impl Fn(ScanEvent<S::Record>) -> Result<(), String>,
+    cancel: &CancelToken,
+) -> Result<Vec<KeyDefinition>, String> {
+    let mut records: Vec<S::Record> = Vec::new();
+    let mut definitions: Vec<KeyDefinition> = Vec::new();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut pending = 0;
+    let mut current = 0;
+
+    #[cfg(debug_assertions)]
+    let (mut ok_count, mut err_count, mut record_count) = (0usize, 0usize, 0usize);
+
+    loop {
+        let received = rx.recv_timeout(FLUSH_INTERVAL);
+        if cancel.is_cancelled() {
+            return Ok(definitions);
+        }
+
+        let disconnected = match received {
+            Ok(result) => {
+                pending += 1;
+                match result {
+                    Ok(mut parsed) => {
+                        #[cfg(debug_assertions)]
+                        {
+                            ok_count += 1;
+                            record_count += parsed.records.len();
+                        }
+                        finish_records::<S>(&mut parsed.records, resource_index, options);
+                        definitions.extend(parsed.records.iter().filter_map(S::key_definition));
+                        records.extend(parsed.records);
+                        diagnostics.extend(parsed.diagnostics);
+                    }
+                    Err(diagnostic) => {
+                        #[cfg(debug_assertions)]
+                        {
+                            err_count += 1;
+                        }
+                        diagnostics.push(diagnostic);
+                    }
+                }
+                if pending < BATCH_SIZE {
+                    continue;
+                }
+                false
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => false,
+            Err(mpsc::RecvTimeoutError::Disconnected) => true,
+        };
+
+        if pending > 0 {
+            if !records.is_empty() {
+                send_event(ScanEvent::Chunk(std::mem::take(&mut records)))?;
+            }
+            for diagnostic in diagnostics.drain(..) {
+                send_event(ScanEvent::Diagnostic(diagnostic))?;
+            }
+            current += pending;
+            pending = 0;
+            send_event(ScanEvent::Progress { current, total })?;
+        }
+
+        if disconnected {
+            break;
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    {
+        eprintln!(
+            "[scan_{}] parsed_ok={} parsed_err={} records={}",
+            S::KIND,
+            ok_count,
+            err_count,
+            record_count
+        );
+    }
+
+    Ok(definitions)
+}
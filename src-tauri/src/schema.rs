@@ -0,0 +1,439 @@
+//! Attribute schema inference
+//!
+//! The `Raw*` structs silently ignore attributes they don't declare, so a typo such as
+//! `magzine_size` just leaves the field at its template value. [`infer_schema`] learns the
+//! attributes vanilla uses per file type, with their value types and ranges, from the
+//! [`attribute_bags`] of every vanilla file; [`check_bag`] compares a mod record against it.
+//!
+//! Schema paths are bag paths without sibling positions (`stance[1].accuracy` →
+//! `stance.accuracy`).
+
+use crate::attributes::{attribute_bags, AttributeBag};
+use crate::diagnostics::{read_file, Severity};
+use crate::scanner::existing_package_roots;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+/// Checked file types: extension and the element holding one record
+const SCHEMA_KINDS: &[(&str, &str)] = &[
+    ("weapon", "weapon"),
+    ("carry_item", "carry_item"),
+    ("visual_item", "visual_item"),
+    ("projectile", "projectile"),
+];
+
+/// Largest edit distance at which an unknown name counts as a typo of a known one
+const MAX_TYPO_DISTANCE: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    Integer,
+    Number,
+    Text,
+}
+
+/// What vanilla does with one attribute path
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeSpec {
+    pub path: String,
+    pub value_type: ValueType,
+    /// Numeric types only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Number of vanilla records setting it
+    pub occurrences: usize,
+}
+
+/// Known attributes of one file type
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KindSchema {
+    /// File extension (`weapon`, `carry_item`, ...)
+    pub kind: String,
+    /// Number of vanilla records the schema was learned from
+    pub records: usize,
+    /// Sorted by path
+    pub attributes: Vec<AttributeSpec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeIssueKind {
+    /// Neither the attribute nor a similar one is known
+    Unknown,
+    /// Not known, but close to a known name (see `suggestion`)
+    Mistyped,
+    /// Numeric value outside the range vanilla uses
+    OutOfRange,
+    /// Non-numeric value for a numeric attribute
+    WrongType,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeIssue {
+    pub kind: AttributeIssueKind,
+    pub severity: Severity,
+    /// Absolute path of the mod file
+    pub file: String,
+    /// Bag path as written, with sibling positions
+    pub path: String,
+    pub value: String,
+    /// Known path the attribute was probably meant to be
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    pub message: String,
+}
+
+/// Result of checking the mod packages of a directory
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeCheckResult {
+    pub schemas: Vec<KindSchema>,
+    /// Sorted by file, then path
+    pub issues: Vec<AttributeIssue>,
+}
+
+/// Learn the attribute schema of every checked file type from the vanilla package.
+#[tauri::command]
+pub async fn infer_attribute_schema(
+    game_path: String,
+    directory: Option<String>,
+) -> Result<Vec<KindSchema>, String> {
+    let roots = existing_package_roots(&directory.unwrap_or(game_path))?;
+    Ok(learn_schemas(&roots))
+}
+
+/// Check the attributes of every mod file against the vanilla schema.
+///
+/// With `package_name` set only that package is checked, otherwise every package except
+/// vanilla.
+#[tauri::command]
+pub async fn check_attributes(
+    game_path: String,
+    directory: Option<String>,
+    package_name: Option<String>,
+) -> Result<AttributeCheckResult, String> {
+    let roots = existing_package_roots(&directory.unwrap_or(game_path))?;
+    let schemas = learn_schemas(&roots);
+
+    let package_dirs: Vec<PathBuf> = roots
+        .iter()
+        .filter_map(|root| std::fs::read_dir(root).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|dir| dir.is_dir())
+        .filter(|dir| {
+            let name = dir.file_name().map(|n| n.to_string_lossy().to_string());
+            match &package_name {
+                Some(package) => name.as_deref() == Some(package.as_str()),
+                None => name.as_deref() != Some("vanilla"),
+            }
+        })
+        .collect();
+
+    let mut issues: Vec<AttributeIssue> = schemas
+        .par_iter()
+        .flat_map(|schema| {
+            let element = element_of(&schema.kind);
+            record_files(&package_dirs, &schema.kind)
+                .into_par_iter()
+                .flat_map_iter(move |file| {
+                    let bags = read_file(&file)
+                        .map(|content| attribute_bags(&content, element))
+                        .unwrap_or_default();
+                    let file = file.to_string_lossy().to_string();
+                    bags.into_iter()
+                        .flat_map(|bag| check_bag(schema, &bag, &file))
+                        .collect::<Vec<_>>()
+                })
+        })
+        .collect();
+    issues.sort_by(|a, b| (&a.file, &a.path).cmp(&(&b.file, &b.path)));
+
+    Ok(AttributeCheckResult { schemas, issues })
+}
+
+fn element_of(kind: &str) -> &'static str {
+    SCHEMA_KINDS
+        .iter()
+        .find(|(extension, _)| *extension == kind)
+        .map(|(_, element)| *element)
+        .unwrap_or_default()
+}
+
+/// Files with extension `kind` below `dirs`
+fn record_files(dirs: &[PathBuf], kind: &str) -> Vec<PathBuf> {
+    dirs.iter()
+        .flat_map(|dir| WalkDir::new(dir).into_iter().filter_map(|e| e.ok()))
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == kind))
+        .collect()
+}
+
+fn learn_schemas(roots: &[PathBuf]) -> Vec<KindSchema> {
+    let vanilla: Vec<PathBuf> = roots.iter().map(|root| root.join("vanilla")).collect();
+    SCHEMA_KINDS
+        .par_iter()
+        .map(|(kind, element)| {
+            let bags: Vec<AttributeBag> = record_files(&vanilla, kind)
+                .par_iter()
+                .filter_map(|file| read_file(file).ok())
+                .flat_map_iter(|content| attribute_bags(&content, element))
+                .collect();
+            infer_schema(kind, &bags)
+        })
+        .collect()
+}
+
+/// `stance[1].accuracy` → `stance.accuracy`
+fn schema_path(path: &str) -> String {
+    path.split('.')
+        .map(|segment| segment.split('[').next().unwrap_or(segment))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn value_type(value: &str) -> ValueType {
+    let value = value.trim();
+    if value.parse::<i64>().is_ok() {
+        ValueType::Integer
+    } else if value.parse::<f64>().is_ok() {
+        ValueType::Number
+    } else {
+        ValueType::Text
+    }
+}
+
+/// Learn a schema from the bags of the vanilla records of one file type
+pub(crate) fn infer_schema(kind: &str, bags: &[AttributeBag]) -> KindSchema {
+    let mut specs: BTreeMap<String, AttributeSpec> = BTreeMap::new();
+    for bag in bags {
+        for (path, value) in bag {
+            let path = schema_path(path);
+            let found = value_type(value);
+            let number = value.trim().parse::<f64>().ok();
+            let spec = specs.entry(path.clone()).or_insert(AttributeSpec {
+                path,
+                value_type: found,
+                min: number,
+                max: number,
+                occurrences: 0,
+            });
+            // Integer < Number < Text: the widest type seen wins
+            spec.value_type = spec.value_type.max(found);
+            if spec.value_type == ValueType::Text {
+                spec.min = None;
+                spec.max = None;
+            } else if let Some(number) = number {
+                spec.min = spec.min.map(|min| min.min(number));
+                spec.max = spec.max.map(|max| max.max(number));
+            }
+            spec.occurrences += 1;
+        }
+    }
+
+    KindSchema {
+        kind: kind.to_string(),
+        records: bags.len(),
+        attributes: specs.into_values().collect(),
+    }
+}
+
+/// Compare the attributes of one mod record with the schema of its file type
+pub(crate) fn check_bag(
+    schema: &KindSchema,
+    bag: &AttributeBag,
+    file: &str,
+) -> Vec<AttributeIssue> {
+    let known: BTreeMap<&str, &AttributeSpec> = schema
+        .attributes
+        .iter()
+        .map(|spec| (spec.path.as_str(), spec))
+        .collect();
+
+    let mut issues = Vec::new();
+    for (path, value) in bag {
+        let issue = |kind, severity, suggestion, message| AttributeIssue {
+            kind,
+            severity,
+            file: file.to_string(),
+            path: path.clone(),
+            value: value.clone(),
+            suggestion,
+            message,
+        };
+        let normalized = schema_path(path);
+
+        let Some(spec) = known.get(normalized.as_str()) else {
+            issues.push(match closest_path(&normalized, known.keys().copied()) {
+                Some(suggestion) => issue(
+                    AttributeIssueKind::Mistyped,
+                    Severity::Warning,
+                    Some(suggestion.to_string()),
+                    format!("Unknown attribute {}, did you mean {}?", path, suggestion),
+                ),
+                None => issue(
+                    AttributeIssueKind::Unknown,
+                    Severity::Warning,
+                    None,
+                    format!("Unknown attribute {} for {} files", path, schema.kind),
+                ),
+            });
+            continue;
+        };
+
+        if spec.value_type == ValueType::Text {
+            continue;
+        }
+        let Ok(number) = value.trim().parse::<f64>() else {
+            issues.push(issue(
+                AttributeIssueKind::WrongType,
+                Severity::Warning,
+                None,
+                format!("{} expects a number, found \"{}\"", path, value),
+            ));
+            continue;
+        };
+        if let (Some(min), Some(max)) = (spec.min, spec.max) {
+            if number < min || number > max {
+                issues.push(issue(
+                    AttributeIssueKind::OutOfRange,
+                    Severity::Info,
+                    None,
+                    format!(
+                        "{} = {} is outside the vanilla range {} to {}",
+                        path, value, min, max
+                    ),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// Known path within [`MAX_TYPO_DISTANCE`] edits of `path`, preferring the closest one.
+///
+/// Only paths with the same number of segments are compared, so a typo in an element name
+/// (`specifcation.name`) is found as well as one in the attribute name.
+fn closest_path<'a>(path: &str, known: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let depth = path.split('.').count();
+    known
+        .filter(|candidate| candidate.split('.').count() == depth)
+        .map(|candidate| (edit_distance(path, candidate), candidate))
+        .filter(|(distance, _)| *distance <= MAX_TYPO_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bag(pairs: &[(&str, &str)]) -> AttributeBag {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn infers_types_and_ranges() {
+        let schema = infer_schema(
+            "weapon",
+            &[
+                bag(&[
+                    ("specification.magazine_size", "30"),
+                    ("stance.accuracy", "0.5"),
+                ]),
+                bag(&[
+                    ("specification.magazine_size", "5"),
+                    ("stance[1].accuracy", "1"),
+                ]),
+            ],
+        );
+        let magazine = &schema.attributes[0];
+        assert_eq!(magazine.path, "specification.magazine_size");
+        assert_eq!(magazine.value_type, ValueType::Integer);
+        assert_eq!((magazine.min, magazine.max), (Some(5.0), Some(30.0)));
+        let accuracy = &schema.attributes[1];
+        assert_eq!(accuracy.path, "stance.accuracy");
+        assert_eq!(accuracy.value_type, ValueType::Number);
+        assert_eq!(accuracy.occurrences, 2);
+    }
+
+    #[test]
+    fn flags_typos_types_and_ranges() {
+        let schema = infer_schema(
+            "weapon",
+            &[bag(&[
+                ("specification.magazine_size", "30"),
+                ("specification.name", "AK-47"),
+            ])],
+        );
+        let issues = check_bag(
+            &schema,
+            &bag(&[
+                ("specification.magzine_size", "30"),
+                ("specification.name", "Mod"),
+                ("specifcation.name", "Mod"),
+                ("specification.magazine_size", "lots"),
+                ("sound.fileref", "a.wav"),
+            ]),
+            "mod.weapon",
+        );
+        let kinds: Vec<(AttributeIssueKind, Option<&str>)> = issues
+            .iter()
+            .map(|i| (i.kind, i.suggestion.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (AttributeIssueKind::Unknown, None),
+                (AttributeIssueKind::Mistyped, Some("specification.name")),
+                (AttributeIssueKind::WrongType, None),
+                (
+                    AttributeIssueKind::Mistyped,
+                    Some("specification.magazine_size")
+                ),
+            ]
+        );
+
+        let issues = check_bag(
+            &schema,
+            &bag(&[("specification.magazine_size", "100")]),
+            "mod.weapon",
+        );
+        assert_eq!(issues[0].kind, AttributeIssueKind::OutOfRange);
+    }
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        assert_eq!(edit_distance("magzine_size", "magazine_size"), 1);
+        assert_eq!(edit_distance("abc", "abc"), 0);
+        assert_eq!(edit_distance("", "ab"), 2);
+    }
+}
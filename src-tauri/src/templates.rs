@@ -0,0 +1,310 @@
+//! Template inheritance shared by the file scanners
+//!
+//! A record names the file it inherits from with `file="..."`, relative to its own folder.
+//! When no such file exists the game looks in the same folder of the vanilla package, so
+//! mods can build on base game definitions without copying them. Templates can inherit from
+//! further templates; the chain is merged bottom-up with the inheriting record winning.
+
+use crate::diagnostics::{parse_recovering, read_file, Diagnostic, DiagnosticCode, Severity};
+use crate::packages::VANILLA_PACKAGE;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Maximum template chain length before resolution gives up
+pub(crate) const MAX_TEMPLATE_DEPTH: usize = 10;
+
+/// A raw XML record that can inherit from a template file
+pub(crate) trait Template: Sized {
+    /// Folder of the vanilla package templates of this kind fall back to (e.g. "weapons")
+    const VANILLA_DIR: &'static str;
+
+    /// Record kind named in diagnostics (e.g. "vehicle")
+    const LABEL: &'static str;
+
+    fn parse(content: &str, path: &Path) -> Result<Self, Diagnostic>;
+
+    /// The `file="..."` this record inherits from, if any
+    fn template_file(&self) -> Option<String>;
+
+    /// Merge a resolved template underneath the record inheriting from it
+    fn merge(parent: Self, child: Self) -> Self;
+}
+
+/// Paths `file` referenced from a file in `base_dir` may live at, in lookup order: next to
+/// it, then in `vanilla_dir` of the vanilla package.
+fn candidates(base_dir: &Path, file: &str, vanilla_dir: &str) -> Vec<PathBuf> {
+    let mut candidates = vec![base_dir.join(file)];
+    // e.g. /path/to/packages/man_vs_zombies/weapons -> /path/to/packages
+    if let Some(packages_dir) = base_dir.ancestors().nth(2) {
+        candidates.push(
+            packages_dir
+                .join(VANILLA_PACKAGE)
+                .join(vanilla_dir)
+                .join(file),
+        );
+    }
+    candidates
+}
+
+/// Locate `file` referenced from a file in `base_dir`: next to it, else in `vanilla_dir` of
+/// the vanilla package. When neither exists the local path is returned, so errors name the
+/// file the author wrote.
+pub(crate) fn locate(base_dir: &Path, file: &str, vanilla_dir: &str) -> PathBuf {
+    let mut candidates = candidates(base_dir, file, vanilla_dir);
+    let found = candidates.iter().position(|path| path.exists());
+    candidates.swap_remove(found.unwrap_or(0))
+}
+
+/// [`locate`] `file` and record it in `dependencies`.
+///
+/// Candidates that were looked up but don't exist are recorded ahead of it, so a local
+/// override added after a vanilla fallback (or a template that was missing) invalidates
+/// cached results. [`Diagnostic::with_template_chain`] skips them again.
+pub(crate) fn locate_dependency(
+    base_dir: &Path,
+    file: &str,
+    vanilla_dir: &str,
+    dependencies: &mut Vec<PathBuf>,
+) -> PathBuf {
+    let candidates = candidates(base_dir, file, vanilla_dir);
+    let found = candidates.iter().position(|path| path.exists());
+    let path = candidates[found.unwrap_or(0)].clone();
+    // Every candidate looked up before the one found, or all of them when none exists
+    let consulted = found.unwrap_or(candidates.len());
+    dependencies.extend(
+        candidates
+            .into_iter()
+            .take(consulted)
+            .filter(|candidate| *candidate != path),
+    );
+    dependencies.push(path.clone());
+    path
+}
+
+/// Locate `template_file` and mark it as being resolved, recording it in `dependencies`
+/// (see [`locate_dependency`]).
+///
+/// Fails when the template is already part of the chain, the chain is longer than
+/// [`MAX_TEMPLATE_DEPTH`] or the file doesn't exist. Callers remove the returned path from
+/// `visited` once the template and its parents are resolved.
+pub(crate) fn enter_template(
+    base_dir: &Path,
+    template_file: &str,
+    vanilla_dir: &str,
+    label: &str,
+    visited: &mut HashSet<PathBuf>,
+    dependencies: &mut Vec<PathBuf>,
+) -> Result<PathBuf, Diagnostic> {
+    let template_path = locate_dependency(base_dir, template_file, vanilla_dir, dependencies);
+
+    if !visited.insert(template_path.clone()) {
+        return Err(Diagnostic::error(
+            DiagnosticCode::TemplateCycle,
+            &template_path,
+            format!(
+                "Circular {} template reference detected: {}",
+                label, template_file
+            ),
+        ));
+    }
+
+    if visited.len() > MAX_TEMPLATE_DEPTH {
+        return Err(Diagnostic::error(
+            DiagnosticCode::TemplateDepthExceeded,
+            &template_path,
+            format!(
+                "Template depth exceeded limit (>{}) at {} template: {}",
+                MAX_TEMPLATE_DEPTH, label, template_file
+            ),
+        ));
+    }
+
+    if !template_path.exists() {
+        return Err(Diagnostic::error(
+            DiagnosticCode::TemplateNotFound,
+            &template_path,
+            format!("Template not found: {}", template_file),
+        ));
+    }
+
+    Ok(template_path)
+}
+
+/// Recursively resolve the template chain starting at `template_file`, referenced from a
+/// file in `base_dir`, into one record, together with whether any file of the chain was
+/// malformed and only parsed after repair. Every template consulted is appended to
+/// `dependencies`.
+pub(crate) fn resolve_template<T: Template>(
+    base_dir: &Path,
+    template_file: &str,
+    visited: &mut HashSet<PathBuf>,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(T, bool), Diagnostic> {
+    let template_path = enter_template(
+        base_dir,
+        template_file,
+        T::VANILLA_DIR,
+        T::LABEL,
+        visited,
+        dependencies,
+    )?;
+
+    let content = read_file(&template_path)?;
+    let (mut current, mut repaired) = parse_recovering(&content, diagnostics, |content| {
+        T::parse(content, &template_path)
+    })?;
+
+    if let Some(parent_file) = current.template_file() {
+        let template_parent = template_path.parent().unwrap_or(base_dir);
+        let (parent, parent_repaired) = resolve_template(
+            template_parent,
+            &parent_file,
+            visited,
+            dependencies,
+            diagnostics,
+        )?;
+        current = T::merge(parent, current);
+        repaired |= parent_repaired;
+    }
+
+    visited.remove(&template_path);
+    Ok((current, repaired))
+}
+
+/// Merge the template chain `record` inherits from underneath it.
+///
+/// `file` is the file `record` was read from. Template failures are reported against it as
+/// warnings in `diagnostics` and leave `record` as declared. The flag tells whether a
+/// template of the chain was malformed and only parsed after repair.
+pub(crate) fn inherit<T: Template>(
+    record: T,
+    file: &Path,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (T, bool) {
+    let Some(template_file) = record.template_file() else {
+        return (record, false);
+    };
+
+    let base_dir = file.parent().unwrap_or(file);
+    let chain_start = dependencies.len();
+    match resolve_template(
+        base_dir,
+        &template_file,
+        &mut HashSet::new(),
+        dependencies,
+        diagnostics,
+    ) {
+        Ok((parent, repaired)) => (T::merge(parent, record), repaired),
+        Err(diagnostic) => {
+            diagnostics.push(
+                diagnostic
+                    .with_severity(Severity::Warning)
+                    .with_template_chain(file, &dependencies[chain_start..]),
+            );
+            (record, false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::parse_xml;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct RawNamed {
+        #[serde(rename = "@file", default)]
+        file: Option<String>,
+        #[serde(rename = "@name", default)]
+        name: Option<String>,
+    }
+
+    impl Template for RawNamed {
+        const VANILLA_DIR: &'static str = "weapons";
+        const LABEL: &'static str = "weapon";
+
+        fn parse(content: &str, path: &Path) -> Result<Self, Diagnostic> {
+            parse_xml(content, path)
+        }
+
+        fn template_file(&self) -> Option<String> {
+            self.file.clone()
+        }
+
+        fn merge(parent: Self, mut child: Self) -> Self {
+            child.name = child.name.or(parent.name);
+            child
+        }
+    }
+
+    #[test]
+    fn records_every_candidate_consulted() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let local_dir = packages.join("mod/weapons");
+        let vanilla_dir = packages.join("vanilla/weapons");
+        std::fs::create_dir_all(&local_dir).unwrap();
+        std::fs::create_dir_all(&vanilla_dir).unwrap();
+        std::fs::write(vanilla_dir.join("base.weapon"), "<weapon/>").unwrap();
+
+        let mut dependencies = Vec::new();
+        let path = locate_dependency(&local_dir, "base.weapon", "weapons", &mut dependencies);
+        assert_eq!(path, vanilla_dir.join("base.weapon"));
+        assert_eq!(
+            dependencies,
+            [
+                local_dir.join("base.weapon"),
+                vanilla_dir.join("base.weapon")
+            ]
+        );
+
+        let mut dependencies = Vec::new();
+        let path = locate_dependency(&local_dir, "missing.weapon", "weapons", &mut dependencies);
+        assert_eq!(path, local_dir.join("missing.weapon"));
+        assert_eq!(
+            dependencies,
+            [
+                vanilla_dir.join("missing.weapon"),
+                local_dir.join("missing.weapon")
+            ]
+        );
+
+        std::fs::write(local_dir.join("base.weapon"), "<weapon/>").unwrap();
+        let mut dependencies = Vec::new();
+        locate_dependency(&local_dir, "base.weapon", "weapons", &mut dependencies);
+        assert_eq!(dependencies, [local_dir.join("base.weapon")]);
+    }
+
+    #[test]
+    fn recovers_malformed_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let weapons = packages.join("vanilla/weapons");
+        std::fs::create_dir_all(&weapons).unwrap();
+        std::fs::write(
+            weapons.join("base.weapon"),
+            r#"<weapon name="Salt & Pepper"/>"#,
+        )
+        .unwrap();
+        let child = RawNamed {
+            file: Some("base.weapon".to_string()),
+            name: None,
+        };
+
+        let mut diagnostics = Vec::new();
+        let (resolved, repaired) = inherit(
+            child,
+            &weapons.join("child.weapon"),
+            &mut Vec::new(),
+            &mut diagnostics,
+        );
+        assert_eq!(resolved.name.as_deref(), Some("Salt & Pepper"));
+        assert!(repaired);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::XmlSyntax);
+    }
+}
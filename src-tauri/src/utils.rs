@@ -61,3 +61,109 @@ pub fn resolve_packages_dirs(base: &Path) -> Vec<PathBuf> {
 
     deduped
 }
+
+/// Name of the package a file belongs to: the first folder below the packages directory
+///
+/// e.g. `<packages>/vanilla/weapons/ak47.weapon` -> `vanilla`
+pub fn package_name_of(file: &Path, packages_root: &Path) -> String {
+    file.strip_prefix(packages_root)
+        .ok()
+        .and_then(|relative| relative.components().next())
+        .and_then(|c| c.as_os_str().to_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Path of a file relative to the packages directory, or the whole path outside of it
+///
+/// e.g. `<packages>/vanilla/weapons/ak47.weapon` -> `vanilla/weapons/ak47.weapon`
+pub fn relative_file_path(file: &Path, packages_root: &Path) -> String {
+    file.strip_prefix(packages_root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// Locate a hud icon in the `textures/` folder that is a sibling of the folder holding `file`
+///
+/// e.g. `<packages>/vanilla/weapons/ak47.weapon` + `hud_ak47.png`
+/// -> `<packages>/vanilla/textures/hud_ak47.png` (canonicalized)
+pub fn resolve_texture_path(file: &Path, icon_filename: &str) -> Result<PathBuf, String> {
+    let textures_dir = file
+        .parent()
+        .ok_or("Invalid file path: cannot get parent directory")?
+        .parent()
+        .ok_or("Invalid file path: cannot get grandparent directory")?
+        .join("textures");
+
+    let icon_path = textures_dir.join(icon_filename);
+
+    if !icon_path.exists() {
+        return Err(format!(
+            "Icon file not found: {} (expected at: {})",
+            icon_filename,
+            icon_path.display()
+        ));
+    }
+
+    icon_path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve icon path: {}", e))
+}
+
+/// Parse a "0"/"1" flag attribute
+pub fn parse_flag(value: Option<&str>) -> Option<bool> {
+    match value?.trim() {
+        "1" => Some(true),
+        "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_name_is_first_folder_below_the_packages_root() {
+        let root = Path::new("/game/media/packages");
+        assert_eq!(
+            package_name_of(&root.join("vanilla/weapons/ak47.weapon"), root),
+            "vanilla"
+        );
+        assert_eq!(
+            package_name_of(&root.join("overhaul/items/carry/vest.carry_item"), root),
+            "overhaul"
+        );
+        assert_eq!(
+            package_name_of(&root.join("overhaul/weapons/rifles/sub/m4.weapon"), root),
+            "overhaul"
+        );
+    }
+
+    #[test]
+    fn package_name_of_file_outside_the_root_is_unknown() {
+        let root = Path::new("/game/media/packages");
+        assert_eq!(
+            package_name_of(Path::new("/elsewhere/weapons/ak47.weapon"), root),
+            "unknown"
+        );
+    }
+
+    #[test]
+    fn relative_file_path_strips_the_packages_root() {
+        let root = Path::new("/game/media/packages");
+        assert_eq!(
+            relative_file_path(
+                Path::new("/game/media/packages/vanilla/weapons/ak47.weapon"),
+                root
+            ),
+            "vanilla/weapons/ak47.weapon"
+        );
+        assert_eq!(
+            relative_file_path(Path::new("/elsewhere/ak47.weapon"), root),
+            "elsewhere/ak47.weapon"
+        );
+    }
+}
@@ -0,0 +1,529 @@
+//! Vehicles scanner module
+//!
+//! Scans RWR packages for `.vehicle` files, resolves their `file="..."` template inheritance
+//! and returns health, speed, seats and turrets (with the weapons mounted on them).
+
+use crate::diagnostics::{parse_recovering, parse_xml, read_file, Diagnostic};
+use crate::packages::{KeyDefinition, Provenance};
+use crate::scan_cache::{Parsed, ScanCache};
+use crate::scan_jobs::ScanJobs;
+use crate::scanner::{self, PackageScanner, ScanOptions, ScanSummary};
+use crate::templates::{inherit, Template};
+use crate::utils::{package_name_of, relative_file_path, resolve_texture_path};
+use crate::watch::{self, WatchRegistry};
+use crate::ScanEvent;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// Vehicle with its template chain resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vehicle {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub name: String,
+    /// `<physics max_health="...">`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_health: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mass: Option<f64>,
+    /// `<control max_speed="...">`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_speed: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_reverse_speed: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acceleration: Option<f64>,
+    /// One entry per `<character_slot>`
+    pub seats: Vec<VehicleSeat>,
+    pub turrets: Vec<VehicleTurret>,
+    /// Keys of every weapon mounted on the vehicle, in turret order without repeats
+    pub mounted_weapons: Vec<String>,
+    /// Icon filename from <hud_icon filename="..."/> element
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hud_icon: Option<String>,
+    /// Absolute path of `hud_icon`, resolved like `get_texture_path`; None if the file is missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hud_icon_path: Option<String>,
+    pub file_path: String,
+    pub source_file: String,
+    pub source_directory: String,
+    pub package_name: String,
+    /// Effective scans only: the package this definition was taken from and what it overrides
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provenance: Option<Provenance>,
+    /// The file is malformed XML; these fields were recovered from a repaired copy and
+    /// anything after the broken markup may be missing
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub partially_parsed: bool,
+}
+
+/// A seat (`<character_slot>`) of a vehicle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VehicleSeat {
+    /// Slot type (e.g. "driver", "gunner", "passenger")
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Turret operated from this seat, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turret_index: Option<usize>,
+}
+
+/// A turret and the weapons mounted on it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VehicleTurret {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Weapon keys (e.g. "m2_browning.weapon"), without repeats
+    pub weapons: Vec<String>,
+}
+
+/// Result from vehicle scanning
+#[derive(Debug, Serialize)]
+pub struct VehicleScanResult {
+    pub vehicles: Vec<Vehicle>,
+    #[serde(flatten)]
+    pub summary: ScanSummary,
+}
+
+/// Raw vehicle XML structure
+#[derive(Debug, Deserialize, Default)]
+struct RawVehicle {
+    #[serde(rename = "@file", default)]
+    template_file: Option<String>,
+    #[serde(rename = "@key", default)]
+    key: Option<String>,
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "physics", default)]
+    physics: Option<RawPhysics>,
+    #[serde(rename = "control", default)]
+    control: Option<RawControl>,
+    #[serde(rename = "character_slot", default)]
+    seats: Vec<RawSeat>,
+    #[serde(rename = "turret", default)]
+    turrets: Vec<RawTurret>,
+    #[serde(rename = "hud_icon", default)]
+    hud_icon: Option<RawHudIcon>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawPhysics {
+    #[serde(rename = "@max_health", default)]
+    max_health: Option<f64>,
+    #[serde(rename = "@mass", default)]
+    mass: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawControl {
+    #[serde(rename = "@max_speed", default)]
+    max_speed: Option<f64>,
+    #[serde(rename = "@max_reverse_speed", default)]
+    max_reverse_speed: Option<f64>,
+    #[serde(rename = "@acceleration", default)]
+    acceleration: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawSeat {
+    #[serde(rename = "@type", default)]
+    kind: Option<String>,
+    #[serde(rename = "@turret_index", default)]
+    turret_index: Option<usize>,
+}
+
+/// `<turret weapon_key="...">`, optionally with nested `<weapon key="..."/>` entries
+#[derive(Debug, Deserialize, Default)]
+struct RawTurret {
+    #[serde(rename = "@name", default)]
+    name: Option<String>,
+    #[serde(rename = "@weapon_key", default)]
+    weapon_key: Option<String>,
+    #[serde(rename = "weapon", default)]
+    weapons: Vec<RawMountedWeapon>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawMountedWeapon {
+    #[serde(rename = "@key", default)]
+    key: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawHudIcon {
+    #[serde(rename = "@filename", default)]
+    filename: Option<String>,
+}
+
+/// Scan all vehicle files from a game/workshop directory.
+///
+/// The scan runs in the background; the returned job id can be passed to `cancel_scan`.
+#[tauri::command]
+pub async fn scan_vehicles(
+    game_path: String,
+    directory: Option<String>,
+    jobs: State<'_, ScanJobs>,
+    on_event: Channel<ScanEvent<Vehicle>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    scanner::spawn_scan::<VehicleScanner>(&jobs, source_directory, on_event)
+}
+
+/// [`scan_vehicles`] in one payload, for WebViews that drop `Channel` messages
+/// (see `scan_weapons_collect`).
+#[tauri::command]
+pub async fn scan_vehicles_collect(
+    game_path: String,
+    directory: Option<String>,
+    effective: Option<bool>,
+) -> Result<VehicleScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        effective: effective.unwrap_or(false),
+        ..Default::default()
+    };
+    let scan = scanner::collect_scan::<VehicleScanner>(&source_directory, options)?;
+    Ok(VehicleScanResult {
+        vehicles: scan.records,
+        summary: scan.summary,
+    })
+}
+
+/// Watch the packages roots of a vehicles scan and stream add/update/remove deltas.
+#[tauri::command]
+pub async fn watch_vehicles(
+    game_path: String,
+    directory: Option<String>,
+    registry: State<'_, WatchRegistry>,
+    on_event: Channel<ScanEvent<Vehicle>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    watch::start::<VehicleScanner>(&registry, source_directory, on_event)
+}
+
+/// Vehicle scanner for the shared scanning framework
+pub(crate) struct VehicleScanner;
+
+impl PackageScanner for VehicleScanner {
+    type Record = Vehicle;
+
+    const KIND: &'static str = "vehicles";
+
+    fn matches(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "vehicle")
+    }
+
+    fn parse_cached(
+        cache: &ScanCache<Vehicle>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Vehicle>, Diagnostic> {
+        parse_vehicle_cached(cache, file, packages_root, index, source_directory)
+    }
+
+    fn record_id(record: &Vehicle) -> &str {
+        &record.id
+    }
+
+    fn key_definition(record: &Vehicle) -> Option<KeyDefinition> {
+        Some(KeyDefinition {
+            key: record.key.clone()?,
+            id: record.id.clone(),
+            package_name: record.package_name.clone(),
+            file_path: record.file_path.clone(),
+            source_file: record.source_file.clone(),
+        })
+    }
+
+    fn set_provenance(record: &mut Vehicle, provenance: Provenance) {
+        record.provenance = Some(provenance);
+    }
+}
+
+/// Parse a vehicle file through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits, and the hud icon
+/// is located afresh since textures aren't tracked as dependencies.
+fn parse_vehicle_cached(
+    cache: &ScanCache<Vehicle>,
+    path: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<Vehicle>, Diagnostic> {
+    let id = format!("{}_{}", path.to_string_lossy(), index);
+
+    let mut parsed = cache.get_or_parse(path, |dependencies, diagnostics| {
+        parse_vehicle_file(
+            path,
+            packages_root,
+            id.clone(),
+            source_directory,
+            dependencies,
+            diagnostics,
+        )
+        .map(|vehicle| vec![vehicle])
+    })?;
+
+    for vehicle in &mut parsed.records {
+        vehicle.id = id.clone();
+        vehicle.source_directory = source_directory.to_string();
+        vehicle.hud_icon_path = vehicle
+            .hud_icon
+            .as_deref()
+            .and_then(|icon| resolve_texture_path(path, icon).ok())
+            .map(|p| p.to_string_lossy().to_string());
+    }
+    Ok(parsed)
+}
+
+/// Parse a single vehicle file with template resolution.
+/// Every template file consulted is appended to `dependencies`; template failures are
+/// reported as warnings in `diagnostics`.
+fn parse_vehicle_file(
+    vehicle_path: &Path,
+    packages_root: &Path,
+    id: String,
+    source_directory: &str,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Vehicle, Diagnostic> {
+    let content = read_file(vehicle_path)?;
+    let (raw, mut partially_parsed) = parse_recovering(&content, diagnostics, |content| {
+        parse_xml::<RawVehicle>(content, vehicle_path)
+    })?;
+    let (raw, template_repaired) = inherit(raw, vehicle_path, dependencies, diagnostics);
+    partially_parsed |= template_repaired;
+
+    let physics = raw.physics.unwrap_or_default();
+    let control = raw.control.unwrap_or_default();
+
+    let turrets: Vec<VehicleTurret> = raw
+        .turrets
+        .into_iter()
+        .map(|t| {
+            let mut weapons: Vec<String> = Vec::new();
+            let keys = t
+                .weapon_key
+                .into_iter()
+                .chain(t.weapons.into_iter().filter_map(|w| w.key));
+            for key in keys.filter(|k| !k.is_empty()) {
+                if !weapons.contains(&key) {
+                    weapons.push(key);
+                }
+            }
+            VehicleTurret {
+                name: t.name,
+                weapons,
+            }
+        })
+        .collect();
+
+    let mut mounted_weapons: Vec<String> = Vec::new();
+    for key in turrets.iter().flat_map(|t| &t.weapons) {
+        if !mounted_weapons.contains(key) {
+            mounted_weapons.push(key.clone());
+        }
+    }
+
+    let seats = raw
+        .seats
+        .into_iter()
+        .map(|s| VehicleSeat {
+            kind: s.kind.unwrap_or_default(),
+            turret_index: s.turret_index,
+        })
+        .collect();
+
+    let hud_icon = raw
+        .hud_icon
+        .and_then(|h| h.filename)
+        .filter(|s| !s.is_empty());
+
+    let file_path = relative_file_path(vehicle_path, packages_root);
+
+    let key = raw.key.filter(|k| !k.is_empty()).or_else(|| {
+        vehicle_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_string())
+    });
+
+    Ok(Vehicle {
+        id,
+        name: raw.name.unwrap_or_default(),
+        key,
+        max_health: physics.max_health,
+        mass: physics.mass,
+        max_speed: control.max_speed,
+        max_reverse_speed: control.max_reverse_speed,
+        acceleration: control.acceleration,
+        seats,
+        turrets,
+        mounted_weapons,
+        hud_icon,
+        hud_icon_path: None,
+        file_path,
+        source_file: vehicle_path.to_string_lossy().to_string(),
+        source_directory: source_directory.to_string(),
+        package_name: package_name_of(vehicle_path, packages_root),
+        provenance: None,
+        partially_parsed,
+    })
+}
+
+impl Template for RawVehicle {
+    const VANILLA_DIR: &'static str = "vehicles";
+    const LABEL: &'static str = "vehicle";
+
+    fn parse(content: &str, path: &Path) -> Result<Self, Diagnostic> {
+        parse_xml(content, path)
+    }
+
+    fn template_file(&self) -> Option<String> {
+        self.template_file.clone()
+    }
+
+    fn merge(parent: Self, child: Self) -> Self {
+        merge_vehicle_attributes(parent, child)
+    }
+}
+
+/// Child values win; missing ones are taken from `parent`.
+/// Seats and turrets are replaced as a whole when the child declares any.
+fn merge_vehicle_attributes(parent: RawVehicle, mut child: RawVehicle) -> RawVehicle {
+    child.name = child.name.or(parent.name);
+    child.hud_icon = child.hud_icon.or(parent.hud_icon);
+
+    child.physics = match (parent.physics, child.physics) {
+        (Some(p), Some(mut c)) => {
+            c.max_health = c.max_health.or(p.max_health);
+            c.mass = c.mass.or(p.mass);
+            Some(c)
+        }
+        (p, c) => c.or(p),
+    };
+
+    child.control = match (parent.control, child.control) {
+        (Some(p), Some(mut c)) => {
+            c.max_speed = c.max_speed.or(p.max_speed);
+            c.max_reverse_speed = c.max_reverse_speed.or(p.max_reverse_speed);
+            c.acceleration = c.acceleration.or(p.acceleration);
+            Some(c)
+        }
+        (p, c) => c.or(p),
+    };
+
+    if child.seats.is_empty() {
+        child.seats = parent.seats;
+    }
+    if child.turrets.is_empty() {
+        child.turrets = parent.turrets;
+    }
+
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inherits_vanilla_template_and_resolves_hud_icon() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let vanilla = packages.join("vanilla/vehicles");
+        let vehicles = packages.join("mod/vehicles");
+        std::fs::create_dir_all(&vanilla).unwrap();
+        std::fs::create_dir_all(&vehicles).unwrap();
+        std::fs::write(
+            vanilla.join("base.vehicle"),
+            r#"<vehicle name="Jeep">
+                <physics max_health="10.0" mass="900.0"/>
+                <control max_speed="20.0"/>
+                <character_slot type="driver"/>
+                <turret name="top" weapon_key="m2.weapon"><weapon key="m2.weapon"/></turret>
+                <hud_icon filename="hud_jeep.png"/>
+            </vehicle>"#,
+        )
+        .unwrap();
+        let jeep = vehicles.join("jeep.vehicle");
+        std::fs::write(
+            &jeep,
+            r#"<vehicle file="base.vehicle" key="jeep.vehicle"><physics max_health="15.0"/></vehicle>"#,
+        )
+        .unwrap();
+
+        let source_directory = packages.to_string_lossy().to_string();
+        let cache = ScanCache::load(VehicleScanner::KIND, &source_directory);
+        let parse = || {
+            parse_vehicle_cached(&cache, &jeep, packages, 0, &source_directory)
+                .unwrap()
+                .records
+                .remove(0)
+        };
+
+        let vehicle = parse();
+        assert_eq!(vehicle.name, "Jeep");
+        assert_eq!(vehicle.max_health, Some(15.0));
+        assert_eq!(vehicle.mass, Some(900.0));
+        assert_eq!(vehicle.max_speed, Some(20.0));
+        assert_eq!(vehicle.seats.len(), 1);
+        assert_eq!(vehicle.turrets[0].weapons, ["m2.weapon"]);
+        assert_eq!(vehicle.mounted_weapons, ["m2.weapon"]);
+        assert_eq!(vehicle.package_name, "mod");
+        assert_eq!(vehicle.hud_icon.as_deref(), Some("hud_jeep.png"));
+        assert_eq!(vehicle.hud_icon_path, None);
+
+        // The icon isn't a dependency: it is looked up again on every scan
+        std::fs::create_dir_all(packages.join("mod/textures")).unwrap();
+        std::fs::write(packages.join("mod/textures/hud_jeep.png"), "").unwrap();
+        assert!(parse().hud_icon_path.is_some());
+    }
+
+    #[test]
+    fn parses_seats_interleaved_with_turrets() {
+        let packages = tempfile::tempdir().unwrap();
+        let vehicles = packages.path().join("vanilla/vehicles");
+        std::fs::create_dir_all(&vehicles).unwrap();
+        let apc = vehicles.join("apc.vehicle");
+        std::fs::write(
+            &apc,
+            r#"<vehicle key="apc.vehicle" name="APC">
+                <character_slot type="driver"/>
+                <turret name="main" weapon_key="cannon.weapon"/>
+                <character_slot type="gunner" turret_index="0"/>
+                <turret name="coax" weapon_key="mg.weapon"/>
+                <character_slot type="gunner" turret_index="1"/>
+            </vehicle>"#,
+        )
+        .unwrap();
+
+        let source_directory = packages.path().to_string_lossy().to_string();
+        let cache = ScanCache::load(VehicleScanner::KIND, &source_directory);
+        let vehicle = parse_vehicle_cached(&cache, &apc, packages.path(), 0, &source_directory)
+            .unwrap()
+            .records
+            .remove(0);
+
+        assert!(!vehicle.partially_parsed);
+        let seats: Vec<_> = vehicle
+            .seats
+            .iter()
+            .map(|s| (s.kind.as_str(), s.turret_index))
+            .collect();
+        assert_eq!(
+            seats,
+            [("driver", None), ("gunner", Some(0)), ("gunner", Some(1))]
+        );
+        assert_eq!(vehicle.turrets.len(), 2);
+        assert_eq!(vehicle.mounted_weapons, ["cannon.weapon", "mg.weapon"]);
+    }
+}
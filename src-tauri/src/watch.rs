@@ -0,0 +1,437 @@
+//! Live package watching
+//!
+//! Keeps the packages roots of a scan under a filesystem watcher and pushes add/update/remove
+//! deltas over the same `Channel<ScanEvent<T>>` the scan used. Editing a template re-emits
+//! every record whose template chain includes it.
+
+use crate::resource_lists::ResourceIndex;
+use crate::scan_cache::ScanCache;
+use crate::scan_jobs::CancelToken;
+use crate::scanner::{
+    discover_files, existing_package_roots, finish_records, resource_index, PackageScanner,
+    ScanOptions,
+};
+use crate::ScanEvent;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// Quiet period after the last filesystem event before deltas are computed.
+/// Editors commonly write a file several times per save.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Active watch sessions (managed Tauri state).
+/// Dropping a session's watcher ends its worker thread; a worker that stops on its own
+/// (e.g. because the frontend dropped the channel) removes its session.
+#[derive(Default)]
+pub struct WatchRegistry {
+    next_id: AtomicU32,
+    sessions: Arc<Mutex<HashMap<u32, RecommendedWatcher>>>,
+}
+
+/// A scanned file and the record ids it produced
+struct TrackedFile {
+    packages_root: PathBuf,
+    index: usize,
+    ids: Vec<String>,
+}
+
+struct WatchSession<S: PackageScanner> {
+    source_directory: String,
+    roots: Vec<PathBuf>,
+    cache: ScanCache<S::Record>,
+    files: HashMap<PathBuf, TrackedFile>,
+    next_index: usize,
+    options: ScanOptions,
+    /// Reloaded only when a resource list changes
+    resource_index: Option<ResourceIndex>,
+    on_event: Channel<ScanEvent<S::Record>>,
+}
+
+/// Start watching the packages roots of `source_directory` for files handled by `S`.
+///
+/// Record ids follow the same discovery order as the regular scan, so deltas apply
+/// directly to rows the frontend received from `scan_weapons` / `scan_items`.
+pub(crate) fn start<S>(
+    registry: &WatchRegistry,
+    source_directory: String,
+    on_event: Channel<ScanEvent<S::Record>>,
+) -> Result<u32, String>
+where
+    S: PackageScanner + 'static,
+{
+    start_with::<S>(registry, source_directory, ScanOptions::default(), on_event)
+}
+
+/// [`start`] with the [`ScanOptions`] of the scan being watched
+pub(crate) fn start_with<S>(
+    registry: &WatchRegistry,
+    source_directory: String,
+    options: ScanOptions,
+    on_event: Channel<ScanEvent<S::Record>>,
+) -> Result<u32, String>
+where
+    S: PackageScanner + 'static,
+{
+    let roots = existing_package_roots(&source_directory)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("Failed to create watcher: {}", e))?;
+    for root in &roots {
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+    }
+
+    let watch_id = registry.next_id.fetch_add(1, Ordering::Relaxed);
+    let sessions = Arc::clone(&registry.sessions);
+    sessions
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(watch_id, watcher);
+
+    std::thread::spawn(move || {
+        let session = WatchSession::<S>::new(source_directory, roots, options, on_event);
+        session.run(rx);
+        // No-op when the session ended through `stop_watch`
+        if let Ok(mut sessions) = sessions.lock() {
+            sessions.remove(&watch_id);
+        }
+    });
+
+    Ok(watch_id)
+}
+
+/// Stop a watch session started by `watch_weapons` / `watch_items`
+#[tauri::command]
+pub async fn stop_watch(watch_id: u32, registry: State<'_, WatchRegistry>) -> Result<(), String> {
+    registry
+        .sessions
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&watch_id)
+        .map(|_| ())
+        .ok_or_else(|| format!("No active watch with id {}", watch_id))
+}
+
+impl<S: PackageScanner> WatchSession<S> {
+    /// Build the baseline silently. The preceding scan has just refreshed the
+    /// scan cache, so this is mostly cache hits.
+    fn new(
+        source_directory: String,
+        roots: Vec<PathBuf>,
+        options: ScanOptions,
+        on_event: Channel<ScanEvent<S::Record>>,
+    ) -> Self {
+        let cache = ScanCache::load(S::KIND, &source_directory);
+        let discovered = discover_files::<S>(&roots, &CancelToken::default());
+        let next_index = discovered.len();
+
+        let files = discovered
+            .into_par_iter()
+            .enumerate()
+            .map(|(index, (packages_root, file))| {
+                let ids = S::parse_cached(&cache, &file, &packages_root, index, &source_directory)
+                    .map(|parsed| record_ids::<S>(&parsed.records))
+                    .unwrap_or_default();
+                let tracked = TrackedFile {
+                    packages_root,
+                    index,
+                    ids,
+                };
+                (file, tracked)
+            })
+            .collect();
+
+        Self {
+            source_directory,
+            resource_index: resource_index::<S>(&roots),
+            roots,
+            cache,
+            files,
+            next_index,
+            options,
+            on_event,
+        }
+    }
+
+    fn run(mut self, events: mpsc::Receiver<notify::Result<notify::Event>>) {
+        while let Ok(first) = events.recv() {
+            let mut changed = HashSet::new();
+            collect_paths(first, &mut changed);
+            while let Ok(next) = events.recv_timeout(DEBOUNCE) {
+                collect_paths(next, &mut changed);
+            }
+
+            if changed.is_empty() {
+                continue;
+            }
+            if let Err(_e) = self.apply(changed) {
+                // The frontend dropped the channel; nothing left to report to.
+                #[cfg(debug_assertions)]
+                eprintln!("[watch_{}] stopping: {}", S::KIND, _e);
+                break;
+            }
+        }
+
+        if let Err(_e) = self.cache.save() {
+            #[cfg(debug_assertions)]
+            eprintln!("[watch_{}] cache save failed: {}", S::KIND, _e);
+        }
+    }
+
+    /// Turn a batch of changed paths into `Chunk` / `Updated` / `Removed` events
+    fn apply(&mut self, changed: HashSet<PathBuf>) -> Result<(), String> {
+        let mut removed: Vec<PathBuf> = Vec::new();
+        let mut reparse: HashSet<PathBuf> = HashSet::new();
+
+        for file in self.files.keys() {
+            if !file.exists() {
+                // Covers deleted files as well as deleted package folders.
+                if changed.iter().any(|c| file.starts_with(c)) {
+                    removed.push(file.clone());
+                }
+            } else if changed.contains(file)
+                || self
+                    .cache
+                    .dependencies(file)
+                    .iter()
+                    .any(|dep| changed.contains(dep))
+            {
+                reparse.insert(file.clone());
+            }
+        }
+        for path in &changed {
+            if S::matches(path) && path.is_file() {
+                reparse.insert(path.clone());
+            }
+        }
+
+        for path in changed.iter().chain(reparse.iter()) {
+            self.cache.invalidate(path);
+        }
+
+        // An edited resource list can flip the status of any record; records are re-sent
+        // from the cache with the new status.
+        let list_changed =
+            S::RESOURCE_LIST.is_some_and(|kind| changed.iter().any(|path| kind.is_list_file(path)));
+        if list_changed {
+            self.resource_index = resource_index::<S>(&self.roots);
+            reparse.extend(self.files.keys().filter(|f| f.exists()).cloned());
+            for diagnostic in self.resource_index.iter().flat_map(|i| &i.diagnostics) {
+                self.send(ScanEvent::Diagnostic(diagnostic.clone()))?;
+            }
+        }
+
+        let mut removed_ids = Vec::new();
+        for file in &removed {
+            self.cache.invalidate(file);
+            if let Some(tracked) = self.files.remove(file) {
+                removed_ids.extend(tracked.ids);
+            }
+        }
+        if !removed_ids.is_empty() {
+            self.send(ScanEvent::Removed(removed_ids))?;
+        }
+
+        let mut reparse: Vec<PathBuf> = reparse.into_iter().collect();
+        reparse.sort();
+        for file in reparse {
+            self.reparse(file)?;
+        }
+
+        Ok(())
+    }
+
+    fn reparse(&mut self, file: PathBuf) -> Result<(), String> {
+        let (packages_root, index, old_ids) = match self.files.get(&file) {
+            Some(tracked) => (
+                tracked.packages_root.clone(),
+                tracked.index,
+                tracked.ids.clone(),
+            ),
+            None => {
+                let Some(root) = self.roots.iter().find(|r| file.starts_with(r)) else {
+                    return Ok(());
+                };
+                let index = self.next_index;
+                self.next_index += 1;
+                (root.clone(), index, Vec::new())
+            }
+        };
+
+        let parsed = S::parse_cached(
+            &self.cache,
+            &file,
+            &packages_root,
+            index,
+            &self.source_directory,
+        );
+
+        let new_ids = match parsed {
+            Ok(parsed) => {
+                let mut records = parsed.records;
+                finish_records::<S>(&mut records, self.resource_index.as_ref(), self.options);
+                let new_ids = record_ids::<S>(&records);
+                let stale: Vec<String> = old_ids
+                    .iter()
+                    .filter(|id| !new_ids.contains(id))
+                    .cloned()
+                    .collect();
+
+                if !stale.is_empty() {
+                    self.send(ScanEvent::Removed(stale))?;
+                }
+                if !records.is_empty() {
+                    if old_ids.is_empty() {
+                        self.send(ScanEvent::Chunk(records))?;
+                    } else {
+                        self.send(ScanEvent::Updated(records))?;
+                    }
+                }
+                for diagnostic in parsed.diagnostics {
+                    self.send(ScanEvent::Diagnostic(diagnostic))?;
+                }
+                new_ids
+            }
+            Err(diagnostic) => {
+                // A file that no longer parses disappears from the table, like in a full scan.
+                if !old_ids.is_empty() {
+                    self.send(ScanEvent::Removed(old_ids))?;
+                }
+                self.send(ScanEvent::Diagnostic(diagnostic))?;
+                Vec::new()
+            }
+        };
+
+        self.files.insert(
+            file,
+            TrackedFile {
+                packages_root,
+                index,
+                ids: new_ids,
+            },
+        );
+        Ok(())
+    }
+
+    fn send(&self, event: ScanEvent<S::Record>) -> Result<(), String> {
+        self.on_event
+            .send(event)
+            .map_err(|e| format!("Failed to send scan event: {e}"))
+    }
+}
+
+fn record_ids<S: PackageScanner>(records: &[S::Record]) -> Vec<String> {
+    records
+        .iter()
+        .map(|r| S::record_id(r).to_string())
+        .collect()
+}
+
+fn collect_paths(event: notify::Result<notify::Event>, changed: &mut HashSet<PathBuf>) {
+    let Ok(event) = event else {
+        return;
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    changed.extend(event.paths);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weapons::WeaponScanner;
+    use tauri::ipc::InvokeResponseBody;
+
+    /// Every event sent by `apply`, as `(event, data)` pairs
+    type Events = std::sync::Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+
+    fn recording_channel<T: serde::Serialize>(events: &Events) -> Channel<T> {
+        let events = events.clone();
+        Channel::new(move |body| {
+            if let InvokeResponseBody::Json(json) = body {
+                let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+                let event = value["event"].as_str().unwrap_or_default().to_string();
+                events.lock().unwrap().push((event, value["data"].take()));
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn applies_template_list_and_delete_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages = dir.path();
+        let weapons = packages.join("vanilla/weapons");
+        std::fs::create_dir_all(&weapons).unwrap();
+        let base = weapons.join("base.weapon");
+        let rifle = weapons.join("rifle.weapon");
+        let list = weapons.join("all_weapons.xml");
+        std::fs::write(
+            &base,
+            r#"<weapon><specification magazine_size="30"/></weapon>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &rifle,
+            r#"<weapon file="base.weapon" key="rifle.weapon"><specification name="Rifle"/></weapon>"#,
+        )
+        .unwrap();
+        std::fs::write(&list, r#"<weapons><weapon file="rifle.weapon"/></weapons>"#).unwrap();
+
+        let events = Events::default();
+        let mut session = WatchSession::<WeaponScanner>::new(
+            packages.to_string_lossy().to_string(),
+            vec![packages.to_path_buf()],
+            ScanOptions::default(),
+            recording_channel(&events),
+        );
+        let rifle_event = |name: &str| {
+            let events = std::mem::take(&mut *events.lock().unwrap());
+            events
+                .into_iter()
+                .filter(|(event, _)| event == name)
+                .flat_map(|(_, data)| data.as_array().cloned().unwrap_or_default())
+                .find(|record| record["key"] == "rifle.weapon")
+        };
+
+        // Editing the template re-emits the weapon inheriting from it
+        std::fs::write(
+            &base,
+            r#"<weapon><specification magazine_size="40"/></weapon>"#,
+        )
+        .unwrap();
+        session.apply(HashSet::from([base.clone()])).unwrap();
+        let updated = rifle_event("updated").unwrap();
+        assert_eq!(updated["magazineSize"], 40.0);
+        assert_eq!(updated["resourceStatus"], "referenced");
+
+        // Unlisting the file flips its status
+        std::fs::write(&list, "<weapons/>").unwrap();
+        session.apply(HashSet::from([list.clone()])).unwrap();
+        assert_eq!(
+            rifle_event("updated").unwrap()["resourceStatus"],
+            "orphaned"
+        );
+
+        // Deleting it removes its record
+        std::fs::remove_file(&rifle).unwrap();
+        session.apply(HashSet::from([rifle.clone()])).unwrap();
+        let removed = std::mem::take(&mut *events.lock().unwrap());
+        assert!(
+            removed
+                .iter()
+                .any(|(event, data)| event == "removed"
+                    && data[0].as_str().unwrap().contains("rifle"))
+        );
+    }
+}
@@ -3,21 +3,28 @@
 //! Scans RWR game directory for weapon XML files, parses them with template inheritance resolution,
 //! and returns structured weapon data to the frontend.
 
-use crate::utils::resolve_packages_dirs;
+use crate::attributes::{self, AttributeBag, AttributeTrace, Explanation, ListMerge, MergeRules};
+use crate::diagnostics::{parse_recovering, parse_xml, read_file, Diagnostic, Severity};
+use crate::metrics::{self, WeaponMetrics};
+use crate::packages::{KeyDefinition, Provenance};
+use crate::projectiles::{self, Projectile, RawProjectile};
+use crate::resource_lists::{ResourceIndex, ResourceListKind, ResourceStatus};
+use crate::scan_cache::{Parsed, ScanCache};
+use crate::scan_jobs::ScanJobs;
+use crate::scanner::{self, PackageScanner, ScanOptions, ScanSummary};
+use crate::templates::{self, resolve_template, Template};
+use crate::utils::{package_name_of, parse_flag, relative_file_path, resolve_texture_path};
+use crate::watch::{self, WatchRegistry};
 use crate::ScanEvent;
-use quick_xml::de::from_str;
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 use tauri::ipc::Channel;
+use tauri::State;
 use tauri_plugin_opener::OpenerExt;
 use walkdir::WalkDir;
 
-const MAX_TEMPLATE_DEPTH: usize = 10;
-const BATCH_SIZE: usize = 50;
-
 /// Main weapon structure with all attributes from XML
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")] // Apply camelCase to all fields by default
@@ -43,13 +50,45 @@ pub struct Weapon {
     pub sight_range_modifier: Option<f64>,
     pub projectile_speed: Option<f64>,
     pub barrel_offset: Option<f64>,
+    /// Projectiles fired per trigger pull (e.g. shotgun pellets), from
+    /// <specification projectiles_per_shot="..."/>
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub projectiles_per_shot: Option<f64>,
+    /// From <specification accuracy_factor="..."/>
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub accuracy_factor: Option<f64>,
+    /// Accuracy penalty added per shot while firing continuously
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sustained_fire_grow_step: Option<f64>,
+    /// Rate at which the sustained fire penalty recovers
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sustained_fire_diminish_rate: Option<f64>,
     pub encumbrance: Option<f64>,
     pub price: Option<f64>,
+    /// Spawn frequency from <commonness value="..."/>
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub commonness: Option<f64>,
     pub suppressed: bool,
+    /// From <commonness can_respawn_with="..."/>; true when not specified
     pub can_respawn_with: bool,
+    /// From <commonness in_stock="..."/>; true when not specified
     pub in_stock: bool,
+    /// Attribute modifiers applied while the weapon is carried
+    #[serde(default)]
+    pub modifiers: Vec<WeaponModifier>,
+    /// Keys from `<next_in_chain key="..."/>`, the weapons switching modes cycles through
     pub chain_variants: Vec<String>,
     pub stance_accuracies: Vec<StanceAccuracy>,
+    /// Projectile file the weapon fires, relative to the packages directory
+    /// (matches `Projectile::file_path`)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub projectile_file: Option<String>,
+    /// Inline `<projectile>` merged over the projectile file it references
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub projectile: Option<Projectile>,
+    /// Figures derived from the fields above (fire rate, DPS, time to kill, ...)
+    #[serde(default)]
+    pub metrics: WeaponMetrics,
     pub file_path: String,
     pub source_file: String,
     pub source_directory: String,
@@ -58,6 +97,20 @@ pub struct Weapon {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "templateError")]
     pub template_error: Option<String>,
+    /// The file is malformed XML; these fields were recovered from a repaired copy and
+    /// anything after the broken markup may be missing
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub partially_parsed: bool,
+    /// Effective scans only: the package this definition was taken from and what it overrides
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provenance: Option<Provenance>,
+    /// Whether the package's resource list registers this file; not cached, set per scan
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub resource_status: Option<ResourceStatus>,
+    /// Every XML attribute after template merging (e.g. `specification.stab_range`);
+    /// only sent when the scan asked for it
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub attributes: Option<AttributeBag>,
 }
 
 /// Stance accuracy values
@@ -67,23 +120,20 @@ pub struct StanceAccuracy {
     pub accuracy: f64,
 }
 
-/// Error during weapon scanning
-#[derive(Debug, Serialize, Clone)]
-pub struct ScanError {
-    pub file: String,
-    pub error: String,
-    pub severity: String,
+/// Attribute modifier (`<modifier class="..." value="..."/>`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeaponModifier {
+    pub class: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
 }
 
 /// Result from weapon scanning
 #[derive(Debug, Serialize)]
 pub struct WeaponScanResult {
     pub weapons: Vec<Weapon>,
-    pub errors: Vec<ScanError>,
-    #[serde(rename = "duplicateKeys")]
-    pub duplicate_keys: Vec<String>,
-    #[serde(rename = "scanTime")]
-    pub scan_time: u64,
+    #[serde(flatten)]
+    pub summary: ScanSummary,
 }
 
 /// Validation result for game path
@@ -103,6 +153,12 @@ pub struct ValidationResult {
 /// All attributes use @ prefix to map XML attributes correctly
 #[derive(Debug, Deserialize, Default)]
 struct RawWeapon {
+    /// Every attribute of the file and the file declaring it, filled in after deserializing
+    #[serde(skip)]
+    attributes: AttributeTrace,
+    /// The file was malformed and only parsed after repair
+    #[serde(skip)]
+    partially_parsed: bool,
     #[serde(rename = "@key", default)]
     key: Option<String>,
     #[serde(rename = "@file", default)]
@@ -115,17 +171,29 @@ struct RawWeapon {
     hud_icon: Option<RawHudIcon>,
     #[serde(rename = "inventory", default)]
     inventory: Option<RawInventory>,
+    #[serde(rename = "commonness", default)]
+    commonness: Option<RawCommonness>,
     #[serde(rename = "projectile", default)]
     projectile: Option<RawProjectile>,
     #[serde(rename = "modifier", default)]
-    #[allow(dead_code)]
     modifiers: Vec<RawModifier>,
     #[serde(rename = "nextInChain", default)]
-    chain_variants: Vec<String>,
+    chain_variants: Vec<RawChainLink>,
+    #[serde(rename = "next_in_chain", default)]
+    chain_variants_snake: Vec<RawChainLink>,
     #[serde(rename = "stance", default)]
     stances: Vec<RawStance>,
 }
 
+/// `<next_in_chain key="..."/>`; older files spell it `nextInChain` or put the key in the text
+#[derive(Debug, Deserialize, Default, Clone)]
+struct RawChainLink {
+    #[serde(rename = "@key", default)]
+    key: Option<String>,
+    #[serde(rename = "$text", default)]
+    text: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Default, Clone)]
 struct RawTag {
     #[serde(rename = "@name", default)]
@@ -160,6 +228,16 @@ struct RawSpecification {
     class: Option<String>,
     #[serde(rename = "@projectile_speed", default)]
     projectile_speed: Option<f64>,
+    #[serde(rename = "@burst_shots", default)]
+    burst_shots: Option<f64>,
+    #[serde(rename = "@spread_range", default)]
+    spread_range: Option<f64>,
+    #[serde(rename = "@sight_range_modifier", default)]
+    sight_range_modifier: Option<f64>,
+    #[serde(rename = "@barrel_offset", default)]
+    barrel_offset: Option<f64>,
+    #[serde(rename = "@projectiles_per_shot", default)]
+    projectiles_per_shot: Option<f64>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -171,36 +249,20 @@ struct RawInventory {
 }
 
 #[derive(Debug, Deserialize, Default)]
-struct RawProjectile {
-    #[serde(rename = "@file", default)]
-    #[allow(dead_code)]
-    file: Option<String>,
-    #[serde(rename = "result", default)]
-    result: Option<RawProjectileResult>,
-}
-
-#[derive(Debug, Deserialize, Default)]
-struct RawProjectileResult {
-    #[serde(rename = "@class", default)]
-    #[allow(dead_code)]
-    class: Option<String>,
-    #[serde(rename = "@kill_probability", default)]
-    kill_probability: Option<f64>,
-    #[serde(rename = "@kill_decay_start_time", default)]
-    #[allow(dead_code)]
-    kill_decay_start_time: Option<f64>,
-    #[serde(rename = "@kill_decay_end_time", default)]
-    #[allow(dead_code)]
-    kill_decay_end_time: Option<f64>,
+struct RawCommonness {
+    #[serde(rename = "@value", default)]
+    value: Option<f64>,
+    #[serde(rename = "@in_stock", default)]
+    in_stock: Option<String>,
+    #[serde(rename = "@can_respawn_with", default)]
+    can_respawn_with: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
 struct RawModifier {
     #[serde(rename = "@class", default)]
-    #[allow(dead_code)]
     class: Option<String>,
     #[serde(rename = "@value", default)]
-    #[allow(dead_code)]
     value: Option<f64>,
 }
 
@@ -290,250 +352,250 @@ pub async fn validate_game_path(path: String) -> Result<ValidationResult, String
 /// `directory` / `game_path` may be either:
 /// - the `packages` directory
 /// - the game/workshop root that contains `media/` (we'll scan `media/packages`)
+///
+/// The scan runs in the background; the returned job id can be passed to `cancel_scan`.
+/// Every definition of a key is streamed; use `scan_weapons_collect` for the effective view.
 #[tauri::command]
 pub async fn scan_weapons(
     game_path: String,
     directory: Option<String>,
+    attributes: Option<bool>,
+    jobs: State<'_, ScanJobs>,
     on_event: Channel<ScanEvent<Weapon>>,
-) -> Result<(), String> {
-    let source_directory = directory.clone().unwrap_or_else(|| game_path.clone());
-    let package_roots = resolve_packages_dirs(Path::new(&source_directory));
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        attributes: attributes.unwrap_or(false),
+        ..Default::default()
+    };
+    scanner::spawn_scan_with::<WeaponScanner>(&jobs, source_directory, options, on_event)
+}
 
-    #[cfg(debug_assertions)]
-    {
-        eprintln!(
-            "[scan_weapons] source_directory={} roots={}",
-            source_directory,
-            package_roots
-                .iter()
-                .map(|p| p.display().to_string())
-                .collect::<Vec<_>>()
-                .join(" | ")
-        );
-    }
+/// Fallback scan API (no IPC `Channel`).
+///
+/// Some environments (notably macOS WebView) may fail to deliver IPC channel
+/// messages reliably. This command returns all results in one payload; the
+/// frontend can still process the returned array in a worker in smaller chunks.
+///
+/// With `effective` set, only the definition the game uses is returned for each key,
+/// carrying its `provenance` (see `packages::LoadOrder` for the precedence rules).
+#[tauri::command]
+pub async fn scan_weapons_collect(
+    game_path: String,
+    directory: Option<String>,
+    effective: Option<bool>,
+    attributes: Option<bool>,
+) -> Result<WeaponScanResult, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        effective: effective.unwrap_or(false),
+        attributes: attributes.unwrap_or(false),
+    };
+    let scan = scanner::collect_scan::<WeaponScanner>(&source_directory, options)?;
+    Ok(WeaponScanResult {
+        weapons: scan.records,
+        summary: scan.summary,
+    })
+}
 
-    if package_roots.iter().all(|p| !p.exists()) {
-        let paths = package_roots
-            .iter()
-            .map(|p| p.display().to_string())
-            .collect::<Vec<_>>()
-            .join(", ");
-        return Err(format!("Directory not found (tried: {paths})"));
-    }
+/// Watch the packages roots of a weapons scan and stream add/update/remove deltas.
+///
+/// Call after `scan_weapons` with the same directory and channel; record ids match the
+/// scan's ids. Returns a watch id for `stop_watch`.
+#[tauri::command]
+pub async fn watch_weapons(
+    game_path: String,
+    directory: Option<String>,
+    attributes: Option<bool>,
+    registry: State<'_, WatchRegistry>,
+    on_event: Channel<ScanEvent<Weapon>>,
+) -> Result<u32, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let options = ScanOptions {
+        attributes: attributes.unwrap_or(false),
+        ..Default::default()
+    };
+    watch::start_with::<WeaponScanner>(&registry, source_directory, options, on_event)
+}
 
-    // Collect all .weapon files across possible packages roots (macOS app bundle + external media).
-    let mut weapon_files: Vec<(PathBuf, PathBuf)> = Vec::new();
-    for root in &package_roots {
-        if !root.exists() {
-            continue;
-        }
-        for weapon_file in discover_weapons(root) {
-            weapon_files.push((root.clone(), weapon_file));
-        }
-    }
+/// Explain where every attribute of a weapon file comes from along its `file="..."`
+/// template chain. `file_path` is the absolute path of the `.weapon` file.
+#[tauri::command]
+pub async fn explain_weapon(file_path: String) -> Result<Explanation, String> {
+    let path = Path::new(&file_path);
+    let mut dependencies = Vec::new();
+    let mut errors = Vec::new();
+    let (raw_weapon, _) = load_raw_weapon(
+        path,
+        path.parent().unwrap_or(path),
+        &mut dependencies,
+        &mut errors,
+    )
+    .map_err(|diagnostic| diagnostic.message)?;
+
+    Ok(Explanation::new(
+        path,
+        raw_weapon.key,
+        &dependencies,
+        raw_weapon.attributes,
+        errors,
+    ))
+}
 
-    let send_event = |evt: ScanEvent<Weapon>| -> Result<(), String> {
-        on_event
-            .send(evt)
-            .map_err(|e| format!("Failed to send scan event: {e}"))
-    };
+/// Export the effective weapon `key` as a standalone `.weapon` file with its template
+/// chain inlined. Writes to `output_path` when given and returns the XML either way.
+///
+/// The file is rebuilt from the attribute bag: text content and elements without
+/// attributes are dropped, and child elements are grouped by name rather than kept in
+/// source order (see `attributes::to_xml`).
+///
+/// `weapons` takes the rows of an effective scan the frontend already holds; without them
+/// the packages are scanned to find `key`.
+#[tauri::command]
+pub async fn export_weapon(
+    game_path: String,
+    directory: Option<String>,
+    key: String,
+    output_path: Option<String>,
+    weapons: Option<Vec<Weapon>>,
+) -> Result<String, String> {
+    let source_directory = directory.unwrap_or(game_path);
+    let weapons = scanner::records_or_scan::<WeaponScanner>(weapons, &source_directory)?;
+    let weapon = weapons
+        .iter()
+        .find(|w| w.key.as_deref() == Some(key.as_str()))
+        .ok_or_else(|| format!("Weapon not found: {}", key))?;
 
-    #[cfg(debug_assertions)]
-    {
-        eprintln!("[scan_weapons] discovered_files={}", weapon_files.len());
+    let xml = standalone_weapon(Path::new(&weapon.source_file))?;
+    if let Some(output_path) = output_path {
+        std::fs::write(&output_path, &xml)
+            .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
     }
+    Ok(xml)
+}
 
-    if weapon_files.is_empty() {
-        // Nothing to scan, but still notify the frontend so it can stop loading.
-        send_event(ScanEvent::Finished)?;
-        return Ok(());
+/// `.weapon` XML of the weapon in `path` with its template chain inlined. Malformed files are
+/// recovered like scans do; failed template resolution is an error.
+fn standalone_weapon(path: &Path) -> Result<String, String> {
+    let (raw_weapon, template_error) = load_raw_weapon(
+        path,
+        path.parent().unwrap_or(path),
+        &mut Vec::new(),
+        &mut Vec::new(),
+    )
+    .map_err(|diagnostic| diagnostic.message)?;
+    if let Some(error) = template_error {
+        return Err(error);
     }
 
-    let total = weapon_files.len();
-    send_event(ScanEvent::Progress { current: 0, total })?;
-
-    // Parallel processing with chunked channel sending
-    let all_results: Vec<Result<Weapon, ScanError>> = weapon_files
-        .into_par_iter()
-        .enumerate()
-        .map(|(index, (packages_root, weapon_file))| {
-            let file_str = weapon_file.to_string_lossy().to_string();
-            let id = format!("{}_{}", file_str, index);
-
-            parse_weapon_file(&weapon_file, &packages_root, id, &source_directory).map_err(|e| {
-                ScanError {
-                    file: file_str,
-                    error: e.to_string(),
-                    severity: "error".to_string(),
-                }
-            })
-        })
-        .collect();
+    let bag = attributes::standalone(&raw_weapon.attributes, path);
+    Ok(format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{}",
+        attributes::to_xml("weapon", &bag, 0)
+    ))
+}
 
-    #[cfg(debug_assertions)]
-    {
-        let ok_count = all_results.iter().filter(|r| r.is_ok()).count();
-        let err_count = all_results.len() - ok_count;
-        eprintln!(
-            "[scan_weapons] parsed_ok={} parsed_err={}",
-            ok_count, err_count
-        );
-    }
+/// Weapon scanner for the shared scanning framework
+pub(crate) struct WeaponScanner;
 
-    // Send in batches
-    for (i, chunk) in all_results.chunks(BATCH_SIZE).enumerate() {
-        let weapons: Vec<Weapon> = chunk
-            .iter()
-            .filter_map(|r| r.as_ref().ok().cloned())
-            .collect();
-        let errors: Vec<ScanError> = chunk
-            .iter()
-            .filter_map(|r| r.as_ref().err().cloned())
-            .collect();
+impl PackageScanner for WeaponScanner {
+    type Record = Weapon;
 
-        if !weapons.is_empty() {
-            send_event(ScanEvent::Chunk(weapons))?;
-        }
+    const KIND: &'static str = "weapons";
 
-        for error in errors {
-            send_event(ScanEvent::Error(error.error))?;
-        }
+    const RESOURCE_LIST: Option<ResourceListKind> = Some(ResourceListKind::Weapons);
 
-        send_event(ScanEvent::Progress {
-            current: ((i + 1) * BATCH_SIZE).min(total),
-            total,
-        })?;
+    fn matches(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "weapon")
     }
 
-    send_event(ScanEvent::Finished)?;
-
-    Ok(())
-}
-
-/// Fallback scan API (no IPC `Channel`).
-///
-/// Some environments (notably macOS WebView) may fail to deliver IPC channel
-/// messages reliably. This command returns all results in one payload; the
-/// frontend can still process the returned array in a worker in smaller chunks.
-#[tauri::command]
-pub async fn scan_weapons_collect(
-    game_path: String,
-    directory: Option<String>,
-) -> Result<WeaponScanResult, String> {
-    let started = std::time::Instant::now();
-
-    let source_directory = directory.clone().unwrap_or_else(|| game_path.clone());
-    let package_roots = resolve_packages_dirs(Path::new(&source_directory));
-
-    if package_roots.iter().all(|p| !p.exists()) {
-        let paths = package_roots
-            .iter()
-            .map(|p| p.display().to_string())
-            .collect::<Vec<_>>()
-            .join(", ");
-        return Err(format!("Directory not found (tried: {paths})"));
+    fn parse_cached(
+        cache: &ScanCache<Weapon>,
+        file: &Path,
+        packages_root: &Path,
+        index: usize,
+        source_directory: &str,
+    ) -> Result<Parsed<Weapon>, Diagnostic> {
+        parse_weapon_cached(cache, file, packages_root, index, source_directory)
     }
 
-    let mut weapon_files: Vec<(PathBuf, PathBuf)> = Vec::new();
-    for root in &package_roots {
-        if !root.exists() {
-            continue;
-        }
-        for weapon_file in discover_weapons(root) {
-            weapon_files.push((root.clone(), weapon_file));
-        }
+    fn record_id(record: &Weapon) -> &str {
+        &record.id
     }
 
-    if weapon_files.is_empty() {
-        return Ok(WeaponScanResult {
-            weapons: vec![],
-            errors: vec![],
-            duplicate_keys: vec![],
-            scan_time: started.elapsed().as_millis() as u64,
-        });
+    fn apply_resource_index(record: &mut Weapon, index: &ResourceIndex) {
+        record.resource_status = Some(index.status(&record.source_file));
     }
 
-    let all_results: Vec<Result<Weapon, ScanError>> = weapon_files
-        .into_par_iter()
-        .enumerate()
-        .map(|(index, (packages_root, weapon_file))| {
-            let file_str = weapon_file.to_string_lossy().to_string();
-            let id = format!("{}_{}", file_str, index);
+    fn clear_attributes(record: &mut Weapon) {
+        record.attributes = None;
+    }
 
-            parse_weapon_file(&weapon_file, &packages_root, id, &source_directory).map_err(|e| {
-                ScanError {
-                    file: file_str,
-                    error: e.to_string(),
-                    severity: "error".to_string(),
-                }
-            })
+    fn key_definition(record: &Weapon) -> Option<KeyDefinition> {
+        Some(KeyDefinition {
+            key: record.key.clone()?,
+            id: record.id.clone(),
+            package_name: record.package_name.clone(),
+            file_path: record.file_path.clone(),
+            source_file: record.source_file.clone(),
         })
-        .collect();
-
-    let weapons: Vec<Weapon> = all_results
-        .iter()
-        .filter_map(|r| r.as_ref().ok().cloned())
-        .collect();
-    let errors: Vec<ScanError> = all_results
-        .iter()
-        .filter_map(|r| r.as_ref().err().cloned())
-        .collect();
+    }
 
-    Ok(WeaponScanResult {
-        weapons,
-        errors,
-        duplicate_keys: vec![],
-        scan_time: started.elapsed().as_millis() as u64,
-    })
+    fn set_provenance(record: &mut Weapon, provenance: Provenance) {
+        record.provenance = Some(provenance);
+    }
 }
 
-/// Discover all .weapon files in packages directory
-fn discover_weapons(input_path: &Path) -> Vec<PathBuf> {
-    WalkDir::new(input_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "weapon"))
-        .map(|e| e.path().to_path_buf())
-        .collect()
+/// Parse a weapon file through the scan cache.
+/// Per-scan fields (`id`, `source_directory`) are refreshed on cache hits.
+fn parse_weapon_cached(
+    cache: &ScanCache<Weapon>,
+    weapon_file: &Path,
+    packages_root: &Path,
+    index: usize,
+    source_directory: &str,
+) -> Result<Parsed<Weapon>, Diagnostic> {
+    let file_str = weapon_file.to_string_lossy().to_string();
+    let id = format!("{}_{}", file_str, index);
+
+    let mut parsed = cache.get_or_parse(weapon_file, |dependencies, diagnostics| {
+        parse_weapon_file(
+            weapon_file,
+            packages_root,
+            id.clone(),
+            source_directory,
+            dependencies,
+            diagnostics,
+        )
+        .map(|weapon| vec![weapon])
+    })?;
+
+    for weapon in &mut parsed.records {
+        weapon.id = id.clone();
+        weapon.source_directory = source_directory.to_string();
+        if let Some(projectile) = &mut weapon.projectile {
+            projectile.id = format!("{}_projectile", id);
+            projectile.source_directory = source_directory.to_string();
+        }
+    }
+    Ok(parsed)
 }
 
-/// Parse a single weapon XML file with template resolution
+/// Parse a single weapon XML file with template resolution.
+/// Every template file consulted is appended to `dependencies`; template failures are
+/// reported as warnings in `diagnostics`.
 fn parse_weapon_file(
     weapon_path: &Path,
     input_path: &Path,
     id: String,
     source_directory: &str,
-) -> Result<Weapon, anyhow::Error> {
-    let content = std::fs::read_to_string(weapon_path)?;
-
-    // Get package name from path
-    let package_name = weapon_path
-        .components()
-        .rev()
-        .nth(2) // Skip filename and "weapons" directory
-        .and_then(|c| c.as_os_str().to_str())
-        .unwrap_or("unknown");
-
-    // Parse raw weapon
-    let mut raw_weapon: RawWeapon = from_str(&content)?;
-
-    // Resolve template inheritance if needed
-    // If template resolution fails, continue with partial data and set template_error
-    let mut template_error: Option<String> = None;
-    if let Some(template_file) = &raw_weapon.template_file {
-        let weapon_parent = weapon_path
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("Cannot get parent directory of weapon file"))?;
-        match resolve_template(weapon_parent, template_file, &mut HashSet::new()) {
-            Ok(resolved) => {
-                raw_weapon = merge_attributes(resolved, raw_weapon);
-            }
-            Err(e) => {
-                // Template resolution failed - continue with partial data
-                template_error = Some(format!("Template resolution failed: {}", e));
-            }
-        }
-    }
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Weapon, Diagnostic> {
+    let package_name = package_name_of(weapon_path, input_path);
+
+    let (mut raw_weapon, template_error) =
+        load_raw_weapon(weapon_path, input_path, dependencies, diagnostics)?;
 
     // T007, T008, T009: Extract tag and class as separate fields
     // tag: From <tag name="..."/> element (e.g., "assault", "smg")
@@ -570,14 +632,79 @@ fn parse_weapon_file(
     let encumbrance = raw_weapon.inventory.as_ref().and_then(|i| i.encumbrance);
     let price = raw_weapon.inventory.as_ref().and_then(|i| i.price);
 
-    // T012: Extract kill_probability from projectile/result.@kill_probability
-    let kill_probability = raw_weapon
+    // Extract from commonness ("0"/"1" flags, true when not specified)
+    let commonness = raw_weapon.commonness.as_ref().and_then(|c| c.value);
+    let can_respawn_with = raw_weapon
+        .commonness
+        .as_ref()
+        .and_then(|c| parse_flag(c.can_respawn_with.as_deref()))
+        .unwrap_or(true);
+    let in_stock = raw_weapon
+        .commonness
+        .as_ref()
+        .and_then(|c| parse_flag(c.in_stock.as_deref()))
+        .unwrap_or(true);
+
+    let mut chain_variants: Vec<String> = Vec::new();
+    for link in raw_weapon
+        .chain_variants
+        .into_iter()
+        .chain(raw_weapon.chain_variants_snake)
+    {
+        let Some(key) = link.key.or(link.text).map(|k| k.trim().to_string()) else {
+            continue;
+        };
+        if !key.is_empty() && !chain_variants.contains(&key) {
+            chain_variants.push(key);
+        }
+    }
+
+    let modifiers: Vec<WeaponModifier> = raw_weapon
+        .modifiers
+        .into_iter()
+        .filter_map(|m| {
+            Some(WeaponModifier {
+                class: m.class?,
+                value: m.value,
+            })
+        })
+        .collect();
+
+    // Resolve the projectile the weapon fires: the inline <projectile> element inherits
+    // from the `.projectile` file named by its `file` attribute.
+    let weapon_parent = weapon_path.parent().unwrap_or(input_path);
+    let projectile_path = raw_weapon
         .projectile
+        .as_ref()
+        .and_then(|p| p.file.as_deref())
+        .map(|file| templates::locate(weapon_parent, file, RawProjectile::VANILLA_DIR));
+    let raw_projectile = raw_weapon.projectile.take().map(|inline| {
+        let (projectile, repaired) =
+            templates::inherit(inline, weapon_path, dependencies, diagnostics);
+        raw_weapon.partially_parsed |= repaired;
+        projectile
+    });
+
+    // T012: Extract kill_probability from projectile/result.@kill_probability
+    let kill_probability = raw_projectile
         .as_ref()
         .and_then(|p| p.result.as_ref())
         .and_then(|r| r.kill_probability)
         .unwrap_or(0.0);
 
+    let projectile_file = projectile_path
+        .as_ref()
+        .map(|path| relative_file_path(path, input_path));
+    let projectile = raw_projectile.map(|raw| {
+        projectiles::build_projectile(
+            raw,
+            format!("{}_projectile", id),
+            projectile_path.as_deref().unwrap_or(weapon_path),
+            input_path,
+            source_directory,
+        )
+    });
+
     // Determine suppressed status (from specification.@suppressed as "0"/"1")
     let suppressed = raw_weapon
         .specification
@@ -597,17 +724,10 @@ fn parse_weapon_file(
         })
         .collect();
 
-    // Calculate relative file path from packages directory
-    // e.g., "vanilla/weapons/ak47.weapon"
-    let file_path = weapon_path
-        .strip_prefix(input_path)
-        .unwrap_or(weapon_path)
-        .to_string_lossy()
-        .trim_start_matches('/')
-        .to_string();
+    let file_path = relative_file_path(weapon_path, input_path);
 
     // Convert to final Weapon structure
-    let weapon = Weapon {
+    let mut weapon = Weapon {
         id,
         key: raw_weapon.key.filter(|k| !k.is_empty()).or_else(|| {
             weapon_path
@@ -622,91 +742,127 @@ fn parse_weapon_file(
         magazine_size,
         kill_probability,
         retrigger_time,
-        burst_shots: None,          // Not in current XML structure
-        spread_range: None,         // Not in current XML structure
-        sight_range_modifier: None, // Not in current XML structure
+        burst_shots: raw_weapon.specification.burst_shots,
+        spread_range: raw_weapon.specification.spread_range,
+        sight_range_modifier: raw_weapon.specification.sight_range_modifier,
         projectile_speed,
-        barrel_offset: None, // Not in current XML structure
+        barrel_offset: raw_weapon.specification.barrel_offset,
+        projectiles_per_shot: raw_weapon.specification.projectiles_per_shot,
+        accuracy_factor: raw_weapon.specification.accuracy_factor,
+        sustained_fire_grow_step: raw_weapon.specification.sustained_fire_grow_step,
+        sustained_fire_diminish_rate: raw_weapon.specification.sustained_fire_diminish_rate,
         encumbrance,
         price,
+        commonness,
         suppressed,
-        can_respawn_with: true, // Default true
-        in_stock: true,         // Default true
-        chain_variants: raw_weapon.chain_variants,
+        can_respawn_with,
+        in_stock,
+        modifiers,
+        chain_variants,
         stance_accuracies,
+        projectile_file,
+        projectile,
+        metrics: WeaponMetrics::default(),
         file_path,
         source_file: weapon_path.to_string_lossy().to_string(),
         source_directory: source_directory.to_string(),
-        package_name: package_name.to_string(),
+        package_name,
         template_error, // Set to Some(message) if template resolution failed, None otherwise
+        partially_parsed: raw_weapon.partially_parsed,
+        provenance: None,
+        resource_status: None,
+        attributes: Some(attributes::resolved(&raw_weapon.attributes)),
     };
+    weapon.metrics = metrics::weapon_metrics(&weapon);
 
     Ok(weapon)
 }
 
-/// Recursively resolve template inheritance with cycle detection
-/// base_dir: Parent directory of the file being parsed (for resolving relative template paths)
-/// Supports fallback to vanilla package for cross-package template references
-fn resolve_template(
-    base_dir: &Path,
-    template_file: &str,
-    visited: &mut HashSet<PathBuf>,
-) -> Result<RawWeapon, anyhow::Error> {
-    // Try to resolve template relative to the base directory first
-    let template_path = base_dir.join(template_file);
-
-    // If template doesn't exist in the same directory, try vanilla package as fallback
-    let template_path = if !template_path.exists() {
-        // Extract the packages root from base_dir
-        // e.g., /path/to/packages/man_vs_zombies/weapons -> /path/to/packages
-        if let Some(packages_dir) = base_dir.ancestors().nth(2) {
-            let vanilla_template = packages_dir.join("vanilla/weapons").join(template_file);
-            if vanilla_template.exists() {
-                vanilla_template
-            } else {
-                template_path // Return original path for better error message
+/// Read a weapon file and merge its template chain into it.
+/// If template resolution fails, the file's own data is returned with a template error
+/// message and a warning in `diagnostics`.
+fn load_raw_weapon(
+    weapon_path: &Path,
+    input_path: &Path,
+    dependencies: &mut Vec<PathBuf>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(RawWeapon, Option<String>), Diagnostic> {
+    let content = read_file(weapon_path)?;
+
+    // Parse raw weapon, recovering what we can from malformed files
+    let (mut raw_weapon, partially_parsed) = parse_recovering(&content, diagnostics, |content| {
+        RawWeapon::parse(content, weapon_path)
+    })?;
+    raw_weapon.partially_parsed = partially_parsed;
+
+    let mut template_error: Option<String> = None;
+    if let Some(template_file) = &raw_weapon.template_file {
+        let weapon_parent = weapon_path.parent().unwrap_or(input_path);
+        let chain_start = dependencies.len();
+        match resolve_template(
+            weapon_parent,
+            template_file,
+            &mut HashSet::new(),
+            dependencies,
+            diagnostics,
+        ) {
+            Ok((resolved, repaired)) => {
+                raw_weapon = merge_attributes(resolved, raw_weapon);
+                raw_weapon.partially_parsed |= repaired;
+            }
+            Err(diagnostic) => {
+                // Template resolution failed - continue with partial data
+                template_error = Some(format!(
+                    "Template resolution failed: {}",
+                    diagnostic.message
+                ));
+                diagnostics.push(
+                    diagnostic
+                        .with_severity(Severity::Warning)
+                        .with_template_chain(weapon_path, &dependencies[chain_start..]),
+                );
             }
-        } else {
-            template_path
         }
-    } else {
-        template_path
-    };
-
-    // Cycle detection
-    if !visited.insert(template_path.clone()) {
-        return Err(anyhow::anyhow!(
-            "Circular reference detected: {}",
-            template_file
-        ));
     }
 
-    if visited.len() > MAX_TEMPLATE_DEPTH {
-        return Err(anyhow::anyhow!(
-            "Template depth exceeded limit (>{})",
-            MAX_TEMPLATE_DEPTH
-        ));
-    }
+    Ok((raw_weapon, template_error))
+}
 
-    let content = std::fs::read_to_string(&template_path)?;
-    let mut raw_weapon: RawWeapon = from_str(&content)?;
+impl Template for RawWeapon {
+    const VANILLA_DIR: &'static str = "weapons";
+    const LABEL: &'static str = "weapon";
 
-    // Resolve parent template if exists
-    if let Some(parent_file) = &raw_weapon.template_file {
-        let template_parent = template_path
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("Cannot get parent directory of template"))?;
-        let parent = resolve_template(template_parent, parent_file, visited)?;
-        raw_weapon = merge_attributes(parent, raw_weapon);
+    fn parse(content: &str, path: &Path) -> Result<Self, Diagnostic> {
+        let mut raw: RawWeapon = parse_xml(content, path)?;
+        raw.attributes = root_attributes(content, path);
+        Ok(raw)
     }
 
-    visited.remove(&template_path);
-    Ok(raw_weapon)
+    fn template_file(&self) -> Option<String> {
+        self.template_file.clone()
+    }
+
+    fn merge(parent: Self, child: Self) -> Self {
+        merge_attributes(parent, child)
+    }
 }
 
+/// `<weapon>` attribute paths merged like [`merge_attributes`] merges the typed fields
+const WEAPON_MERGE: MergeRules = MergeRules {
+    lists: &[
+        (&["tag"], ListMerge::ByKey("name")),
+        (&["stance"], ListMerge::ByKey("state_key")),
+        (&["modifier"], ListMerge::Replace),
+        (&["nextInChain", "next_in_chain"], ListMerge::Replace),
+    ],
+    not_inherited: &["key", "projectile.key"],
+};
+
 /// Merge parent template attributes into child (child overrides parent)
 /// T009: Adjust template merge logic to merge stances by state_key
 fn merge_attributes(parent: RawWeapon, mut child: RawWeapon) -> RawWeapon {
+    child.attributes = attributes::merge_traces(parent.attributes, child.attributes, &WEAPON_MERGE);
+
     // Merge specification fields (parent values used if child's are None)
     merge_specification(&parent.specification, &mut child.specification);
 
@@ -715,11 +871,38 @@ fn merge_attributes(parent: RawWeapon, mut child: RawWeapon) -> RawWeapon {
         child.inventory = parent.inventory;
     }
 
-    // Merge projectile if child doesn't have one
-    if child.projectile.is_none() && parent.projectile.is_some() {
-        child.projectile = parent.projectile;
+    // Merge commonness field by field (child values win)
+    child.commonness = match (parent.commonness, child.commonness) {
+        (Some(parent), Some(mut child)) => {
+            if child.value.is_none() {
+                child.value = parent.value;
+            }
+            if child.in_stock.is_none() {
+                child.in_stock = parent.in_stock;
+            }
+            if child.can_respawn_with.is_none() {
+                child.can_respawn_with = parent.can_respawn_with;
+            }
+            Some(child)
+        }
+        (p, c) => c.or(p),
+    };
+
+    // Modifiers are inherited only when the child declares none
+    if child.modifiers.is_empty() {
+        child.modifiers = parent.modifiers;
     }
 
+    // Merge projectile field by field (child values win); its key is never inherited
+    child.projectile = match (parent.projectile, child.projectile) {
+        (Some(p), Some(c)) => Some(projectiles::merge_projectile_attributes(p, c)),
+        (Some(mut p), None) => {
+            p.key = None;
+            Some(p)
+        }
+        (None, c) => c,
+    };
+
     // Merge hud_icon if child doesn't have one
     if child.hud_icon.is_none() && parent.hud_icon.is_some() {
         child.hud_icon = parent.hud_icon;
@@ -754,12 +937,24 @@ fn merge_attributes(parent: RawWeapon, mut child: RawWeapon) -> RawWeapon {
         entries.into_iter().map(|(_, v)| v).collect()
     };
 
-    // Merge chain_variants (extend, not replace)
-    child.chain_variants.extend(parent.chain_variants);
+    // Chain links are inherited only when the child declares none; a variant extending its
+    // base weapon would otherwise link to itself
+    if child.chain_variants.is_empty() && child.chain_variants_snake.is_empty() {
+        child.chain_variants = parent.chain_variants;
+        child.chain_variants_snake = parent.chain_variants_snake;
+    }
 
     child
 }
 
+/// Attribute trace of the `<weapon>` root element
+fn root_attributes(content: &str, file: &Path) -> AttributeTrace {
+    attributes::attribute_traces(content, "weapon", file)
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
 /// Helper to merge specification fields
 fn merge_specification(parent: &RawSpecification, child: &mut RawSpecification) {
     if child.retrigger_time.is_none() && parent.retrigger_time.is_some() {
@@ -793,6 +988,21 @@ fn merge_specification(parent: &RawSpecification, child: &mut RawSpecification)
     if child.projectile_speed.is_none() && parent.projectile_speed.is_some() {
         child.projectile_speed = parent.projectile_speed;
     }
+    if child.burst_shots.is_none() && parent.burst_shots.is_some() {
+        child.burst_shots = parent.burst_shots;
+    }
+    if child.spread_range.is_none() && parent.spread_range.is_some() {
+        child.spread_range = parent.spread_range;
+    }
+    if child.sight_range_modifier.is_none() && parent.sight_range_modifier.is_some() {
+        child.sight_range_modifier = parent.sight_range_modifier;
+    }
+    if child.barrel_offset.is_none() && parent.barrel_offset.is_some() {
+        child.barrel_offset = parent.barrel_offset;
+    }
+    if child.projectiles_per_shot.is_none() && parent.projectiles_per_shot.is_some() {
+        child.projectiles_per_shot = parent.projectiles_per_shot;
+    }
 }
 
 /// Open a file in the system's default editor
@@ -816,35 +1026,10 @@ pub async fn get_texture_path(
     weapon_file_path: String,
     icon_filename: String,
 ) -> Result<String, String> {
-    // Navigate from weapon file to textures folder
-    let weapon_path = PathBuf::from(&weapon_file_path);
-    let weapon_dir = weapon_path
-        .parent()
-        .ok_or("Invalid weapon path: cannot get parent directory")?;
-
     // textures/ is a sibling to weapons/ folder
     // weapon_file_path = ".../packages/vanilla/weapons/ak47.weapon"
     // we need to go to ".../packages/vanilla/textures/hud_ak47.png"
-    let textures_dir = weapon_dir
-        .parent()
-        .ok_or("Invalid weapon path: cannot get grandparent directory")?
-        .join("textures");
-
-    let icon_path = textures_dir.join(&icon_filename);
-
-    // Check if icon file exists
-    if !icon_path.exists() {
-        return Err(format!(
-            "Icon file not found: {} (expected at: {})",
-            icon_filename,
-            icon_path.display()
-        ));
-    }
-
-    // Get canonical absolute path
-    let canonical = icon_path
-        .canonicalize()
-        .map_err(|e| format!("Failed to resolve icon path: {}", e))?;
+    let canonical = resolve_texture_path(Path::new(&weapon_file_path), &icon_filename)?;
 
     Ok(canonical.to_string_lossy().to_string())
 }
@@ -860,26 +1045,7 @@ pub async fn get_weapon_icon_base64(
     use std::fs;
 
     // Navigate from weapon file to textures folder
-    let weapon_path = PathBuf::from(&weapon_file_path);
-    let weapon_dir = weapon_path
-        .parent()
-        .ok_or("Invalid weapon path: cannot get parent directory")?;
-
-    let textures_dir = weapon_dir
-        .parent()
-        .ok_or("Invalid weapon path: cannot get grandparent directory")?
-        .join("textures");
-
-    let icon_path = textures_dir.join(&icon_filename);
-
-    // Check if icon file exists
-    if !icon_path.exists() {
-        return Err(format!(
-            "Icon file not found: {} (expected at: {})",
-            icon_filename,
-            icon_path.display()
-        ));
-    }
+    let icon_path = resolve_texture_path(Path::new(&weapon_file_path), &icon_filename)?;
 
     // Read image file
     let image_data =
@@ -899,3 +1065,174 @@ pub async fn get_weapon_icon_base64(
 
     Ok(format!("data:{};base64,{}", mime_type, base64_string))
 }
+
+/// Weapon `key` of the vanilla package with every other field empty, for tests
+#[cfg(test)]
+pub(crate) fn test_weapon(key: &str) -> Weapon {
+    Weapon {
+        id: key.to_string(),
+        key: Some(key.to_string()),
+        name: key.to_string(),
+        tag: String::new(),
+        class: 0,
+        hud_icon: None,
+        magazine_size: 0.0,
+        kill_probability: 0.0,
+        retrigger_time: 0.0,
+        burst_shots: None,
+        spread_range: None,
+        sight_range_modifier: None,
+        projectile_speed: None,
+        barrel_offset: None,
+        projectiles_per_shot: None,
+        accuracy_factor: None,
+        sustained_fire_grow_step: None,
+        sustained_fire_diminish_rate: None,
+        encumbrance: None,
+        price: None,
+        commonness: None,
+        suppressed: false,
+        can_respawn_with: true,
+        in_stock: true,
+        modifiers: Vec::new(),
+        chain_variants: Vec::new(),
+        stance_accuracies: Vec::new(),
+        projectile_file: None,
+        projectile: None,
+        metrics: WeaponMetrics::default(),
+        file_path: format!("vanilla/weapons/{}", key),
+        source_file: String::new(),
+        source_directory: String::new(),
+        package_name: "vanilla".to_string(),
+        template_error: None,
+        partially_parsed: false,
+        provenance: None,
+        resource_status: None,
+        attributes: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute_bag_agrees_with_the_typed_merge() {
+        let packages = tempfile::tempdir().unwrap();
+        let weapons = packages.path().join("vanilla/weapons");
+        std::fs::create_dir_all(&weapons).unwrap();
+        std::fs::write(
+            weapons.join("base.weapon"),
+            r#"<weapon key="base.weapon">
+                <specification name="Base" magazine_size="30" projectiles_per_shot="8" />
+                <stance state_key="running" accuracy="0.3" />
+                <stance state_key="prone" accuracy="0.9" />
+                <modifier class="speed" value="0.1" />
+                <modifier class="detectability" value="0.2" />
+                <next_in_chain key="base_gl.weapon" />
+                <projectile key="base_bullet"><result kill_probability="1.5" /></projectile>
+            </weapon>"#,
+        )
+        .unwrap();
+        let variant = weapons.join("variant.weapon");
+        std::fs::write(
+            &variant,
+            r#"<weapon file="base.weapon">
+                <specification name="Variant" />
+                <stance state_key="prone" accuracy="1.0" />
+                <modifier class="speed" value="0.5" />
+                <nextInChain key="base.weapon" />
+            </weapon>"#,
+        )
+        .unwrap();
+
+        let weapon = parse_weapon_file(
+            &variant,
+            packages.path(),
+            "variant".to_string(),
+            "",
+            &mut Vec::new(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+        let bag = weapon.attributes.clone().unwrap();
+        // Every `<name>` / `<name>[n]` element's `attribute`, in bag order
+        let listed = |names: &[&str], attribute: &str| -> Vec<String> {
+            bag.iter()
+                .filter(|(path, _)| {
+                    path.split_once('.').is_some_and(|(segment, rest)| {
+                        rest == attribute && names.contains(&parse_element(segment))
+                    })
+                })
+                .map(|(_, value)| value.clone())
+                .collect()
+        };
+
+        assert!(!bag.contains_key("key"));
+        assert!(!bag.contains_key("projectile.key"));
+        assert_eq!(weapon.projectile.as_ref().unwrap().key, None);
+        assert_eq!(bag["specification.name"], weapon.name);
+        assert_eq!(bag["specification.magazine_size"], "30");
+        assert_eq!(weapon.magazine_size, 30.0);
+        assert_eq!(weapon.projectiles_per_shot, Some(8.0));
+
+        let mut stances: Vec<(String, f64)> = weapon
+            .stance_accuracies
+            .iter()
+            .map(|s| (s.stance.clone(), s.accuracy))
+            .collect();
+        stances.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut bag_stances: Vec<(String, f64)> = listed(&["stance"], "state_key")
+            .into_iter()
+            .zip(listed(&["stance"], "accuracy"))
+            .map(|(state, accuracy)| (state, accuracy.parse().unwrap()))
+            .collect();
+        bag_stances.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(bag_stances, stances);
+        assert_eq!(
+            stances,
+            [("prone".to_string(), 1.0), ("running".to_string(), 0.3)]
+        );
+
+        let modifiers: Vec<String> = weapon.modifiers.iter().map(|m| m.class.clone()).collect();
+        assert_eq!(listed(&["modifier"], "class"), modifiers);
+        assert_eq!(
+            listed(&["nextInChain", "next_in_chain"], "key"),
+            weapon.chain_variants
+        );
+        assert_eq!(weapon.chain_variants, ["base.weapon"]);
+    }
+
+    #[test]
+    fn exports_a_keyless_variant_without_its_template_key() {
+        let packages = tempfile::tempdir().unwrap();
+        let weapons = packages.path().join("vanilla/weapons");
+        std::fs::create_dir_all(&weapons).unwrap();
+        std::fs::write(
+            weapons.join("base.weapon"),
+            r#"<weapon key="base.weapon"><specification name="Base" magazine_size="30" /></weapon>"#,
+        )
+        .unwrap();
+        let variant = weapons.join("variant.weapon");
+        std::fs::write(
+            &variant,
+            r#"<weapon file="base.weapon"><specification name="Salt & Pepper" /></weapon>"#,
+        )
+        .unwrap();
+
+        let xml = standalone_weapon(&variant).unwrap();
+        let bag = attributes::attribute_bags(&xml, "weapon").remove(0);
+        assert_eq!(bag.get("key"), None);
+        assert_eq!(bag.get("file"), None);
+        assert_eq!(bag["specification.name"], "Salt & Pepper");
+        assert_eq!(bag["specification.magazine_size"], "30");
+
+        std::fs::write(&variant, r#"<weapon file="missing.weapon" />"#).unwrap();
+        assert!(standalone_weapon(&variant).is_err());
+    }
+
+    /// `stance[1]` → `stance`
+    fn parse_element(segment: &str) -> &str {
+        segment.split('[').next().unwrap_or(segment)
+    }
+}
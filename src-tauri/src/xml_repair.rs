@@ -0,0 +1,318 @@
+//! Best-effort repair of malformed XML
+//!
+//! Mod files are edited by hand, and a single bare `&` or unclosed tag makes quick-xml
+//! reject the whole file. [`repair_xml`] rewrites the markup so that it parses, keeping as
+//! much of the author's data as it can:
+//!
+//! - bare `&` and `<` in text and attribute values are escaped
+//! - unquoted attribute values are quoted; attributes without a value and repeated
+//!   attributes are dropped
+//! - a tag cut off by the next `<` is closed as an empty element
+//! - end tags that close nothing are dropped; elements left open by an outer end tag become
+//!   empty elements, and elements still open at the end of the file are closed there
+
+/// Entities quick-xml resolves without a DTD
+const PREDEFINED_ENTITIES: &[&[u8]] = &[b"lt", b"gt", b"amp", b"apos", b"quot"];
+
+/// Rewrite `content` into well-formed XML. Only meant for content that failed to parse;
+/// layout inside tags is normalized even when nothing is wrong with it.
+pub fn repair_xml(content: &str) -> String {
+    let mut repair = Repair {
+        src: content.as_bytes(),
+        pos: 0,
+        out: Vec::with_capacity(content.len()),
+        open: Vec::new(),
+    };
+    repair.run();
+    // Only ASCII is inserted, always next to ASCII, so the output stays valid UTF-8
+    String::from_utf8_lossy(&repair.out).into_owned()
+}
+
+struct Repair<'a> {
+    src: &'a [u8],
+    pos: usize,
+    out: Vec<u8>,
+    /// Open elements, outermost first, with the position of their `>` in `out`
+    open: Vec<(&'a [u8], usize)>,
+}
+
+impl<'a> Repair<'a> {
+    fn run(&mut self) {
+        while let Some(&byte) = self.src.get(self.pos) {
+            match byte {
+                b'<' => self.markup(),
+                b'&' => self.ampersand(),
+                _ => {
+                    self.out.push(byte);
+                    self.pos += 1;
+                }
+            }
+        }
+        while let Some((name, _)) = self.open.pop() {
+            self.push_end_tag(name);
+        }
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.src[self.pos..]
+    }
+
+    fn markup(&mut self) {
+        let rest = self.rest();
+        if rest.starts_with(b"<!--") {
+            self.copy_until(4, b"-->");
+        } else if rest.starts_with(b"<![CDATA[") {
+            self.copy_until(9, b"]]>");
+        } else if rest.starts_with(b"<?") {
+            self.copy_until(2, b"?>");
+        } else if rest.starts_with(b"<!") {
+            self.copy_until(2, b">");
+        } else if rest.starts_with(b"</") {
+            self.end_tag();
+        } else if rest.get(1).is_some_and(|&b| is_name_start(b)) {
+            self.start_tag();
+        } else {
+            self.out.extend_from_slice(b"&lt;");
+            self.pos += 1;
+        }
+    }
+
+    fn ampersand(&mut self) {
+        match entity_len(self.rest()) {
+            Some(len) => {
+                self.out.extend_from_slice(&self.rest()[..len]);
+                self.pos += len;
+            }
+            None => {
+                self.out.extend_from_slice(b"&amp;");
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Copy a comment, CDATA section or declaration, terminating it at the end of the file
+    fn copy_until(&mut self, opener_len: usize, terminator: &[u8]) {
+        let rest = self.rest();
+        match find(&rest[opener_len..], terminator) {
+            Some(i) => {
+                let len = opener_len + i + terminator.len();
+                self.out.extend_from_slice(&rest[..len]);
+                self.pos += len;
+            }
+            None => {
+                self.out.extend_from_slice(rest);
+                self.out.extend_from_slice(terminator);
+                self.pos = self.src.len();
+            }
+        }
+    }
+
+    fn start_tag(&mut self) {
+        let start = self.pos + 1;
+        let name = &self.src[start..start + name_len(&self.src[start..])];
+        self.out.push(b'<');
+        self.out.extend_from_slice(name);
+        self.pos = start + name.len();
+
+        let mut seen: Vec<&[u8]> = Vec::new();
+        loop {
+            match self.src.get(self.pos) {
+                None | Some(b'<') => {
+                    self.out.extend_from_slice(b"/>");
+                    return;
+                }
+                Some(b'>') => {
+                    self.open.push((name, self.out.len()));
+                    self.out.push(b'>');
+                    self.pos += 1;
+                    return;
+                }
+                Some(b'/') if self.src.get(self.pos + 1) == Some(&b'>') => {
+                    self.out.extend_from_slice(b"/>");
+                    self.pos += 2;
+                    return;
+                }
+                Some(&b) if is_name_start(b) => self.attribute(&mut seen),
+                // Whitespace, stray quotes, slashes, ...
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn attribute(&mut self, seen: &mut Vec<&'a [u8]>) {
+        let name = &self.rest()[..name_len(self.rest())];
+        self.pos += name.len();
+        self.skip_whitespace();
+        if self.src.get(self.pos) != Some(&b'=') {
+            return;
+        }
+        self.pos += 1;
+        self.skip_whitespace();
+
+        let rest = self.rest();
+        let value = match rest.first() {
+            Some(&quote @ (b'"' | b'\'')) => {
+                let body = &rest[1..];
+                match body.iter().position(|&b| b == quote || b == b'<') {
+                    Some(end) if body[end] == quote => {
+                        self.pos += end + 2;
+                        &body[..end]
+                    }
+                    // Unterminated: `<` can't be part of a value, so the tag ended before it
+                    _ => {
+                        let end = body
+                            .iter()
+                            .position(|&b| b == b'>' || b == b'<')
+                            .unwrap_or(body.len());
+                        self.pos += end + 1;
+                        &body[..end]
+                    }
+                }
+            }
+            _ => {
+                let mut end = rest
+                    .iter()
+                    .position(|&b| b.is_ascii_whitespace() || b == b'>' || b == b'<')
+                    .unwrap_or(rest.len());
+                if end > 0 && rest[end - 1] == b'/' && rest.get(end) == Some(&b'>') {
+                    end -= 1;
+                }
+                self.pos += end;
+                &rest[..end]
+            }
+        };
+
+        if seen.contains(&name) {
+            return;
+        }
+        seen.push(name);
+        self.out.push(b' ');
+        self.out.extend_from_slice(name);
+        self.out.extend_from_slice(b"=\"");
+        let mut i = 0;
+        while i < value.len() {
+            match value[i] {
+                b'&' => match entity_len(&value[i..]) {
+                    Some(len) => {
+                        self.out.extend_from_slice(&value[i..i + len]);
+                        i += len;
+                        continue;
+                    }
+                    None => self.out.extend_from_slice(b"&amp;"),
+                },
+                b'<' => self.out.extend_from_slice(b"&lt;"),
+                b'"' => self.out.extend_from_slice(b"&quot;"),
+                byte => self.out.push(byte),
+            }
+            i += 1;
+        }
+        self.out.push(b'"');
+    }
+
+    fn end_tag(&mut self) {
+        let start = self.pos + 2;
+        let name = &self.src[start..start + name_len(&self.src[start..])];
+        let rest = &self.src[start + name.len()..];
+        self.pos = start
+            + name.len()
+            + match rest.iter().position(|&b| b == b'>' || b == b'<') {
+                Some(i) if rest[i] == b'>' => i + 1,
+                Some(i) => i,
+                None => rest.len(),
+            };
+
+        let Some(depth) = self.open.iter().rposition(|(open, _)| *open == name) else {
+            return;
+        };
+        // Innermost first, so earlier positions in `out` are not shifted yet
+        for (_, gt) in self.open.drain(depth + 1..).rev() {
+            self.out.insert(gt, b'/');
+        }
+        self.open.pop();
+        self.push_end_tag(name);
+    }
+
+    fn push_end_tag(&mut self, name: &[u8]) {
+        self.out.extend_from_slice(b"</");
+        self.out.extend_from_slice(name);
+        self.out.push(b'>');
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .src
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+}
+
+fn is_name_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b':' || !byte.is_ascii()
+}
+
+fn name_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|&b| !(is_name_start(b) || b.is_ascii_digit() || b == b'-' || b == b'.'))
+        .unwrap_or(bytes.len())
+}
+
+/// Length of the entity reference `bytes` starts with, including `&` and `;`
+fn entity_len(bytes: &[u8]) -> Option<usize> {
+    let end = bytes.iter().take(12).position(|&b| b == b';')?;
+    let body = &bytes[1..end];
+    let valid = match body {
+        [b'#', b'x', hex @ ..] => !hex.is_empty() && hex.iter().all(u8::is_ascii_hexdigit),
+        [b'#', digits @ ..] => !digits.is_empty() && digits.iter().all(u8::is_ascii_digit),
+        name => PREDEFINED_ENTITIES.contains(&name),
+    };
+    valid.then_some(end + 1)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_bare_ampersands_and_brackets() {
+        assert_eq!(
+            repair_xml(r#"<a name="Salt & Pepper &amp; co">x < y &#38; z</a>"#),
+            r#"<a name="Salt &amp; Pepper &amp; co">x &lt; y &#38; z</a>"#
+        );
+    }
+
+    #[test]
+    fn closes_tags_cut_off_by_the_next_tag() {
+        assert_eq!(
+            repair_xml("<weapon>\n<specification name=\"x\"\n</weapon>"),
+            "<weapon>\n<specification name=\"x\"/></weapon>"
+        );
+        assert_eq!(
+            repair_xml("<a><b c=\"1\n<d/></a>"),
+            "<a><b c=\"1\n\"/><d/></a>"
+        );
+    }
+
+    #[test]
+    fn balances_stray_and_missing_end_tags() {
+        assert_eq!(
+            repair_xml("<a><b x=\"1\"><c/></d></a><e>"),
+            "<a><b x=\"1\"/><c/></a><e></e>"
+        );
+    }
+
+    #[test]
+    fn quotes_values_and_drops_broken_attributes() {
+        assert_eq!(
+            repair_xml("<a x=1 y z='2' x=\"3\" w=4/>"),
+            "<a x=\"1\" z=\"2\" w=\"4\"/>"
+        );
+    }
+}
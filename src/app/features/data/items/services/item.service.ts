@@ -282,8 +282,10 @@ export class ItemService implements OnDestroy {
                     } else {
                         this.handleDataChunk(data);
                     }
-                } else if (eventType === 'error') {
-                    this.error.set(data);
+                } else if (eventType === 'diagnostic') {
+                    if (data?.severity === 'error') {
+                        this.error.set(data.message);
+                    }
                 } else if (eventType === 'finished') {
                     finish();
                 }
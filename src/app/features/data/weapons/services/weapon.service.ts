@@ -273,8 +273,10 @@ export class WeaponService implements OnDestroy {
                     } else {
                         this.handleDataChunk(data);
                     }
-                } else if (eventType === 'error') {
-                    this.error.set(data);
+                } else if (eventType === 'diagnostic') {
+                    if (data?.severity === 'error') {
+                        this.error.set(data.message);
+                    }
                 } else if (eventType === 'finished') {
                     finish();
                 }
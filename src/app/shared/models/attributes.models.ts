@@ -0,0 +1,41 @@
+/**
+ * Template inheritance traces returned by explain_weapon / explain_item
+ */
+
+import type { ScanDiagnostic } from './weapons.models';
+
+/**
+ * An attribute value and the file declaring it
+ */
+export interface TracedValue {
+    file: string;
+    value: string;
+}
+
+/**
+ * Where one resolved field comes from
+ */
+export interface FieldTrace {
+    /** Flattened attribute path (e.g. 'specification.magazine_size') */
+    field: string;
+    value: string;
+    /** File supplying the resolved value */
+    sourceFile: string;
+    /** Whether sourceFile is a template rather than the explained file itself */
+    inherited: boolean;
+    /** Values further up the template chain hidden by value, nearest first */
+    overridden: TracedValue[];
+}
+
+/**
+ * Field-by-field inheritance trace of one weapon / carry_item / visual_item
+ */
+export interface Explanation {
+    sourceFile: string;
+    key?: string;
+    /** Templates resolved from the file, outermost first */
+    templateChain: string[];
+    fields: FieldTrace[];
+    /** Template problems; fields of unresolved templates are missing from fields */
+    errors: ScanDiagnostic[];
+}
@@ -0,0 +1,64 @@
+/**
+ * Radio call data models for RWR .call files
+ */
+
+import type {
+    CacheStats,
+    DuplicateKey,
+    Provenance,
+    ScanDiagnostic,
+} from './weapons.models';
+
+/**
+ * Radio call definition from a parsed .call file (template chain resolved)
+ */
+export interface Call {
+    /** Unique identifier for tracking (generated backend) */
+    id: string;
+    /** Call key (key attribute, else file name) */
+    key?: string;
+    /** Display name */
+    name: string;
+    /** In-game cost */
+    price?: number;
+    /** Seconds before the call can be used again */
+    cooldown?: number;
+    /** Minimum rank XP */
+    rankRequirement?: number;
+    /** Projectile files spawned by the call */
+    projectiles: string[];
+    /** Vehicle keys/files spawned by the call */
+    vehicles: string[];
+    /** Icon filename from <hud_icon filename="..."/> */
+    hudIcon?: string;
+    /** Absolute icon path in the sibling textures/ folder, if the file exists */
+    hudIconPath?: string;
+    /** File path relative to packages directory (e.g., 'vanilla/calls/artillery.call') */
+    filePath: string;
+    /** Original absolute XML file path */
+    sourceFile: string;
+    /** Directory path where this call was scanned from */
+    sourceDirectory: string;
+    /** Package name (vanilla or mod) */
+    packageName: string;
+    /** Effective scans only: winning package and the definitions it shadows */
+    provenance?: Provenance;
+    /** Malformed XML: recovered from a repaired copy, fields may be missing */
+    partiallyParsed?: boolean;
+}
+
+/**
+ * Result from call scanning
+ */
+export interface CallScanResult {
+    /** All scanned calls */
+    calls: Call[];
+    /** Errors encountered during scan */
+    errors: ScanDiagnostic[];
+    /** Duplicate call keys detected */
+    duplicateKeys: DuplicateKey[];
+    /** Scan duration in milliseconds */
+    scanTime: number;
+    /** Cache hits and reparsed files for this scan */
+    cacheStats: CacheStats;
+}
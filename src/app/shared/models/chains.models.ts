@@ -0,0 +1,44 @@
+/**
+ * Weapon chain graph returned by weapon_chains / weapon_chain
+ */
+
+export interface ChainMember {
+    key: string;
+    weaponId: string;
+    name: string;
+}
+
+export interface ChainLink {
+    from: string;
+    to: string;
+}
+
+/**
+ * Link to a key no scanned weapon defines
+ */
+export interface DanglingLink {
+    from: string;
+    weaponId: string;
+    /** Key as written in <next_in_chain> */
+    to: string;
+}
+
+/**
+ * Weapons connected by next_in_chain links
+ */
+export interface ChainGroup {
+    /** Sorted by key */
+    members: ChainMember[];
+    links: ChainLink[];
+    /** Sets of members that can reach each other, each sorted by key */
+    cycles: string[][];
+    /** Whether switching can get from every member to every other one and back */
+    closed: boolean;
+}
+
+export interface ChainGraph {
+    groups: ChainGroup[];
+    dangling: DanglingLink[];
+    /** Weapon key → index into groups */
+    groupOf: Record<string, number>;
+}
@@ -0,0 +1,88 @@
+/**
+ * Character data models for RWR .character files
+ */
+
+import type {
+    CacheStats,
+    DuplicateKey,
+    Provenance,
+    ScanDiagnostic,
+} from './weapons.models';
+
+/**
+ * Attribute modifier of a character
+ */
+export interface CharacterModifier {
+    class: string;
+    value?: number;
+}
+
+/**
+ * Soldier character definition from a parsed .character file
+ */
+export interface Character {
+    /** Unique identifier for tracking (generated backend) */
+    id: string;
+    /** Character file name, as factions refer to it (e.g., 'default.character') */
+    key: string;
+    /** Display name */
+    name: string;
+    /** Model files */
+    models: string[];
+    /** Voice sets */
+    voices: string[];
+    /** Skin visual_items */
+    skins: string[];
+    modifiers: CharacterModifier[];
+    /** File path relative to packages directory */
+    filePath: string;
+    /** Original absolute XML file path */
+    sourceFile: string;
+    /** Directory path where this character was scanned from */
+    sourceDirectory: string;
+    /** Package name (vanilla or mod) */
+    packageName: string;
+    /** Effective scans only: winning package and the definitions it shadows */
+    provenance?: Provenance;
+    /** Malformed XML: recovered from a repaired copy, fields may be missing */
+    partiallyParsed?: boolean;
+}
+
+/**
+ * Result from character scanning
+ */
+export interface CharacterScanResult {
+    /** All scanned characters */
+    characters: Character[];
+    /** Errors encountered during scan */
+    errors: ScanDiagnostic[];
+    /** Character file names found in several packages */
+    duplicateKeys: DuplicateKey[];
+    /** Scan duration in milliseconds */
+    scanTime: number;
+    /** Cache hits and reparsed files for this scan */
+    cacheStats: CacheStats;
+}
+
+/**
+ * Faction soldier group using a character
+ */
+export interface SoldierRef {
+    factionId: string;
+    factionName: string;
+    soldier: string;
+}
+
+/**
+ * Character file name with everything referring to it (from character_links)
+ */
+export interface CharacterLink {
+    key: string;
+    /** Ids of the character records; empty if factions refer to a missing file */
+    characterIds: string[];
+    usedBy: SoldierRef[];
+    /** Skins no visual_item provides */
+    missingSkins: string[];
+    /** Ids of the visual_items providing the skins */
+    skinItemIds: string[];
+}
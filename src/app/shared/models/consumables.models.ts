@@ -0,0 +1,49 @@
+/**
+ * Item consumption graph returned by item_consumption / item_consumable
+ */
+
+/**
+ * Modifier of a consumable that acts on character state
+ */
+export interface StateChange {
+    modifierClass: string;
+    /** State the soldier must be in; any state when absent */
+    inputCharacterState?: string;
+    outputCharacterState?: string;
+    value?: number;
+    consumesItem: boolean;
+}
+
+export interface Consumable {
+    key: string;
+    itemId: string;
+    name: string;
+    transformOnConsume?: string;
+    /** Keys the item turns into when consumed repeatedly, in order */
+    transformChain: string[];
+    /** Keys of the items transforming into this one */
+    transformedFrom: string[];
+    stateChanges: StateChange[];
+    /** Whether any modifier removes the item when it applies */
+    consumesItem: boolean;
+    /** Whether the transform chain ends in a loop */
+    cyclic: boolean;
+}
+
+/**
+ * transform_on_consume target no scanned carry_item defines
+ */
+export interface BrokenTransform {
+    from: string;
+    itemId: string;
+    /** Key as written in transform_on_consume */
+    to: string;
+}
+
+export interface ConsumeGraph {
+    /** Sorted by key */
+    consumables: Consumable[];
+    broken: BrokenTransform[];
+    /** Keys that transform back into themselves, each loop starting at its smallest key */
+    cycles: string[][];
+}
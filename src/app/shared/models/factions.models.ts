@@ -0,0 +1,113 @@
+/**
+ * Faction data models for RWR .faction files
+ */
+
+import type {
+    CacheStats,
+    DuplicateKey,
+    Provenance,
+    ScanDiagnostic,
+} from './weapons.models';
+
+/** Kind of resource a soldier can spawn with */
+export type ResourceKind = 'weapon' | 'carry_item';
+
+/**
+ * Rank threshold of a faction
+ */
+export interface FactionRank {
+    name: string;
+    /** XP threshold for the rank */
+    xp: number;
+}
+
+/**
+ * Weapon or carry_item a soldier group can spawn with
+ */
+export interface SoldierResource {
+    kind: ResourceKind;
+    key: string;
+    /** spawn_score of the entry, when set */
+    weight?: number;
+}
+
+/**
+ * <soldier> group of a faction
+ */
+export interface SoldierGroup {
+    name: string;
+    /** Relative weight of this group when the faction spawns a soldier */
+    spawnScore?: number;
+    /** .character file the group uses */
+    character?: string;
+    /** .models file the group uses */
+    models?: string;
+    /** Per <resources> element: its .resources file entries, then its inline ones; weapons before carry items within each */
+    resources: SoldierResource[];
+}
+
+/**
+ * Faction definition from a parsed .faction file
+ */
+export interface Faction {
+    /** Unique identifier for tracking (generated backend) */
+    id: string;
+    /** Faction key (key attribute, else file name) */
+    key?: string;
+    /** Display name */
+    name: string;
+    /** RGB(A) components (0.0 - 1.0) */
+    color?: number[];
+    /** Ranks in declaration order */
+    ranks: FactionRank[];
+    soldiers: SoldierGroup[];
+    /** File path relative to packages directory (e.g., 'vanilla/factions/greenbelts.faction') */
+    filePath: string;
+    /** Original absolute XML file path */
+    sourceFile: string;
+    /** Directory path where this faction was scanned from */
+    sourceDirectory: string;
+    /** Package name (vanilla or mod) */
+    packageName: string;
+    /** Effective scans only: winning package and the definitions it shadows */
+    provenance?: Provenance;
+    /** Malformed XML: recovered from a repaired copy, fields may be missing */
+    partiallyParsed?: boolean;
+}
+
+/**
+ * Result from faction scanning
+ */
+export interface FactionScanResult {
+    /** All scanned factions */
+    factions: Faction[];
+    /** Errors encountered during scan */
+    errors: ScanDiagnostic[];
+    /** Duplicate faction keys detected */
+    duplicateKeys: DuplicateKey[];
+    /** Scan duration in milliseconds */
+    scanTime: number;
+    /** Cache hits and reparsed files for this scan */
+    cacheStats: CacheStats;
+}
+
+/**
+ * Soldier group able to spawn with a resource
+ */
+export interface FactionSpawn {
+    factionId: string;
+    factionName: string;
+    soldier: string;
+    weight?: number;
+}
+
+/**
+ * Factions that can spawn with one weapon/carry_item key (from faction_loadouts)
+ */
+export interface LoadoutUsage {
+    kind: ResourceKind;
+    key: string;
+    /** Ids of the weapon/item records registering the key; empty if none does */
+    recordIds: string[];
+    spawns: FactionSpawn[];
+}
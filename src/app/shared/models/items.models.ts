@@ -3,6 +3,14 @@
  * Feature: 002-table-enhancements
  */
 
+import type {
+    CacheStats,
+    DuplicateKey,
+    Provenance,
+    ResourceStatus,
+    ScanDiagnostic,
+} from './weapons.models';
+
 /**
  * Generic item structure (all item types)
  */
@@ -25,6 +33,14 @@ export interface GameItem {
     capacity?: ItemCapacity;
     commonness?: ItemCommonness;
     modifiers?: ItemModifier[];
+    /** Malformed XML: recovered from a repaired copy, fields may be missing */
+    partiallyParsed?: boolean;
+    /** Effective scans only: winning package and the definitions it shadows */
+    provenance?: Provenance;
+    /** carry_items only: whether the package's all_carry_items.xml lists this file */
+    resourceStatus?: ResourceStatus;
+    /** Every XML attribute after template merging (e.g. 'specification.stab_range'); only when the scan asked for it */
+    attributes?: Record<string, string>;
 }
 
 /**
@@ -115,15 +131,12 @@ export interface ItemCommonness {
 export interface ItemScanResult {
     items: GenericItem[];
     errors: ItemScanError[];
-    duplicateKeys: string[];
+    duplicateKeys: DuplicateKey[];
     scanTime: number;
+    cacheStats: CacheStats;
 }
 
 /**
  * Error during item scanning
  */
-export interface ItemScanError {
-    file: string;
-    error: string;
-    severity: string;
-}
+export type ItemScanError = ScanDiagnostic;
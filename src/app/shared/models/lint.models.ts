@@ -0,0 +1,26 @@
+/**
+ * Package lint report returned by lint_package
+ */
+
+import type { ScanDiagnostic } from './weapons.models';
+
+export interface FileIssues {
+    /** Absolute path of the package file the issues belong to */
+    file: string;
+    issues: ScanDiagnostic[];
+}
+
+export interface SeverityGroup {
+    severity: ScanDiagnostic['severity'];
+    /** Sorted by file */
+    files: FileIssues[];
+}
+
+export interface PackageLint {
+    packageName: string;
+    errorCount: number;
+    warningCount: number;
+    infoCount: number;
+    /** Most severe first; severities without issues are left out */
+    groups: SeverityGroup[];
+}
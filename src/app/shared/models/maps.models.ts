@@ -0,0 +1,70 @@
+/**
+ * Map data models for RWR package maps (maps/<map>/objects.svg)
+ */
+
+import type {
+    CacheStats,
+    DuplicateKey,
+    Provenance,
+    ScanDiagnostic,
+} from './weapons.models';
+
+/**
+ * Labelled object of the map's objects layer
+ */
+export interface MapObject {
+    id?: string;
+    x?: number;
+    y?: number;
+}
+
+/**
+ * Map directory with its objects and configuration
+ */
+export interface GameMap {
+    /** Unique identifier for tracking (generated backend) */
+    id: string;
+    /** Map directory name (e.g., 'map1') */
+    key: string;
+    /** Display name from the map configuration, else the directory name */
+    name: string;
+    /** Path servers report as map_id (e.g., 'media/packages/vanilla/maps/map1') */
+    mapId: string;
+    bases: MapObject[];
+    spawnPoints: MapObject[];
+    /** Map size from the objects layer */
+    width?: number;
+    height?: number;
+    /** Faction files listed in the map configuration */
+    factions: string[];
+    /** Absolute path of the overview image, if the map ships one */
+    overviewImage?: string;
+    /** Absolute path of the configuration file, if any */
+    configFile?: string;
+    /** Map directory relative to packages directory (e.g., 'vanilla/maps/map1') */
+    filePath: string;
+    /** Absolute path of the map's objects.svg */
+    sourceFile: string;
+    /** Directory path where this map was scanned from */
+    sourceDirectory: string;
+    /** Package name (vanilla or mod) */
+    packageName: string;
+    /** Effective scans only: winning package and the definitions it shadows */
+    provenance?: Provenance;
+}
+
+/**
+ * Result from map scanning
+ */
+export interface MapScanResult {
+    /** All scanned maps */
+    maps: GameMap[];
+    /** Errors encountered during scan */
+    errors: ScanDiagnostic[];
+    /** Map directories with the same name in several packages */
+    duplicateKeys: DuplicateKey[];
+    /** Scan duration in milliseconds */
+    scanTime: number;
+    /** Cache hits and reparsed files for this scan */
+    cacheStats: CacheStats;
+}
@@ -0,0 +1,74 @@
+/**
+ * Projectile data models for RWR .projectile files
+ */
+
+import type {
+    CacheStats,
+    DuplicateKey,
+    Provenance,
+    ScanDiagnostic,
+} from './weapons.models';
+
+/**
+ * Projectile definition from a parsed .projectile file (template chain resolved)
+ */
+export interface Projectile {
+    /** Unique identifier for tracking (generated backend) */
+    id: string;
+    /** Projectile key (key attribute, else file name) */
+    key?: string;
+    /** Display name */
+    name: string;
+    /** Projectile class from the root element (grenade, bullet, rocket, ...) */
+    class?: string;
+    /** Result class from <result class="..."/> (blast, hit, spawn, ...) */
+    resultClass?: string;
+    /** Blast radius from <result radius="..."/> */
+    blastRadius?: number;
+    /** Damage from <result damage="..."/> */
+    damage?: number;
+    /** Kill probability (0.0 - 1.0) */
+    killProbability?: number;
+    /** Seconds after which kill probability starts to decay */
+    killDecayStartTime?: number;
+    /** Seconds after which kill probability reaches zero */
+    killDecayEndTime?: number;
+    /** Seconds until the projectile triggers or expires */
+    timeToLive?: number;
+    /** Trigger class from <trigger class="..."/> (time, impact, ...) */
+    triggerClass?: string;
+    /** Icon filename from <hud_icon filename="..."/> */
+    hudIcon?: string;
+    /** Weight/encumbrance value */
+    encumbrance?: number;
+    /** In-game cost */
+    price?: number;
+    /** File path relative to packages directory (e.g., 'vanilla/weapons/hand_grenade.projectile') */
+    filePath: string;
+    /** Original absolute XML file path */
+    sourceFile: string;
+    /** Directory path where this projectile was scanned from */
+    sourceDirectory: string;
+    /** Package name (vanilla or mod) */
+    packageName: string;
+    /** Effective scans only: winning package and the definitions it shadows */
+    provenance?: Provenance;
+    /** Malformed XML: recovered from a repaired copy, fields may be missing */
+    partiallyParsed?: boolean;
+}
+
+/**
+ * Result from projectile scanning
+ */
+export interface ProjectileScanResult {
+    /** All scanned projectiles */
+    projectiles: Projectile[];
+    /** Errors encountered during scan */
+    errors: ScanDiagnostic[];
+    /** Duplicate projectile keys detected */
+    duplicateKeys: DuplicateKey[];
+    /** Scan duration in milliseconds */
+    scanTime: number;
+    /** Cache hits and reparsed files for this scan */
+    cacheStats: CacheStats;
+}
@@ -0,0 +1,51 @@
+/**
+ * Cross-reference index returned by scan_references / find_references
+ */
+
+import type { CacheStats, ScanDiagnostic } from './weapons.models';
+
+export type ReferenceKind =
+    | 'template'
+    | 'projectile'
+    | 'chain_link'
+    | 'transform_on_consume'
+    | 'resource_list'
+    | 'faction_resource'
+    | 'resources_file'
+    | 'character'
+    | 'skin'
+    | 'call_spawn'
+    | 'mounted_weapon'
+    | 'hud_icon';
+
+/**
+ * One attribute pointing at another file or key
+ */
+export interface Reference {
+    id: string;
+    /** File name of the target, the index key */
+    key: string;
+    /** Attribute value as written */
+    target: string;
+    kind: ReferenceKind;
+    element: string;
+    attribute: string;
+    /** 1-based position of the referencing element */
+    line: number;
+    column: number;
+    filePath: string;
+    sourceFile: string;
+    sourceDirectory: string;
+    packageName: string;
+}
+
+export interface ReferenceScanResult {
+    /** Target key → every reference to it */
+    references: Record<string, Reference[]>;
+    /** Files that could not be read completely */
+    errors: ScanDiagnostic[];
+    /** Scan duration in milliseconds */
+    scanTime: number;
+    /** Cache hits and reparsed files for this scan */
+    cacheStats: CacheStats;
+}
@@ -0,0 +1,57 @@
+/**
+ * Attribute schema returned by infer_attribute_schema / check_attributes
+ */
+
+import type { ScanDiagnostic } from './weapons.models';
+
+export type ValueType = 'integer' | 'number' | 'text';
+
+/**
+ * What vanilla does with one attribute path
+ */
+export interface AttributeSpec {
+    /** Bag path without sibling positions (e.g. stance.accuracy) */
+    path: string;
+    valueType: ValueType;
+    /** Numeric types only */
+    min?: number;
+    max?: number;
+    /** Number of vanilla records setting it */
+    occurrences: number;
+}
+
+/**
+ * Known attributes of one file type
+ */
+export interface KindSchema {
+    /** File extension (weapon, carry_item, ...) */
+    kind: string;
+    /** Number of vanilla records the schema was learned from */
+    records: number;
+    attributes: AttributeSpec[];
+}
+
+export type AttributeIssueKind =
+    | 'unknown'
+    | 'mistyped'
+    | 'out_of_range'
+    | 'wrong_type';
+
+export interface AttributeIssue {
+    kind: AttributeIssueKind;
+    severity: ScanDiagnostic['severity'];
+    /** Absolute path of the mod file */
+    file: string;
+    /** Bag path as written, with sibling positions */
+    path: string;
+    value: string;
+    /** Known path the attribute was probably meant to be */
+    suggestion?: string;
+    message: string;
+}
+
+export interface AttributeCheckResult {
+    schemas: KindSchema[];
+    /** Sorted by file, then path */
+    issues: AttributeIssue[];
+}
@@ -0,0 +1,89 @@
+/**
+ * Vehicle data models for RWR .vehicle files
+ */
+
+import type {
+    CacheStats,
+    DuplicateKey,
+    Provenance,
+    ScanDiagnostic,
+} from './weapons.models';
+
+/**
+ * Seat (<character_slot>) of a vehicle
+ */
+export interface VehicleSeat {
+    /** Slot type (driver, gunner, passenger, ...) */
+    type: string;
+    /** Turret operated from this seat, if any */
+    turretIndex?: number;
+}
+
+/**
+ * Turret and the weapons mounted on it
+ */
+export interface VehicleTurret {
+    name?: string;
+    /** Weapon keys (e.g., 'm2_browning.weapon') */
+    weapons: string[];
+}
+
+/**
+ * Vehicle definition from a parsed .vehicle file (template chain resolved)
+ */
+export interface Vehicle {
+    /** Unique identifier for tracking (generated backend) */
+    id: string;
+    /** Vehicle key (key attribute, else file name) */
+    key?: string;
+    /** Display name */
+    name: string;
+    /** Hit points from <physics max_health="..."/> */
+    maxHealth?: number;
+    /** Mass from <physics mass="..."/> */
+    mass?: number;
+    /** Top speed from <control max_speed="..."/> */
+    maxSpeed?: number;
+    /** Top reverse speed */
+    maxReverseSpeed?: number;
+    /** Acceleration */
+    acceleration?: number;
+    /** Seats in declaration order */
+    seats: VehicleSeat[];
+    /** Turrets in declaration order */
+    turrets: VehicleTurret[];
+    /** Keys of every mounted weapon, without repeats */
+    mountedWeapons: string[];
+    /** Icon filename from <hud_icon filename="..."/> */
+    hudIcon?: string;
+    /** Absolute icon path in the sibling textures/ folder, if the file exists */
+    hudIconPath?: string;
+    /** File path relative to packages directory (e.g., 'vanilla/vehicles/jeep.vehicle') */
+    filePath: string;
+    /** Original absolute XML file path */
+    sourceFile: string;
+    /** Directory path where this vehicle was scanned from */
+    sourceDirectory: string;
+    /** Package name (vanilla or mod) */
+    packageName: string;
+    /** Effective scans only: winning package and the definitions it shadows */
+    provenance?: Provenance;
+    /** Malformed XML: recovered from a repaired copy, fields may be missing */
+    partiallyParsed?: boolean;
+}
+
+/**
+ * Result from vehicle scanning
+ */
+export interface VehicleScanResult {
+    /** All scanned vehicles */
+    vehicles: Vehicle[];
+    /** Errors encountered during scan */
+    errors: ScanDiagnostic[];
+    /** Duplicate vehicle keys detected */
+    duplicateKeys: DuplicateKey[];
+    /** Scan duration in milliseconds */
+    scanTime: number;
+    /** Cache hits and reparsed files for this scan */
+    cacheStats: CacheStats;
+}
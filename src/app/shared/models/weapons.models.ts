@@ -3,6 +3,8 @@
  * Feature: 001-weapons-directory-scanner
  */
 
+import type { Projectile } from './projectiles.models';
+
 /**
  * Stance accuracy values for different positions
  */
@@ -13,6 +15,55 @@ export interface StanceAccuracy {
     accuracy: number;
 }
 
+/**
+ * Kill probability of a hit after `time` seconds of projectile flight
+ */
+export interface FalloffPoint {
+    time: number;
+    /** time * projectileSpeed, when the speed is known */
+    distance?: number;
+    killProbability: number;
+}
+
+/**
+ * Accuracy in one stance, scaled by the weapon's accuracyFactor
+ */
+export interface StanceMetrics {
+    stance: string;
+    effectiveAccuracy: number;
+    /** Expected shots fired until a kill, counting misses */
+    shotsToKill?: number;
+}
+
+/**
+ * Performance figures derived from a weapon's fields
+ */
+export interface WeaponMetrics {
+    /** 60 / retriggerTime */
+    roundsPerMinute?: number;
+    /** Expected kills per second while the trigger is held, ignoring reloads */
+    burstDps?: number;
+    /** Expected kills per second over full magazine + reload cycles */
+    sustainedDps?: number;
+    /** Reload duration sustainedDps assumes, in seconds (reload animations aren't parsed) */
+    reloadTime: number;
+    /** Expected number of trigger pulls until a kill, every projectile hitting */
+    shotsToKill?: number;
+    /** Expected seconds from the first to the killing shot, all shots hitting */
+    timeToKill?: number;
+    /** Kill probability over projectile flight time; empty without kill decay times */
+    damageFalloff: FalloffPoint[];
+    stances: StanceMetrics[];
+}
+
+/**
+ * Attribute modifier from <modifier class="..." value="..."/>
+ */
+export interface WeaponModifier {
+    class: string;
+    value?: number;
+}
+
 /**
  * Complete weapon definition from parsed .weapon XML file
  */
@@ -45,20 +96,38 @@ export interface Weapon {
     projectileSpeed?: number;
     /** Barrel length offset */
     barrelOffset?: number;
+    /** Projectiles fired per trigger pull (e.g. shotgun pellets) */
+    projectilesPerShot?: number;
+    /** Accuracy multiplier from <specification accuracy_factor="..."/> */
+    accuracyFactor?: number;
+    /** Accuracy penalty added per shot while firing continuously */
+    sustainedFireGrowStep?: number;
+    /** Rate at which the sustained fire penalty recovers */
+    sustainedFireDiminishRate?: number;
     /** Weight/encumbrance value */
     encumbrance?: number;
     /** In-game cost */
     price?: number;
+    /** Spawn frequency from <commonness value="..."/> */
+    commonness?: number;
     /** Is weapon silenced */
     suppressed: boolean;
-    /** Available in loadout */
+    /** Available in loadout (true when not specified) */
     canRespawnWith: boolean;
-    /** Available for purchase */
+    /** Available for purchase (true when not specified) */
     inStock: boolean;
+    /** Attribute modifiers applied while the weapon is carried */
+    modifiers: WeaponModifier[];
     /** Related weapon modes (next_in_chain) */
     chainVariants: string[];
     /** Accuracy values per stance */
     stanceAccuracies: StanceAccuracy[];
+    /** Projectile file the weapon fires, relative to packages directory (matches Projectile.filePath) */
+    projectileFile?: string;
+    /** Inline <projectile> merged over the projectile file it references */
+    projectile?: Projectile;
+    /** Figures derived from the fields above (fire rate, DPS, time to kill, ...) */
+    metrics: WeaponMetrics;
     /** File path relative to packages directory (e.g., 'vanilla/weapons/ak47.weapon') */
     filePath: string;
     /** Original absolute XML file path */
@@ -69,18 +138,99 @@ export interface Weapon {
     sourceDirectory: string;
     /** Error message if template resolution failed (optional) */
     templateError?: string;
+    /** Malformed XML: recovered from a repaired copy, fields may be missing */
+    partiallyParsed?: boolean;
+    /** Effective scans only: winning package and the definitions it shadows */
+    provenance?: Provenance;
+    /** Whether the package's all_weapons.xml lists this file */
+    resourceStatus?: ResourceStatus;
+    /** Every XML attribute after template merging (e.g. 'specification.stab_range'); only when the scan asked for it */
+    attributes?: Record<string, string>;
 }
 
 /**
- * Error encountered during weapon scanning
+ * Whether a package resource list (all_weapons.xml / all_carry_items.xml) registers a file
+ */
+export type ResourceStatus = 'referenced' | 'orphaned';
+
+/**
+ * Stable diagnostic codes reported by the scanners
  */
-export interface ScanError {
-    /** File that caused error */
+export type DiagnosticCode =
+    | 'read_failed'
+    | 'xml_syntax'
+    | 'xml_structure'
+    | 'template_not_found'
+    | 'template_cycle'
+    | 'template_depth_exceeded'
+    | 'template_selection'
+    | 'reference_not_found';
+
+/**
+ * Problem found while scanning weapons/items
+ */
+export interface ScanDiagnostic {
+    /** Stable error code */
+    code: DiagnosticCode;
+    /** error: file skipped; warning: record produced but may be incomplete */
+    severity: 'error' | 'warning' | 'info';
+    /** Absolute path of the file the diagnostic points at */
     file: string;
-    /** Error message */
-    error: string;
-    /** Error classification */
-    severity: 'error' | 'warning';
+    /** 1-based line, when known */
+    line?: number;
+    /** 1-based column, when known */
+    column?: number;
+    /** Human readable message */
+    message: string;
+    /** Scanned file followed by the templates resolved from it */
+    templateChain?: string[];
+}
+
+/**
+ * Incremental scan cache statistics
+ */
+export interface CacheStats {
+    /** Files whose cached records were reused */
+    hits: number;
+    /** Files parsed again because they or one of their templates changed */
+    reparsed: number;
+}
+
+/**
+ * One definition of a weapon/item key
+ */
+export interface KeyDefinition {
+    key: string;
+    /** Id of the scanned record holding this definition */
+    id: string;
+    packageName: string;
+    /** Path relative to the packages directory */
+    filePath: string;
+    sourceFile: string;
+}
+
+/**
+ * Where an effective record was taken from.
+ * Assumes vanilla loads first and other packages load after it in name order (last wins);
+ * the game's actual order depends on the packages a campaign or server enables.
+ */
+export interface Provenance {
+    packageName: string;
+    filePath: string;
+    /** Definitions of the same key hidden by this one, in load order */
+    shadowed: KeyDefinition[];
+}
+
+/**
+ * Key defined more than once across packages or files
+ */
+export interface DuplicateKey {
+    key: string;
+    /** Every definition in load order; the last one is used by the game */
+    definitions: KeyDefinition[];
+    /** filePath of the definition the game uses */
+    effectiveFile: string;
+    effectivePackage: string;
 }
 
 /**
@@ -90,11 +240,13 @@ export interface WeaponScanResult {
     /** All scanned weapons */
     weapons: Weapon[];
     /** Errors encountered during scan */
-    errors: ScanError[];
+    errors: ScanDiagnostic[];
     /** Duplicate weapon keys detected */
-    duplicateKeys: string[];
+    duplicateKeys: DuplicateKey[];
     /** Scan duration in milliseconds */
     scanTime: number;
+    /** Cache hits and reparsed files for this scan */
+    cacheStats: CacheStats;
 }
 
 /**